@@ -1,8 +1,19 @@
+use crate::error::OraError;
+use crate::front_matter::{FrontMatter, MetaValue};
+use crate::history::{History, Revision, RevisionSource};
+use crate::links::{Link, extract_links};
+use crate::shelf::config::CONFIG_FILE;
+use crate::shelf::storage::ShelfError;
+use crate::shelf::trash::{Trash, TrashItem};
+use crate::tags::extract_tags;
+use crate::watcher::service::WatcherService;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tempfile::NamedTempFile;
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Debug, Error)]
 pub enum NoteError {
@@ -14,17 +25,109 @@ pub enum NoteError {
     #[error("no changes to file")]
     NoChanges,
 
+    /// Returned when no note matches the requested title or ID.
+    #[error("note not found: {0}")]
+    NotFound(String),
+
+    /// Returned when a note is saved, but its file changed on disk since
+    /// it was read. Carries both versions for resolution.
+    #[error("note changed on disk: {}", .0.path.display())]
+    Conflict(Box<NoteConflict>),
+
     /// Wraps any underlying I/O error (read/write/rename/delete).
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+/// Details of a [`NoteError::Conflict`]: the two versions of a note that
+/// was changed on disk while it was being edited in memory.
+///
+/// To resolve the conflict, either overwrite the file with
+/// [`LocalNote::save_force`], or [`reload`](LocalNote::reload) the note and
+/// save the merged content on top of the disk version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteConflict {
+    /// Path of the note file.
+    pub path: PathBuf,
+
+    /// Content of the note in memory, which was not written.
+    pub ours: String,
+
+    /// Current content of the file, or `None` if it was deleted.
+    pub theirs: Option<String>,
+}
+
+/// The state of a note file on disk when it was last read or written.
+///
+/// Used by [`LocalNote::save`] to detect changes made by other programs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Modification time of the file, if the platform reports it.
+    pub modified: Option<SystemTime>,
+
+    /// Size of the file in bytes.
+    pub len: u64,
+
+    /// Hash of the file content, as used by the index.
+    pub hash: String,
+}
+
+impl Fingerprint {
+    /// Reads the fingerprint of the file at `path`.
+    ///
+    /// # Returns
+    /// The fingerprint, or `None` if the file does not exist
+    ///
+    /// # Errors
+    /// - [`NoteError::Io`] if the file cannot be read
+    pub fn read(path: &Path) -> Result<Option<Fingerprint>, NoteError> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let metadata = fs::metadata(path)?;
+        Ok(Some(Fingerprint {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: content_hash(&data),
+        }))
+    }
+
+    /// Returns `true` if the file at `path` still has this fingerprint.
+    ///
+    /// If the modification time and size are unchanged the file is assumed
+    /// unchanged without reading it; otherwise its content is hashed, so a
+    /// file that was only touched still matches.
+    fn matches(&self, path: &Path) -> Result<bool, NoteError> {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        if self.modified.is_some()
+            && metadata.modified().ok() == self.modified
+            && metadata.len() == self.len
+        {
+            return Ok(true);
+        }
+        Ok(Self::read(path)?.is_some_and(|current| current.hash == self.hash))
+    }
+}
+
 /// A note stored locally on disk as a Markdown file (`.md`).
-#[derive(Clone)]
+///
+/// A note read from or written to disk remembers the file's
+/// [`Fingerprint`]. [`save`](LocalNote::save) and
+/// [`save_as`](LocalNote::save_as) fail with [`NoteError::Conflict`]
+/// instead of overwriting changes made to the file by another program in
+/// the meantime.
+#[derive(Debug, Clone)]
 pub struct LocalNote {
     pub title: String,
     pub content: String,
     pub path: PathBuf,
+    fingerprint: Option<Fingerprint>,
 }
 
 impl LocalNote {
@@ -47,15 +150,17 @@ impl LocalNote {
             title.trim().to_string()
         };
 
-        let filename = create_unique_filename(&note_title, &path);
+        let filename = create_unique_filename(&note_title, path);
         let note_path = path.join(filename);
 
         fs::write(&note_path, content)?;
+        let fingerprint = Fingerprint::read(&note_path)?;
 
         Ok(LocalNote {
             title: note_title,
             content: content.to_string(),
             path: note_path,
+            fingerprint,
         })
     }
 
@@ -64,50 +169,285 @@ impl LocalNote {
     /// Useful when the file might have been modified externally (e.g. in Vim, Obsidian, etc.).
     ///
     /// Does **not** mutate `self`; instead returns a refreshed instance holding
-    /// the current file contents. The title is extracted from the filename.
+    /// the current file contents and fingerprint. The title is extracted from
+    /// the filename.
     ///
     /// # Errors
     /// - [`NoteError::Io`] if the file cannot be read
     pub fn reload(&self) -> Result<LocalNote, NoteError> {
-        let data = fs::read_to_string(&self.path)?;
-        let note_title = extract_title_from_path(&self.path);
-
-        Ok(LocalNote {
-            title: note_title,
-            content: data,
-            path: self.path.clone(),
-        })
+        LocalNote::open(&self.path)
     }
 
     /// Returns an in-memory copy of this note with new content.
     ///
     /// `with_content` does not persist to disk; it only creates a variant of
-    /// this `LocalNote` with `content` replaced. Useful for version history
-    /// or sync engines.
+    /// this `LocalNote` with `content` replaced. Useful for sync engines;
+    /// saved versions of a note are kept by [`crate::history`].
+    ///
+    /// The copy keeps the fingerprint of this note, so saving it still
+    /// detects changes made to the file since it was read.
     pub fn with_content(&self, new_content: &str) -> LocalNote {
         LocalNote {
             title: self.title.clone(),
             content: new_content.to_string(),
             path: self.path.clone(),
+            fingerprint: self.fingerprint.clone(),
         }
     }
 
+    /// Returns the fingerprint of the file as it was last read or written
+    /// by this note, or `None` if the note was never on disk.
+    pub fn fingerprint(&self) -> Option<&Fingerprint> {
+        self.fingerprint.as_ref()
+    }
+
+    /// Returns `true` if the file changed on disk since this note read or
+    /// wrote it, including when it was deleted.
+    ///
+    /// # Errors
+    /// - [`NoteError::Io`] if the file cannot be read
+    pub fn is_stale(&self) -> Result<bool, NoteError> {
+        match &self.fingerprint {
+            Some(fingerprint) => Ok(!fingerprint.matches(&self.path)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Parses the YAML front matter at the top of the note.
+    ///
+    /// Returns an empty [`FrontMatter`] if the note has no `---` header.
+    /// The content itself is not modified.
+    pub fn front_matter(&self) -> FrontMatter {
+        FrontMatter::parse(&self.content)
+    }
+
+    /// Returns the note content without its front matter header.
+    pub fn body(&self) -> &str {
+        FrontMatter::strip(&self.content)
+    }
+
+    /// Returns the stable ID stored in the note's front matter `id:` key.
+    ///
+    /// Returns `None` if the note has no ID in its header. Such notes still
+    /// get an ID from the index; see [`crate::watcher::index::IndexedNote::id`].
+    pub fn id(&self) -> Option<String> {
+        self.front_matter()
+            .get("id")
+            .and_then(MetaValue::as_text)
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Returns an in-memory copy of this note that carries a stable ID.
+    ///
+    /// If the note already has an `id:` in its front matter it is returned
+    /// unchanged; otherwise a random UUID is added to the header. Does not
+    /// persist to disk.
+    pub fn with_stable_id(&self) -> LocalNote {
+        match self.id() {
+            Some(_) => self.clone(),
+            None => self.with_meta("id", Uuid::new_v4().to_string()),
+        }
+    }
+
+    /// Returns the normalized tags of this note.
+    ///
+    /// Combines the front matter `tags:` key with inline `#hashtags` from
+    /// the body. See [`crate::tags`] for the exact rules.
+    pub fn tags(&self) -> Vec<String> {
+        extract_tags(&self.content)
+    }
+
+    /// Returns the wikilinks and Markdown links to other notes in this note.
+    ///
+    /// See [`crate::links`] for the recognized syntax.
+    pub fn links(&self) -> Vec<Link> {
+        extract_links(&self.content)
+    }
+
+    /// Returns an in-memory copy of this note with a new body.
+    ///
+    /// The front matter header is kept byte-for-byte as it is, only the
+    /// text after it is replaced. Does not persist to disk.
+    pub fn with_body(&self, new_body: &str) -> LocalNote {
+        let front_matter = self.front_matter();
+        self.with_content(&format!("{}{}", front_matter.render(), new_body))
+    }
+
+    /// Returns an in-memory copy of this note with a new front matter header.
+    ///
+    /// The body is kept as it is. Passing an empty [`FrontMatter`] removes
+    /// the header. Does not persist to disk.
+    pub fn with_front_matter(&self, front_matter: &FrontMatter) -> LocalNote {
+        self.with_content(&format!("{}{}", front_matter.render(), self.body()))
+    }
+
+    /// Returns an in-memory copy of this note with one metadata key set.
+    ///
+    /// Only the lines belonging to `key` are rewritten; the rest of the
+    /// header and the body stay byte-for-byte identical. A header is
+    /// created if the note has none. Does not persist to disk.
+    pub fn with_meta(&self, key: &str, value: impl Into<MetaValue>) -> LocalNote {
+        let mut front_matter = self.front_matter();
+        front_matter.set(key, value);
+        self.with_front_matter(&front_matter)
+    }
+
     /// Saves the note with a new title, handling both title change and content changes atomically.
     ///
     /// This method will:
     /// 1. Create a new filename based on the title
     /// 2. Atomically write the current content to the new path
     /// 3. Remove the old file if the path changed
-    /// 4. Update the note's title and path in memory
+    /// 4. Update the note's title, path and fingerprint in memory
     ///
     /// Empty titles are replaced with "Untitled". If a file with the same
     /// name exists, a number suffix is added (e.g., "My Note 1.md").
     ///
     /// # Errors
+    /// - [`NoteError::Conflict`] if the old file changed on disk since the
+    ///   note was read; nothing is written
     /// - [`NoteError::InvalidPath`] if the parent directory cannot be determined
     /// - [`NoteError::NoChanges`] if there are no changes to the file
     /// - [`NoteError::Io`] if write or rename fails
     pub fn save_as(&mut self, title: &str) -> Result<(), NoteError> {
+        self.write_as(title, false)
+    }
+
+    /// Saves the note with a new title like [`save_as`](LocalNote::save_as),
+    /// even if the old file changed on disk since the note was read.
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the parent directory cannot be determined
+    /// - [`NoteError::NoChanges`] if there are no changes to the file
+    /// - [`NoteError::Io`] if write or rename fails
+    pub fn save_as_force(&mut self, title: &str) -> Result<(), NoteError> {
+        self.write_as(title, true)
+    }
+
+    /// Saves the current note to disk at `self.path`.
+    ///
+    /// Uses an atomic write (tempfile + rename) to avoid corruption.
+    /// Overwrites the previous contents of the file, unless another program
+    /// changed it since the note was read. The note's fingerprint is
+    /// updated to the written file.
+    ///
+    /// A running watcher sees the write as an external change; use
+    /// [`save_with`](LocalNote::save_with) to apply it to its index directly.
+    ///
+    /// Nothing is recorded in the shelf's revision history; call
+    /// [`record_revision`](LocalNote::record_revision) after the save to
+    /// keep one.
+    ///
+    /// # Errors
+    /// - [`NoteError::Conflict`] if the file changed on disk since the note
+    ///   was read; nothing is written
+    /// - [`NoteError::InvalidPath`] if the parent directory cannot be determined
+    /// - [`NoteError::NoChanges`] if there are no changes to the file
+    /// - [`NoteError::Io`] if write or rename fails
+    pub fn save(&mut self) -> Result<(), NoteError> {
+        self.write(false)
+    }
+
+    /// Saves the current note like [`save`](LocalNote::save), overwriting
+    /// the file even if it changed on disk since the note was read.
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the parent directory cannot be determined
+    /// - [`NoteError::NoChanges`] if there are no changes to the file
+    /// - [`NoteError::Io`] if write or rename fails
+    pub fn save_force(&mut self) -> Result<(), NoteError> {
+        self.write(true)
+    }
+
+    /// Saves the note like [`save`](LocalNote::save) and applies the change
+    /// to the index of `watcher` right away.
+    ///
+    /// The watcher's subscribers get a single event for the save, and the
+    /// file system events it causes are skipped as echoes; see
+    /// [`crate::watcher::handler`].
+    ///
+    /// # Errors
+    /// - [`NoteError`] as for [`save`](LocalNote::save)
+    /// - [`OraError`] if indexing the note fails
+    pub fn save_with(&mut self, watcher: &WatcherService) -> Result<(), OraError> {
+        self.save()?;
+        watcher.handler().apply_save(&self.path, self)
+    }
+
+    /// Saves the note with a new title like [`save_as`](LocalNote::save_as)
+    /// and applies the change to the index of `watcher` right away, keeping
+    /// the note's stable ID.
+    ///
+    /// # Errors
+    /// - [`NoteError`] as for [`save_as`](LocalNote::save_as)
+    /// - [`OraError`] if indexing the note fails
+    pub fn save_as_with(&mut self, title: &str, watcher: &WatcherService) -> Result<(), OraError> {
+        let from = self.path.clone();
+        self.save_as(title)?;
+        watcher.handler().apply_save(&from, self)
+    }
+
+    /// Records the note's content as a library revision in `history`.
+    ///
+    /// Call this after a successful save: the note is already on disk, so
+    /// a failure here only means the revision is missing. `from` is the
+    /// path the note was saved under before, so revisions follow a
+    /// [`save_as`](LocalNote::save_as); pass the note's own path after a
+    /// plain save.
+    ///
+    /// # Returns
+    /// The new revision, or `None` if the content equals the latest one
+    ///
+    /// # Errors
+    /// Returns `OraError` if the history database operation fails
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use ora_core::domain::LocalNote;
+    /// use ora_core::history::History;
+    /// use std::path::Path;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let history = History::open(Path::new("/path/to/shelf"), None)?;
+    /// let mut note = LocalNote::open(Path::new("/path/to/shelf/Plan.md"))?;
+    /// let from = note.path.clone();
+    /// note.save_as("Roadmap")?;
+    /// note.record_revision(&history, &from)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn record_revision(
+        &self,
+        history: &History,
+        from: &Path,
+    ) -> Result<Option<Revision>, OraError> {
+        if from != self.path {
+            history.rename(from, &self.path)?;
+        }
+        history.record(&self.path, &self.content, RevisionSource::Library)
+    }
+
+    /// Writes the note to `self.path`, checking for conflicts unless `force`.
+    fn write(&mut self, force: bool) -> Result<(), NoteError> {
+        if self.path.exists()
+            && let Ok(existing_content) = fs::read_to_string(&self.path)
+            && existing_content == self.content
+        {
+            return Err(NoteError::NoChanges);
+        }
+        if !force {
+            self.check_conflict()?;
+        }
+        write_atomic(&self.path, self.content.as_bytes())?;
+        self.fingerprint = Fingerprint::read(&self.path)?;
+        Ok(())
+    }
+
+    /// Writes the note under a new title, checking for conflicts unless
+    /// `force`.
+    fn write_as(&mut self, title: &str, force: bool) -> Result<(), NoteError> {
         let new_title = if title.trim().is_empty() {
             "Untitled".to_string()
         } else {
@@ -115,58 +455,88 @@ impl LocalNote {
         };
 
         if new_title == self.title {
-            return self.save();
+            return self.write(force);
         }
 
         let base_dir = self.path.parent().ok_or(NoteError::InvalidPath)?;
         let new_filename = create_unique_filename(&new_title, base_dir);
         let new_path = base_dir.join(new_filename);
 
-        if self.path.exists() {
-            if let Ok(existing_content) = fs::read_to_string(&self.path) {
-                if existing_content == self.content && new_path == self.path {
-                    return Err(NoteError::NoChanges);
-                }
-            }
+        if self.path.exists()
+            && let Ok(existing_content) = fs::read_to_string(&self.path)
+            && existing_content == self.content
+            && new_path == self.path
+        {
+            return Err(NoteError::NoChanges);
+        }
+        if !force {
+            self.check_conflict()?;
         }
 
         write_atomic(&new_path, self.content.as_bytes())?;
 
         if new_path != self.path {
-            fs::remove_file(&self.path)?;
+            match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
         }
 
+        self.fingerprint = Fingerprint::read(&new_path)?;
         self.title = new_title;
         self.path = new_path;
 
         Ok(())
     }
 
-    /// Saves the current note to disk at `self.path`.
+    /// Fails with [`NoteError::Conflict`] if the file changed on disk since
+    /// this note read or wrote it.
+    fn check_conflict(&self) -> Result<(), NoteError> {
+        if !self.is_stale()? {
+            return Ok(());
+        }
+        let theirs = match fs::read_to_string(&self.path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        Err(NoteError::Conflict(Box::new(NoteConflict {
+            path: self.path.clone(),
+            ours: self.content.clone(),
+            theirs,
+        })))
+    }
+
+    /// Moves this note into the trash of its shelf.
     ///
-    /// Uses an atomic write (tempfile + rename) to avoid corruption.
-    /// Overwrites the previous contents of the file.
+    /// The shelf is the nearest directory above the note with a
+    /// `.shelf.toml` or `.shelf.db`; a note outside any shelf goes to the
+    /// trash of its own directory. See [`crate::shelf::trash`] for listing
+    /// and restoring deleted notes.
+    ///
+    /// # Returns
+    /// The trash entry holding the note
     ///
     /// # Errors
-    /// - [`NoteError::InvalidPath`] if the parent directory cannot be determined
-    /// - [`NoteError::NoChanges`] if there are no changes to the file
-    /// - [`NoteError::Io`] if write or rename fails
-    pub fn save(&self) -> Result<(), NoteError> {
-        if self.path.exists() {
-            if let Ok(existing_content) = fs::read_to_string(&self.path) {
-                if existing_content == self.content {
-                    return Err(NoteError::NoChanges);
-                }
-            }
-        }
-        write_atomic(&self.path, self.content.as_bytes())
+    /// - [`NoteError::InvalidPath`] if the note has no parent directory
+    /// - [`NoteError::Io`] if the file cannot be moved
+    pub fn delete(&self) -> Result<TrashItem, NoteError> {
+        let dir = self.path.parent().ok_or(NoteError::InvalidPath)?;
+        let root = dir
+            .ancestors()
+            .find(|d| d.join(CONFIG_FILE).exists() || d.join(".shelf.db").exists())
+            .unwrap_or(dir);
+        Trash::new(root).put(&self.path).map_err(|e| match e {
+            ShelfError::Io(e) => NoteError::Io(e),
+            _ => NoteError::InvalidPath,
+        })
     }
 
-    /// Deletes this note from disk.
+    /// Deletes this note from disk permanently, bypassing the trash.
     ///
     /// # Errors
     /// - [`NoteError::Io`] if the file cannot be removed
-    pub fn delete(&self) -> Result<(), NoteError> {
+    pub fn delete_permanently(&self) -> Result<(), NoteError> {
         fs::remove_file(&self.path)?;
         Ok(())
     }
@@ -175,26 +545,47 @@ impl LocalNote {
     ///
     /// Reads the file contents into memory and extracts the title
     /// from the filename (without .md extension), returning a new `LocalNote`.
+    /// The file's [`Fingerprint`] is recorded to detect later changes.
     ///
     /// # Errors
     /// - [`NoteError::Io`] if reading fails
     pub fn open(path: &Path) -> Result<LocalNote, NoteError> {
         let content = fs::read_to_string(path)?;
         let title = extract_title_from_path(path);
+        let metadata = fs::metadata(path)?;
+        let fingerprint = Fingerprint {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: content_hash(content.as_bytes()),
+        };
 
         Ok(LocalNote {
             title,
             content,
             path: path.to_path_buf(),
+            fingerprint: Some(fingerprint),
         })
     }
+
+    /// Creates an in-memory note for `path` without reading the file.
+    ///
+    /// The title is extracted from the filename; content is empty and there
+    /// is no fingerprint. Used to refer to notes that no longer exist.
+    pub(crate) fn at_path(path: &Path) -> LocalNote {
+        LocalNote {
+            title: extract_title_from_path(path),
+            content: String::new(),
+            path: path.to_path_buf(),
+            fingerprint: None,
+        }
+    }
 }
 
 /// Checks for a unique Markdown filename in `dir` based on the title.
 ///
 /// If `title.md` exists, tries `title 1.md`, `title 2.md`, ... until a free
 /// path is found. Returns the first non‑existing candidate `PathBuf`.
-fn create_unique_filename(title: &str, dir: &Path) -> PathBuf {
+pub(crate) fn create_unique_filename(title: &str, dir: &Path) -> PathBuf {
     let mut count = 0;
     loop {
         let candidate = if count == 0 {
@@ -222,6 +613,28 @@ fn extract_title_from_path(path: &Path) -> String {
         .to_string()
 }
 
+/// Computes a fingerprint of note content for change detection.
+///
+/// Uses 64-bit FNV-1a, which is stable across platforms and releases. It is
+/// not cryptographic and only meant to tell whether a file has changed.
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// Returns `dir` as a string ending in the platform's path separator, for
+/// matching the stored paths of files below it by prefix.
+///
+/// A plain string prefix would also match siblings such as `dir 2/`, and
+/// a hard-coded `/` would miss paths stored with `\` separators.
+pub(crate) fn dir_prefix(dir: &Path) -> String {
+    dir.join("").display().to_string()
+}
+
 /// Internal helper to atomically write note content to disk.
 ///
 /// Writes data to a temporary file in the target directory and renames it
@@ -230,7 +643,7 @@ fn extract_title_from_path(path: &Path) -> String {
 /// # Errors
 /// - [`NoteError::InvalidPath`] if the parent directory cannot be determined
 /// - [`NoteError::Io`] if writing or persisting the tempfile fails
-fn write_atomic(path: &Path, data: &[u8]) -> Result<(), NoteError> {
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> Result<(), NoteError> {
     let dir = path.parent().ok_or(NoteError::InvalidPath)?;
     let mut tmp = NamedTempFile::new_in(dir)?;
     tmp.write_all(data)?;
@@ -12,6 +12,7 @@
 //! - **Note errors**: Issues with note operations (create, read, update, delete)
 //! - **Shelf errors**: Problems with shelf management and storage
 //! - **Database errors**: SQLite connection and query failures
+//! - **Query errors**: Search queries that cannot be parsed
 //! - **I/O errors**: File system operations
 //! - **Watcher errors**: File system monitoring issues
 //! - **Generic errors**: Catch-all for other error conditions
@@ -70,6 +71,21 @@ pub enum OraError {
     #[error("Database error: {0}")]
     Db(#[from] rusqlite::Error),
 
+    /// A user search query could not be parsed.
+    ///
+    /// Automatically converted from
+    /// [`QueryParseError`](crate::search::parser::QueryParseError), which
+    /// holds the position of the problem in the query.
+    #[error("invalid search query: {0}")]
+    Query(#[from] crate::search::parser::QueryParseError),
+
+    /// The search index was written by a newer version of the library.
+    ///
+    /// The database is left untouched; open it with that version or
+    /// delete `.shelf.db` to rebuild it from the notes on disk.
+    #[error("index schema version {found} is newer than supported version {supported}")]
+    SchemaTooNew { found: i32, supported: i32 },
+
     /// Database connection failures.
     ///
     /// Used for connection-specific errors that don't fit into the standard
@@ -103,6 +119,12 @@ impl From<crate::domain::NoteError> for OraError {
             crate::domain::NoteError::InvalidPath => {
                 OraError::Note(crate::domain::NoteError::InvalidPath)
             }
+            crate::domain::NoteError::NotFound(what) => {
+                OraError::Note(crate::domain::NoteError::NotFound(what))
+            }
+            crate::domain::NoteError::Conflict(conflict) => {
+                OraError::Note(crate::domain::NoteError::Conflict(conflict))
+            }
             crate::domain::NoteError::Io(io_error) => {
                 OraError::Note(crate::domain::NoteError::Io(io_error))
             }
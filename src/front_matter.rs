@@ -0,0 +1,696 @@
+//! YAML front matter parsing and round-tripping.
+//!
+//! Notes may start with a `---` delimited YAML header holding metadata such
+//! as tags, aliases, or creation dates:
+//!
+//! ```text
+//! ---
+//! tags: [rust, notes]
+//! aliases:
+//!   - Ora
+//! created: 2024-01-01
+//! ---
+//! # Body starts here
+//! ```
+//!
+//! This module parses the commonly used subset of YAML (scalar values,
+//! literal `|` and folded `>` block scalars, flow lists and block lists)
+//! into a typed [`FrontMatter`] map while keeping the original text of
+//! every entry. Rendering a parsed header reproduces it byte-for-byte, and
+//! editing one key only rewrites the lines of that key.
+//!
+//! Anything the parser does not understand (comments, blank lines) is
+//! preserved verbatim. Nested maps are kept as [`MetaValue::Raw`] values
+//! holding their text as written.
+
+use std::fmt;
+
+/// A single metadata value from a front matter header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetaValue {
+    /// A scalar value such as `title: My Note` or `created: 2024-01-01`.
+    Text(String),
+
+    /// A list value, either in flow (`[a, b]`) or block (`- a`) style.
+    List(Vec<String>),
+
+    /// A value the parser does not interpret, such as a nested map, as
+    /// written after the key: `{a: 1}` for a flow map, or the indented
+    /// lines of a block map joined with `\n`.
+    Raw(String),
+}
+
+impl MetaValue {
+    /// Returns the scalar value, or `None` if this is not a scalar.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MetaValue::Text(text) => Some(text),
+            MetaValue::List(_) | MetaValue::Raw(_) => None,
+        }
+    }
+
+    /// Returns the list items, or `None` if this is not a list.
+    pub fn as_list(&self) -> Option<&[String]> {
+        match self {
+            MetaValue::List(items) => Some(items),
+            MetaValue::Text(_) | MetaValue::Raw(_) => None,
+        }
+    }
+
+    /// Returns every value as a flat list of strings.
+    ///
+    /// Scalars yield a single item (or none when empty), lists yield their
+    /// items and raw values yield nothing.
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            MetaValue::Text(text) if text.is_empty() => Vec::new(),
+            MetaValue::Text(text) => vec![text.as_str()],
+            MetaValue::List(items) => items.iter().map(String::as_str).collect(),
+            MetaValue::Raw(_) => Vec::new(),
+        }
+    }
+}
+
+impl From<&str> for MetaValue {
+    fn from(value: &str) -> Self {
+        MetaValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for MetaValue {
+    fn from(value: String) -> Self {
+        MetaValue::Text(value)
+    }
+}
+
+impl From<Vec<String>> for MetaValue {
+    fn from(items: Vec<String>) -> Self {
+        MetaValue::List(items)
+    }
+}
+
+/// One piece of the header: either a parsed `key: value` entry or an
+/// opaque run of text kept exactly as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Field {
+        key: String,
+        value: MetaValue,
+        raw: String,
+    },
+    Verbatim(String),
+}
+
+/// Parsed front matter of a note.
+///
+/// Entries keep their original order and text. [`FrontMatter::render`]
+/// returns the exact header that was parsed unless entries were changed
+/// through [`FrontMatter::set`] or [`FrontMatter::remove`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    /// Opening delimiter line including its line ending. Empty when the
+    /// note has no header.
+    open: String,
+
+    /// Header entries between the delimiters.
+    segments: Vec<Segment>,
+
+    /// Closing delimiter line including its line ending, if any.
+    close: String,
+}
+
+impl FrontMatter {
+    /// Splits note content into its front matter and body.
+    ///
+    /// Returns an empty [`FrontMatter`] and the whole content as body when
+    /// the content does not start with a complete `---` header.
+    pub fn split(content: &str) -> (FrontMatter, &str) {
+        let Some(open_len) = delimiter_len(content, 0, &["---"]) else {
+            return (FrontMatter::default(), content);
+        };
+
+        let mut pos = open_len;
+        let inner_start = pos;
+        while pos < content.len() {
+            if let Some(close_len) = delimiter_len(content, pos, &["---", "..."]) {
+                let front_matter = FrontMatter {
+                    open: content[..open_len].to_string(),
+                    segments: parse_segments(&content[inner_start..pos]),
+                    close: content[pos..pos + close_len].to_string(),
+                };
+                return (front_matter, &content[pos + close_len..]);
+            }
+            pos = next_line(content, pos);
+        }
+
+        (FrontMatter::default(), content)
+    }
+
+    /// Parses the front matter of `content`, discarding the body.
+    pub fn parse(content: &str) -> FrontMatter {
+        Self::split(content).0
+    }
+
+    /// Returns the body of `content` with any front matter removed.
+    pub fn strip(content: &str) -> &str {
+        Self::split(content).1
+    }
+
+    /// Returns `true` if the note had a header or entries were added to it.
+    pub fn is_present(&self) -> bool {
+        !self.open.is_empty() || !self.segments.is_empty()
+    }
+
+    /// Returns `true` if there are no `key: value` entries.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Looks up the value stored under `key`.
+    pub fn get(&self, key: &str) -> Option<&MetaValue> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Iterates over all entries in header order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetaValue)> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Field { key, value, .. } => Some((key.as_str(), value)),
+            Segment::Verbatim(_) => None,
+        })
+    }
+
+    /// Sets `key` to `value`.
+    ///
+    /// An existing entry is rewritten in place, keeping its list style;
+    /// a new entry is appended at the end of the header. Every other entry
+    /// is left untouched.
+    pub fn set(&mut self, key: &str, value: impl Into<MetaValue>) {
+        let value = value.into();
+        let newline = self.newline();
+
+        for segment in &mut self.segments {
+            if let Segment::Field {
+                key: k,
+                value: v,
+                raw,
+            } = segment
+                && k == key
+            {
+                if *v != value {
+                    let block = is_block_list(raw);
+                    *raw = render_field(key, &value, block, newline);
+                    *v = value;
+                }
+                return;
+            }
+        }
+
+        if self.open.is_empty() {
+            self.open = format!("---{newline}");
+            self.close = format!("---{newline}");
+        }
+        if let Some(Segment::Field { raw, .. } | Segment::Verbatim(raw)) = self.segments.last_mut()
+            && !raw.ends_with('\n')
+        {
+            raw.push_str(newline);
+        }
+
+        self.segments.push(Segment::Field {
+            key: key.to_string(),
+            raw: render_field(key, &value, false, newline),
+            value,
+        });
+    }
+
+    /// Removes `key`, returning its previous value.
+    pub fn remove(&mut self, key: &str) -> Option<MetaValue> {
+        let position = self
+            .segments
+            .iter()
+            .position(|s| matches!(s, Segment::Field { key: k, .. } if k == key))?;
+
+        match self.segments.remove(position) {
+            Segment::Field { value, .. } => Some(value),
+            Segment::Verbatim(_) => None,
+        }
+    }
+
+    /// Renders the header back to text, including both delimiters.
+    ///
+    /// Returns an empty string if there is no header.
+    pub fn render(&self) -> String {
+        if !self.is_present() {
+            return String::new();
+        }
+
+        let mut out = self.open.clone();
+        for segment in &self.segments {
+            match segment {
+                Segment::Field { raw, .. } | Segment::Verbatim(raw) => out.push_str(raw),
+            }
+        }
+        out.push_str(&self.close);
+        out
+    }
+
+    /// Returns the line ending used by the header.
+    fn newline(&self) -> &'static str {
+        if self.open.ends_with("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+}
+
+impl fmt::Display for FrontMatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+/// Returns the byte offset of the start of the line after `pos`.
+fn next_line(content: &str, pos: usize) -> usize {
+    match content[pos..].find('\n') {
+        Some(i) => pos + i + 1,
+        None => content.len(),
+    }
+}
+
+/// If the line starting at `pos` is one of `markers` (ignoring trailing
+/// whitespace), returns the length of the line including its line ending.
+fn delimiter_len(content: &str, pos: usize, markers: &[&str]) -> Option<usize> {
+    let end = next_line(content, pos);
+    let line = content[pos..end].trim_end();
+    if !markers.contains(&line) {
+        return None;
+    }
+    // The opening delimiter must be followed by a line break.
+    if pos == 0 && end == content.len() && !content.ends_with('\n') {
+        return None;
+    }
+    Some(end - pos)
+}
+
+/// Splits the header text into entries, keeping every byte.
+fn parse_segments(text: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut pos = 0;
+
+    while pos < text.len() {
+        let end = next_line(text, pos);
+        let line = &text[pos..end];
+        let trimmed = line.trim_end();
+
+        // Blank lines inside a block scalar belong to it, and so do the
+        // trailing ones it keeps with `+`.
+        let block_scalar = match segments.last() {
+            Some(Segment::Field { raw, .. }) if trimmed.is_empty() => block_scalar_header(raw),
+            _ => None,
+        };
+        let in_block_scalar = block_scalar.is_some_and(|header| {
+            header.chomp == Some('+')
+                || text[end..]
+                    .lines()
+                    .find(|next| !next.trim().is_empty())
+                    .is_some_and(|next| next.starts_with([' ', '\t']))
+        });
+        let is_continuation = line.starts_with([' ', '\t'])
+            || trimmed.starts_with("- ")
+            || trimmed == "-"
+            || in_block_scalar;
+        if is_continuation && let Some(Segment::Field { raw, value, .. }) = segments.last_mut() {
+            raw.push_str(line);
+            *value = parse_field_value(raw);
+        } else if let Some((key, _)) = split_key(trimmed) {
+            segments.push(Segment::Field {
+                key: key.to_string(),
+                value: parse_field_value(line),
+                raw: line.to_string(),
+            });
+        } else if let Some(Segment::Verbatim(raw)) = segments.last_mut() {
+            raw.push_str(line);
+        } else {
+            segments.push(Segment::Verbatim(line.to_string()));
+        }
+
+        pos = end;
+    }
+
+    segments
+}
+
+/// Splits `key: rest` into its parts, rejecting comments and list items.
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with('#') || line.starts_with([' ', '\t']) {
+        return None;
+    }
+    let (key, rest) = line.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty() || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some((key, rest))
+}
+
+/// Returns the value written on the first line of a raw field, without
+/// its comment.
+fn inline_value(raw: &str) -> &str {
+    let first = raw.lines().next().unwrap_or_default();
+    split_key(first.trim_end())
+        .map(|(_, rest)| strip_comment(rest).trim())
+        .unwrap_or_default()
+}
+
+/// Parses the value of a field from its raw text (first line plus any
+/// continuation lines).
+fn parse_field_value(raw: &str) -> MetaValue {
+    let inline = inline_value(raw);
+    let lines = raw.lines().skip(1);
+
+    if let Some(inner) = inline.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        return MetaValue::List(split_flow_list(inner));
+    }
+
+    if inline.starts_with('{') {
+        return MetaValue::Raw(inline.to_string());
+    }
+
+    if let Some(header) = block_scalar_header(raw) {
+        return MetaValue::Text(block_scalar(lines, header));
+    }
+
+    if !inline.is_empty() {
+        return MetaValue::Text(unquote(inline));
+    }
+
+    // The first entry below the key tells a block list from a nested map.
+    let mut entries = lines
+        .clone()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    if entries.next().is_some_and(|entry| !entry.starts_with('-')) {
+        return MetaValue::Raw(lines.collect::<Vec<_>>().join("\n"));
+    }
+
+    let items: Vec<String> = lines
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix('-'))
+        .map(|item| unquote(strip_comment(item).trim()))
+        .filter(|item| !item.is_empty())
+        .collect();
+
+    if items.is_empty() {
+        MetaValue::Text(String::new())
+    } else {
+        MetaValue::List(items)
+    }
+}
+
+/// Header of a block scalar such as `|`, `>-` or `|2+`.
+#[derive(Debug, Clone, Copy)]
+struct BlockScalar {
+    /// `true` for `>`, which folds lines into spaces.
+    folded: bool,
+
+    /// The chomping indicator, `-` or `+`, if any.
+    chomp: Option<char>,
+
+    /// The explicit indentation of the content, if any.
+    indent: Option<usize>,
+}
+
+/// Returns the block scalar header of a raw field, if its value is one.
+fn block_scalar_header(raw: &str) -> Option<BlockScalar> {
+    let mut chars = inline_value(raw).chars();
+    let folded = match chars.next()? {
+        '|' => false,
+        '>' => true,
+        _ => return None,
+    };
+
+    let mut header = BlockScalar {
+        folded,
+        chomp: None,
+        indent: None,
+    };
+    for c in chars {
+        match c {
+            '-' | '+' if header.chomp.is_none() => header.chomp = Some(c),
+            '1'..='9' if header.indent.is_none() => {
+                header.indent = c.to_digit(10).map(|d| d as usize)
+            }
+            _ => return None,
+        }
+    }
+    Some(header)
+}
+
+/// Reads the content lines of a block scalar, folding them for `>` and
+/// applying the chomping indicator.
+fn block_scalar<'a>(lines: impl Iterator<Item = &'a str>, header: BlockScalar) -> String {
+    let lines: Vec<&str> = lines.collect();
+    let indent = header.indent.unwrap_or_else(|| {
+        lines
+            .iter()
+            .find(|line| !line.trim().is_empty())
+            .map_or(0, |line| line.len() - line.trim_start_matches(' ').len())
+    });
+
+    let mut texts: Vec<&str> = lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                ""
+            } else if line.len() >= indent && line.as_bytes()[..indent].iter().all(|&b| b == b' ') {
+                &line[indent..]
+            } else {
+                line.trim_start()
+            }
+        })
+        .collect();
+    let mut trailing = 0;
+    while texts.last() == Some(&"") {
+        texts.pop();
+        trailing += 1;
+    }
+
+    let mut text = if header.folded {
+        fold_lines(&texts)
+    } else {
+        texts.join("\n")
+    };
+    match header.chomp {
+        Some('-') => {}
+        Some(_) => {
+            if !texts.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&"\n".repeat(trailing));
+        }
+        None if !texts.is_empty() => text.push('\n'),
+        None => {}
+    }
+    text
+}
+
+/// Folds the lines of a `>` block scalar: lines are joined with spaces,
+/// blank lines become line breaks, and more indented lines are kept as
+/// they are.
+fn fold_lines(lines: &[&str]) -> String {
+    let mut out = String::new();
+    let mut breaks = 0;
+    let mut first = true;
+    let mut prev_indented = false;
+    for line in lines {
+        if line.is_empty() {
+            breaks += 1;
+            continue;
+        }
+
+        let indented = line.starts_with([' ', '\t']);
+        if first {
+            out.push_str(&"\n".repeat(breaks));
+        } else {
+            match breaks + usize::from(indented || prev_indented) {
+                0 => out.push(' '),
+                n => out.push_str(&"\n".repeat(n)),
+            }
+        }
+        out.push_str(line);
+        breaks = 0;
+        first = false;
+        prev_indented = indented;
+    }
+    out
+}
+
+/// Removes a trailing ` # comment` outside of quotes.
+///
+/// Only a quote at the start of the value opens a quoted scalar, so the
+/// apostrophe in `Don't panic` is plain text.
+fn strip_comment(value: &str) -> &str {
+    let mut quote = None;
+    let mut at_start = true;
+    let mut prev = ' ';
+    let mut chars = value.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some('\''), '\'') if chars.next_if(|&(_, next)| next == '\'').is_some() => {}
+            (Some(q), _) if c == q => quote = None,
+            (None, '"' | '\'') if at_start => quote = Some(c),
+            (None, '#') if prev.is_whitespace() => return &value[..i],
+            _ => {}
+        }
+        at_start &= c.is_whitespace();
+        prev = c;
+    }
+    value
+}
+
+/// Splits the inside of a flow list (`a, "b, c", d`) on top-level commas.
+///
+/// Like in [`strip_comment`], only a quote at the start of an item opens a
+/// quoted scalar: `[don't, rust]` has two items.
+fn split_flow_list(inner: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut at_start = true;
+    let mut start = 0;
+    let mut chars = inner.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some('\''), '\'') if chars.next_if(|&(_, next)| next == '\'').is_some() => {}
+            (Some(q), _) if c == q => quote = None,
+            (None, '"' | '\'') if at_start => quote = Some(c),
+            (None, ',') => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+                at_start = true;
+                continue;
+            }
+            _ => {}
+        }
+        at_start &= c.is_whitespace();
+    }
+    items.push(&inner[start..]);
+
+    items
+        .into_iter()
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Removes matching single or double quotes around a scalar.
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 {
+        if let Some(inner) = value.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            return unescape_double(inner);
+        }
+        if let Some(inner) = value.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+            return inner.replace("''", "'");
+        }
+    }
+    value.to_string()
+}
+
+/// Quotes a scalar if writing it bare would change its meaning.
+fn quote(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value != value.trim()
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.contains(['\n', '\r'])
+        || value.starts_with([
+            '[', ']', '{', '}', ',', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`', '-',
+        ]);
+
+    if needs_quotes {
+        quote_double(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Wraps a scalar in double quotes, escaping `\`, `"` and line breaks.
+fn quote_double(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\"{escaped}\"")
+}
+
+/// Resolves the `\\`, `\"`, `\n` and `\r` escapes inside a double-quoted
+/// scalar.
+///
+/// Other backslashes are kept as they are.
+fn unescape_double(inner: &str) -> String {
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(next @ ('\\' | '"')) => out.push(next),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(next) => {
+                out.push(c);
+                out.push(next);
+            }
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+/// Returns `true` if a raw field uses block list style.
+fn is_block_list(raw: &str) -> bool {
+    raw.lines()
+        .skip(1)
+        .any(|line| line.trim_start().starts_with('-'))
+}
+
+/// Renders a single field, ending with `newline`.
+fn render_field(key: &str, value: &MetaValue, block: bool, newline: &str) -> String {
+    match value {
+        MetaValue::Text(text) if text.is_empty() => format!("{key}:{newline}"),
+        MetaValue::Text(text) => format!("{key}: {}{newline}", quote(text)),
+        MetaValue::List(items) if block && !items.is_empty() => {
+            let mut out = format!("{key}:{newline}");
+            for item in items {
+                out.push_str(&format!("  - {}{newline}", quote(item)));
+            }
+            out
+        }
+        MetaValue::Raw(raw) if raw.starts_with([' ', '\t']) => {
+            format!("{key}:{newline}{}{newline}", raw.replace('\n', newline))
+        }
+        MetaValue::Raw(raw) => format!("{key}: {raw}{newline}"),
+        MetaValue::List(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| {
+                    if item.contains(',') {
+                        quote_double(item)
+                    } else {
+                        quote(item)
+                    }
+                })
+                .collect();
+            format!("{key}: [{}]{newline}", items.join(", "))
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! Revision history of notes.
+//!
+//! Shelves with `history = true` in their `.shelf.toml` (see
+//! [`crate::shelf::config`]) keep earlier versions of their notes in a
+//! [`History`] store, `.history.db` in the shelf root. A revision is
+//! recorded:
+//!
+//! - On every save through a [`ShelfManager`](crate::shelf::manager::ShelfManager):
+//!   creating, updating, renaming or moving a note, link rewrites, and
+//!   restoring a revision
+//! - After a [`LocalNote`](crate::domain::LocalNote) save, when the caller
+//!   passes the store to [`LocalNote::record_revision`](crate::domain::LocalNote::record_revision),
+//!   and on every save through a watcher with `save_with`
+//! - On every modification the watcher detects from outside the library,
+//!   and on changes found by the index's startup reconciliation
+//!
+//! A revision holds the full content of the note as it was saved.
+//! Revisions are stored by note path and follow the note when it is
+//! renamed or moved within the shelf, and are carried along when it is moved
+//! or copied to another shelf; saves that do not change the content are
+//! not recorded twice. With `history_limit`, only the most recent
+//! revisions of each note are kept.
+//!
+//! Unlike the index, the history is not derived from the files on disk and
+//! is never rebuilt: deleting `.history.db` deletes the history.
+//!
+//! # Diffs
+//!
+//! [`diff_lines`] compares two texts line by line, producing the shortest
+//! sequence of [`DiffLine`]s that turns one into the other.
+
+use crate::domain::{content_hash, dir_prefix};
+use crate::error::OraError;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the history database in the shelf root.
+pub const HISTORY_FILE: &str = ".history.db";
+
+/// Where a revision came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RevisionSource {
+    /// A save through the library.
+    Library,
+
+    /// A change made outside the library, seen by the watcher or the
+    /// index's reconciliation.
+    External,
+}
+
+impl RevisionSource {
+    /// Returns the name stored in the database for this source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevisionSource::Library => "library",
+            RevisionSource::External => "external",
+        }
+    }
+
+    /// Parses a source from its stored name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "library" => Some(RevisionSource::Library),
+            "external" => Some(RevisionSource::External),
+            _ => None,
+        }
+    }
+}
+
+/// A recorded version of a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    /// Identifier of the revision, increasing with each recorded revision.
+    pub id: i64,
+
+    /// Path of the note, as of its latest rename.
+    pub path: PathBuf,
+
+    /// Full content of the note at this revision.
+    pub content: String,
+
+    /// When the revision was recorded.
+    pub created_at: SystemTime,
+
+    /// Whether the revision was saved through the library or outside it.
+    pub source: RevisionSource,
+}
+
+/// One line of a [`diff_lines`] result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// A line present in both texts.
+    Unchanged(String),
+
+    /// A line only in the new text.
+    Added(String),
+
+    /// A line only in the old text.
+    Removed(String),
+}
+
+impl fmt::Display for DiffLine {
+    /// Formats the line as in a unified diff: `" "`, `"+"` or `"-"`
+    /// followed by the text.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffLine::Unchanged(line) => write!(f, " {line}"),
+            DiffLine::Added(line) => write!(f, "+{line}"),
+            DiffLine::Removed(line) => write!(f, "-{line}"),
+        }
+    }
+}
+
+/// The revision store of a shelf.
+///
+/// Cloning is cheap; clones share the database connection.
+#[derive(Clone)]
+pub struct History {
+    conn: Arc<Mutex<Connection>>,
+    limit: Option<usize>,
+}
+
+impl History {
+    /// Opens the history of the shelf at `shelf_root`, creating
+    /// `.history.db` if needed.
+    ///
+    /// # Arguments
+    /// * `shelf_root` - Root directory of the shelf
+    /// * `limit` - Maximum number of revisions kept per note, `None` for all
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database cannot be opened or created
+    pub fn open(shelf_root: &Path, limit: Option<usize>) -> Result<Self, OraError> {
+        let conn = Connection::open(shelf_root.join(HISTORY_FILE))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                content TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                source TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS revisions_path ON revisions(path, id);",
+        )?;
+        Ok(History {
+            conn: Arc::new(Mutex::new(conn)),
+            limit,
+        })
+    }
+
+    /// Records a revision of the note at `path`.
+    ///
+    /// Nothing is recorded if the latest revision of the note has the same
+    /// content. Revisions beyond the store's limit are dropped, oldest
+    /// first.
+    ///
+    /// # Returns
+    /// The new revision, or `None` if the content did not change
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn record(
+        &self,
+        path: &Path,
+        content: &str,
+        source: RevisionSource,
+    ) -> Result<Option<Revision>, OraError> {
+        let path_str = path.display().to_string();
+        let hash = content_hash(content.as_bytes());
+        let created_at = SystemTime::now();
+        let millis = created_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let latest: Option<String> = tx
+            .query_row(
+                "SELECT hash FROM revisions WHERE path = ? ORDER BY id DESC LIMIT 1",
+                params![path_str],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if latest.as_deref() == Some(hash.as_str()) {
+            return Ok(None);
+        }
+
+        tx.execute(
+            "INSERT INTO revisions (path, content, hash, source, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![path_str, content, hash, source.as_str(), millis],
+        )?;
+        let id = tx.last_insert_rowid();
+        if let Some(limit) = self.limit {
+            tx.execute(
+                "DELETE FROM revisions WHERE path = ?1 AND id NOT IN
+                 (SELECT id FROM revisions WHERE path = ?1 ORDER BY id DESC LIMIT ?2)",
+                params![path_str, limit as i64],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(Some(Revision {
+            id,
+            path: path.to_path_buf(),
+            content: content.to_string(),
+            created_at: UNIX_EPOCH + Duration::from_millis(millis as u64),
+            source,
+        }))
+    }
+
+    /// Lists the revisions of the note at `path`, newest first.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn revisions(&self, path: &Path) -> Result<Vec<Revision>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, path, content, source, created_at FROM revisions
+             WHERE path = ? ORDER BY id DESC",
+        )?;
+        let revisions = stmt
+            .query_map(params![path.display().to_string()], revision_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(revisions)
+    }
+
+    /// Retrieves a revision by its ID.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn get(&self, id: i64) -> Result<Option<Revision>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let revision = conn
+            .query_row(
+                "SELECT id, path, content, source, created_at FROM revisions WHERE id = ?",
+                params![id],
+                revision_from_row,
+            )
+            .optional()?;
+        Ok(revision)
+    }
+
+    /// Compares two revisions line by line.
+    ///
+    /// The revisions may belong to different notes.
+    ///
+    /// # Arguments
+    /// * `from` - ID of the old revision
+    /// * `to` - ID of the new revision
+    ///
+    /// # Errors
+    /// - [`NoteError::NotFound`](crate::domain::NoteError::NotFound) if
+    ///   either revision does not exist
+    /// - `OraError` if the database query fails
+    pub fn diff(&self, from: i64, to: i64) -> Result<Vec<DiffLine>, OraError> {
+        let old = self.require(from)?;
+        let new = self.require(to)?;
+        Ok(diff_lines(&old.content, &new.content))
+    }
+
+    /// Moves the revisions of the note at `from` to the path `to`.
+    ///
+    /// Revisions already stored for `to` are kept and come first.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn rename(&self, from: &Path, to: &Path) -> Result<(), OraError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE revisions SET path = ? WHERE path = ?",
+            params![to.display().to_string(), from.display().to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Copies revisions, such as those of a note in another shelf, to the
+    /// note at `path`, keeping their content, source and time.
+    ///
+    /// `revisions` are expected newest first, as returned by
+    /// [`History::revisions`]. They get new IDs and come after any
+    /// revisions already stored for `path`; the store's limit applies.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn import(&self, path: &Path, revisions: &[Revision]) -> Result<(), OraError> {
+        let path_str = path.display().to_string();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for revision in revisions.iter().rev() {
+            let millis = revision
+                .created_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64;
+            tx.execute(
+                "INSERT INTO revisions (path, content, hash, source, created_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![
+                    path_str,
+                    revision.content,
+                    content_hash(revision.content.as_bytes()),
+                    revision.source.as_str(),
+                    millis
+                ],
+            )?;
+        }
+        if let Some(limit) = self.limit {
+            tx.execute(
+                "DELETE FROM revisions WHERE path = ?1 AND id NOT IN
+                 (SELECT id FROM revisions WHERE path = ?1 ORDER BY id DESC LIMIT ?2)",
+                params![path_str, limit as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Moves the revisions of all notes below the directory `from` to the
+    /// same relative paths below `to`.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn rename_dir(&self, from: &Path, to: &Path) -> Result<(), OraError> {
+        let old_prefix = dir_prefix(from);
+        let new_prefix = dir_prefix(to);
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE revisions SET path = ?2 || substr(path, length(?1) + 1)
+             WHERE substr(path, 1, length(?1)) = ?1",
+            params![old_prefix, new_prefix],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes every revision of the note at `path`.
+    ///
+    /// # Returns
+    /// The number of revisions deleted
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn forget(&self, path: &Path) -> Result<usize, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM revisions WHERE path = ?",
+            params![path.display().to_string()],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Retrieves a revision, failing if it does not exist.
+    fn require(&self, id: i64) -> Result<Revision, OraError> {
+        self.get(id)?
+            .ok_or_else(|| crate::domain::NoteError::NotFound(format!("revision {id}")).into())
+    }
+}
+
+/// Builds a [`Revision`] from a row of `id, path, content, source, created_at`.
+fn revision_from_row(row: &rusqlite::Row) -> rusqlite::Result<Revision> {
+    let source: String = row.get(3)?;
+    let millis: i64 = row.get(4)?;
+    Ok(Revision {
+        id: row.get(0)?,
+        path: PathBuf::from(row.get::<_, String>(1)?),
+        content: row.get(2)?,
+        created_at: UNIX_EPOCH + Duration::from_millis(millis.max(0) as u64),
+        source: RevisionSource::from_name(&source).unwrap_or(RevisionSource::External),
+    })
+}
+
+/// Compares two texts line by line.
+///
+/// Lines common to both texts are kept in order as [`DiffLine::Unchanged`];
+/// for each change, removed lines come before the added lines replacing
+/// them.
+///
+/// # Examples
+/// ```rust
+/// use ora_core::history::{DiffLine, diff_lines};
+///
+/// let diff = diff_lines("a\nb\nc\n", "a\nB\nc\n");
+/// assert_eq!(
+///     diff,
+///     vec![
+///         DiffLine::Unchanged("a".into()),
+///         DiffLine::Removed("b".into()),
+///         DiffLine::Added("B".into()),
+///         DiffLine::Unchanged("c".into()),
+///     ]
+/// );
+/// ```
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(&old, &new, &mut diff);
+
+    // Within each change, list the removed lines before the added ones.
+    for change in diff.chunk_by_mut(|a, b| {
+        !matches!(a, DiffLine::Unchanged(_)) && !matches!(b, DiffLine::Unchanged(_))
+    }) {
+        change.sort_by_key(|line| matches!(line, DiffLine::Added(_)));
+    }
+    diff
+}
+
+/// Appends the diff of `a` and `b` to `diff`, splitting the problem at the
+/// middle of an optimal edit path (Myers' linear space refinement).
+fn diff_range(a: &[&str], b: &[&str], diff: &mut Vec<DiffLine>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let unchanged = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|line| DiffLine::Unchanged(line.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    diff.extend(unchanged(&a[..prefix]));
+    let (inner_a, inner_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if inner_a.is_empty() {
+        diff.extend(inner_b.iter().map(|line| DiffLine::Added(line.to_string())));
+    } else if inner_b.is_empty() {
+        diff.extend(
+            inner_a
+                .iter()
+                .map(|line| DiffLine::Removed(line.to_string())),
+        );
+    } else {
+        let (x, y) = middle_point(inner_a, inner_b);
+        diff_range(&inner_a[..x], &inner_b[..y], diff);
+        diff_range(&inner_a[x..], &inner_b[y..], diff);
+    }
+    diff.extend(unchanged(&a[a.len() - suffix..]));
+}
+
+/// Finds a point `(x, y)` on a shortest edit path from `a` to `b`, about
+/// halfway along it, by searching from both ends at once.
+///
+/// `a` and `b` must be non-empty and differ in their first and last lines,
+/// so the point is neither the start nor the end.
+fn middle_point(a: &[&str], b: &[&str]) -> (usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // Furthest x reached on each diagonal k = x - y, forward from the start
+    // and backward (in reversed coordinates) from the end.
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            while x < n && x - k < m && a[x as usize] == b[(x - k) as usize] {
+                x += 1;
+            }
+            forward[at(k)] = x;
+            if delta % 2 != 0 && (delta - k).abs() < d && x + backward[at(delta - k)] >= n {
+                return (x0 as usize, y0 as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            while x < n && x - k < m && a[(n - 1 - x) as usize] == b[(m - 1 - (x - k)) as usize] {
+                x += 1;
+            }
+            backward[at(k)] = x;
+            if delta % 2 == 0 && (delta - k).abs() <= d && x + forward[at(delta - k)] >= n {
+                return ((n - x0) as usize, (m - y0) as usize);
+            }
+        }
+    }
+    unreachable!("the forward and backward searches always meet")
+}
@@ -0,0 +1,373 @@
+//! Ignore rules for paths inside a shelf.
+//!
+//! Rules come from two places, in increasing order of precedence:
+//! 1. The `ignore` patterns of the shelf's `.shelf.toml`
+//! 2. `.oraignore` files in the shelf root and any subdirectory, each
+//!    applying to its own directory; deeper files take precedence
+//!
+//! Patterns use `.gitignore` syntax and are matched against paths relative
+//! to the directory they were defined for:
+//!
+//! - Blank lines and lines starting with `#` are skipped; `\#` escapes a
+//!   leading hash
+//! - `*` matches anything except `/`, `?` one character except `/`, and
+//!   `[abc]` / `[!a-z]` a character class
+//! - `**` matches across directories: `**/drafts`, `archive/**`, `a/**/b`
+//! - A trailing `/` only matches directories
+//! - A pattern with a `/` at the start or in the middle is anchored to the
+//!   directory it was defined for; otherwise it matches at any depth
+//! - A leading `!` re-includes paths excluded by an earlier pattern
+//!
+//! The last matching pattern decides. As in Git, a path inside an ignored
+//! directory is ignored no matter what later patterns say.
+//!
+//! Hidden files and directories (starting with `.`) are always skipped by
+//! the shelf, whether or not a pattern mentions them. `.oraignore` files
+//! inside ignored or hidden directories are not read.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A single parsed ignore pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnorePattern {
+    /// Directory the pattern was defined for, relative to the shelf root,
+    /// as `/`-separated components without a trailing slash.
+    base: String,
+
+    /// Glob matched against the path relative to `base`.
+    glob: String,
+
+    /// `!` pattern: re-includes matching paths.
+    negated: bool,
+
+    /// Trailing `/`: matches directories only.
+    dir_only: bool,
+}
+
+impl IgnorePattern {
+    /// Parses one line, returning `None` for blank lines and comments.
+    fn parse(line: &str, base: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        let glob = if let Some(anchored) = line.strip_prefix('/') {
+            anchored.to_string()
+        } else if line.contains('/') {
+            line.to_string()
+        } else {
+            format!("**/{line}")
+        };
+
+        Some(IgnorePattern {
+            base: base.to_string(),
+            glob,
+            negated,
+            dir_only,
+        })
+    }
+
+    /// Returns `true` if the pattern matches `path` (relative to the shelf root).
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let relative = if self.base.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(self.base.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(rest) => rest,
+                None => return false,
+            }
+        };
+        glob_match(self.glob.as_bytes(), relative.as_bytes())
+    }
+}
+
+/// Name of the per-directory ignore file.
+pub const IGNORE_FILE: &str = ".oraignore";
+
+/// An ordered set of ignore patterns for one shelf.
+///
+/// Patterns added later take precedence over earlier ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    /// Creates an empty rule set that ignores nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the rules of the shelf at `root`.
+    ///
+    /// Starts from `base_patterns` (the `.shelf.toml` patterns) and adds
+    /// every `.oraignore` file found in `root` and its subdirectories,
+    /// parents before children.
+    ///
+    /// # Errors
+    /// Returns an I/O error if a directory or ignore file cannot be read
+    pub fn load<S: AsRef<str>>(root: &Path, base_patterns: &[S]) -> std::io::Result<Self> {
+        let mut rules = IgnoreRules::new();
+        rules.add_patterns(Path::new(""), base_patterns);
+        rules.load_dir(root, root)?;
+        Ok(rules)
+    }
+
+    /// Adds the ignore file of `dir`, then recurses into its visible,
+    /// not ignored subdirectories.
+    fn load_dir(&mut self, root: &Path, dir: &Path) -> std::io::Result<()> {
+        match fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(text) => {
+                let base = dir.strip_prefix(root).unwrap_or(Path::new(""));
+                self.add_patterns(base, text.lines());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() && !is_hidden(&path) && !self.is_ignored_in(root, &path) {
+                self.load_dir(root, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds patterns defined for the directory `base`, relative to the
+    /// shelf root (empty for the root itself).
+    ///
+    /// # Arguments
+    /// * `base` - Directory the patterns are anchored to
+    /// * `lines` - Pattern lines in `.gitignore` syntax
+    pub fn add_patterns<I, S>(&mut self, base: &Path, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let base = to_slash_path(base);
+        self.patterns.extend(
+            lines
+                .into_iter()
+                .filter_map(|line| IgnorePattern::parse(line.as_ref(), &base)),
+        );
+    }
+
+    /// Returns `true` if the rule set has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Checks whether a path relative to the shelf root is ignored.
+    ///
+    /// # Arguments
+    /// * `relative` - Path relative to the shelf root
+    /// * `is_dir` - Whether the path is a directory
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let path = to_slash_path(relative);
+        if path.is_empty() {
+            return false;
+        }
+
+        // A path inside an ignored directory cannot be re-included.
+        let mut end = 0;
+        while let Some(slash) = path[end..].find('/') {
+            end += slash;
+            if self.decide(&path[..end], true) {
+                return true;
+            }
+            end += 1;
+        }
+        self.decide(&path, is_dir)
+    }
+
+    /// Checks whether an absolute path inside `root` is ignored.
+    ///
+    /// Paths outside `root` are never ignored.
+    pub fn is_ignored_in(&self, root: &Path, path: &Path) -> bool {
+        match path.strip_prefix(root) {
+            Ok(relative) => self.is_ignored(relative, path.is_dir()),
+            Err(_) => false,
+        }
+    }
+
+    /// Applies the last matching pattern to a single path.
+    fn decide(&self, path: &str, is_dir: bool) -> bool {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(path, is_dir))
+            .is_some_and(|pattern| !pattern.negated)
+    }
+}
+
+/// Returns `true` if a file or directory name is hidden (starts with `.`).
+pub(crate) fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.'))
+}
+
+/// Returns `true` if `path` is an ignore file.
+pub(crate) fn is_ignore_file(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()) == Some(IGNORE_FILE)
+}
+
+/// Returns `true` if a directory between `root` and `path` is hidden.
+///
+/// The last component (the file itself) is not checked.
+pub(crate) fn in_hidden_dir(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    relative.parent().is_some_and(|dirs| {
+        dirs.components()
+            .any(|c| is_hidden(Path::new(c.as_os_str())))
+    })
+}
+
+/// Recursively collects the Markdown files under `dir` that are neither
+/// hidden nor ignored by `rules`.
+///
+/// # Arguments
+/// * `root` - Shelf root the rules are relative to
+/// * `dir` - Directory to scan, `root` or one inside it
+/// * `rules` - Ignore rules of the shelf
+pub(crate) fn markdown_files(
+    root: &Path,
+    dir: &Path,
+    rules: &IgnoreRules,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_hidden(&path) || rules.is_ignored_in(root, &path) {
+            continue;
+        }
+
+        if path.is_dir() {
+            files.extend(markdown_files(root, &path, rules)?);
+        } else if path.extension().and_then(|s| s.to_str()) == Some("md") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Joins the normal components of `path` with `/`.
+fn to_slash_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Matches a `/`-separated path against a glob supporting `*`, `?`, `**`
+/// and character classes.
+fn glob_match(glob: &[u8], path: &[u8]) -> bool {
+    if let Some(rest) = glob.strip_prefix(b"**/") {
+        // Zero directories, or skip one component and try again.
+        if glob_match(rest, path) {
+            return true;
+        }
+        return match path.iter().position(|&b| b == b'/') {
+            Some(slash) => glob_match(glob, &path[slash + 1..]),
+            None => false,
+        };
+    }
+    if glob == b"**" {
+        return true;
+    }
+
+    match glob.first() {
+        None => path.is_empty(),
+        Some(b'*') => {
+            let rest = &glob[1..];
+            for i in 0..=path.len() {
+                if glob_match(rest, &path[i..]) {
+                    return true;
+                }
+                if path.get(i) == Some(&b'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some(b'?') => match path.first() {
+            Some(&c) if c != b'/' => glob_match(&glob[1..], &path[1..]),
+            _ => false,
+        },
+        Some(b'[') => match class_match(glob, path.first().copied()) {
+            Some((matched, len)) => {
+                matched && path.first() != Some(&b'/') && glob_match(&glob[len..], &path[1..])
+            }
+            None => path.first() == Some(&b'[') && glob_match(&glob[1..], &path[1..]),
+        },
+        Some(b'\\') if glob.len() > 1 => {
+            path.first() == Some(&glob[1]) && glob_match(&glob[2..], &path[1..])
+        }
+        Some(&g) => path.first() == Some(&g) && glob_match(&glob[1..], &path[1..]),
+    }
+}
+
+/// Matches one byte against the character class at the start of `glob`.
+///
+/// # Returns
+/// Whether it matched and the length of the class, or `None` if the class
+/// is not terminated (and `[` is then taken literally)
+fn class_match(glob: &[u8], c: Option<u8>) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(glob.get(i), Some(b'!') | Some(b'^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while let Some(&g) = glob.get(i) {
+        if g == b']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+        if glob.get(i + 1) == Some(&b'-') && glob.get(i + 2).is_some_and(|&end| end != b']') {
+            let end = glob[i + 2];
+            if c.is_some_and(|c| g <= c && c <= end) {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if c == Some(g) {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+    None
+}
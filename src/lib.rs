@@ -6,9 +6,10 @@
 //! ## Features
 //!
 //! - **Note Management**: Create, read, update, and delete Markdown notes with atomic file operations
-//! - **Shelf Organization**: Organize notes in shelf-based directories with validation
+//! - **Shelf Organization**: Organize notes in shelf-based directories with validation,
+//!   under a configurable library directory
 //! - **Real-time Watching**: Monitor file system changes with debounced event processing
-//! - **Full-text Search**: SQLite FTS5-powered search with BM25 ranking and snippets
+//! - **Full-text Search**: SQLite FTS5-powered search with BM25 ranking, snippets and a typo-tolerant fuzzy mode
 //! - **Robust Error Handling**: Comprehensive error types with automatic conversions
 //! - **Thread-safe Operations**: Safe concurrent access to indexed data
 //!
@@ -37,9 +38,14 @@
 //! The library is organized into several key modules:
 //!
 //! - **[`domain`]**: Core note operations and file management
+//! - **[`front_matter`]**: YAML front matter parsing and round-tripping
+//! - **[`history`]**: Opt-in revision history of notes with line diffs
+//! - **[`ignore`]**: `.gitignore`-style patterns for leaving paths out of a shelf
+//! - **[`links`]**: Wikilink and Markdown link extraction
 //! - **[`shelf`]**: Shelf storage and management functionality
 //! - **[`watcher`]: Real-time file system monitoring and indexing
 //! - **[`search`]: Full-text search with SQLite FTS5
+//! - **[`tags`]**: Tag extraction from front matter and inline `#hashtags`
 //! - **[`error`]: Unified error handling throughout the library
 //!
 //! ## Note Management
@@ -50,7 +56,12 @@
 //! - Atomic file writes to prevent corruption
 //! - Automatic filename generation with conflict resolution
 //! - Content and title updates with proper file renaming
-//! - Safe deletion operations
+//! - Conflict detection when a file changed on disk since it was read, with
+//!   forced saves to overwrite it
+//! - Safe deletion into a per-shelf trash, with restore and retention-based purging
+//! - Folders: notes addressed by shelf-relative path, moved between folders,
+//!   and listed per folder or recursively
+//! - Moving and copying notes and folders between shelves
 //!
 //! ```rust,no_run
 //! use ora_core::domain::LocalNote;
@@ -70,7 +81,7 @@
 //! let mut updated_note = note.with_content("Updated content");
 //! updated_note.save()?;
 //!
-//! // Delete the note
+//! // Move the note to the trash
 //! updated_note.delete()?;
 //! # Ok(())
 //! # }
@@ -112,6 +123,7 @@
 //! - Automatic indexing of new and modified files
 //! - Proper cleanup of deleted files from the index
 //! - Thread-safe operation with graceful shutdown
+//! - Typed change notifications for subscribers
 //!
 //! ## Error Handling
 //!
@@ -132,14 +144,21 @@
 
 pub mod domain;
 pub mod error;
+pub mod front_matter;
+pub mod history;
+pub mod ignore;
+pub mod links;
 pub mod search;
 pub mod shelf;
+pub mod tags;
 pub mod watcher;
 
 // Public API exports
 pub use domain::LocalNote;
 pub use error::{OraError, OraResult};
+pub use front_matter::{FrontMatter, MetaValue};
 pub use search::{Query, SearchOptions};
+pub use shelf::library::Library;
 pub use shelf::manager::ShelfManager;
 pub use shelf::storage::Shelf;
 pub use watcher::index::Index;
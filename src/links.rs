@@ -0,0 +1,581 @@
+//! Link extraction for notes.
+//!
+//! Two kinds of links between notes are recognized:
+//! - Wikilinks: `[[Note Title]]`, `[[Note Title|alias]]`, `[[Note#Heading]]`
+//!   and embeds such as `![[Note]]`
+//! - Markdown links to other Markdown files: `[text](other%20note.md)`,
+//!   `[text](<sub dir/other note.md#section>)`
+//!
+//! Links inside fenced code blocks and inline code spans are ignored, as are
+//! links in the front matter header. Markdown links with a URL scheme
+//! (`https://...`) or to non-Markdown files are not considered note links.
+//!
+//! Every [`Link`] records the byte range of its target in the original
+//! content, so links can be rewritten in place without touching the rest of
+//! the text.
+
+use crate::front_matter::FrontMatter;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+/// The syntax a link was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// A `[[wikilink]]`, resolved by note title or shelf-relative path.
+    Wiki,
+
+    /// A `[text](file.md)` link, resolved relative to the linking note.
+    Markdown,
+}
+
+impl LinkKind {
+    /// Returns the name stored in the index for this kind.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Wiki => "wiki",
+            LinkKind::Markdown => "markdown",
+        }
+    }
+
+    /// Parses a kind from its stored name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wiki" => Some(LinkKind::Wiki),
+            "markdown" => Some(LinkKind::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A link from a note to another note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Whether this is a wikilink or a Markdown link.
+    pub kind: LinkKind,
+
+    /// The link target without fragment or alias.
+    ///
+    /// For wikilinks this is the note title or shelf-relative path as
+    /// written (`Note Title`, `folder/Note`). For Markdown links it is the
+    /// percent-decoded relative path (`../other note.md`).
+    pub target: String,
+
+    /// The `#heading` or `#^block` part of the link, without the `#`.
+    pub fragment: Option<String>,
+
+    /// The display text: the `|alias` of a wikilink or the `[text]` of a
+    /// Markdown link.
+    pub alias: Option<String>,
+
+    /// `true` for `![[embeds]]`.
+    pub embed: bool,
+
+    /// Byte range of the target as written in the content, excluding any
+    /// fragment or alias. For Markdown links this is the raw, still encoded
+    /// destination.
+    pub target_range: Range<usize>,
+}
+
+impl Link {
+    /// Resolves a Markdown link target against the path of the linking note.
+    ///
+    /// Relative targets are resolved against the note's directory and
+    /// targets starting with `/` against `root`. The result is normalized
+    /// lexically; the file does not need to exist.
+    ///
+    /// Returns `None` for wikilinks, which are resolved by title instead.
+    pub fn resolve_path(&self, source: &Path, root: &Path) -> Option<PathBuf> {
+        if self.kind != LinkKind::Markdown {
+            return None;
+        }
+
+        let joined = match self.target.strip_prefix('/') {
+            Some(absolute) => root.join(absolute),
+            None => source.parent()?.join(&self.target),
+        };
+        Some(normalize_path(&joined))
+    }
+}
+
+/// Extracts all note links from note content.
+///
+/// Links are returned in the order they appear. Byte ranges refer to the
+/// full content including any front matter.
+///
+/// # Examples
+/// ```rust
+/// use ora_core::links::{extract_links, LinkKind};
+///
+/// let links = extract_links("See [[Rust Notes|rust]] and [setup](guides/setup.md).");
+/// assert_eq!(links.len(), 2);
+/// assert_eq!(links[0].target, "Rust Notes");
+/// assert_eq!(links[0].alias.as_deref(), Some("rust"));
+/// assert_eq!(links[1].kind, LinkKind::Markdown);
+/// assert_eq!(links[1].target, "guides/setup.md");
+/// ```
+pub fn extract_links(content: &str) -> Vec<Link> {
+    let body = FrontMatter::strip(content);
+    let body_start = content.len() - body.len();
+
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut line_start = body_start;
+
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") {
+            fence = Some("```");
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+        } else {
+            scan_line(line, line_start, &mut links);
+        }
+        line_start += line.len();
+    }
+
+    links
+}
+
+/// Rewrites links in `content` that point at `old_path` to point at `new_path`.
+///
+/// `source` is the path of the note holding `content` (after any rename of
+/// its own) and `root` the shelf root. Wikilinks keep their alias, fragment
+/// and form: `[[Old]]` becomes `[[New]]` and `[[dir/Old]]` becomes the
+/// shelf-relative path of the new note. Markdown links are rewritten to the
+/// relative path from `source` to `new_path`, or to a root-relative path if
+/// they started with `/`.
+///
+/// Wikilinks are matched the way the index resolves them, through the keys
+/// of [`note_keys`] and [`wiki_target_key`]. Wikilinks whose key is in
+/// `ambiguous` are left alone: they name other notes as well, so they may
+/// not mean the one being renamed.
+///
+/// # Returns
+/// The rewritten content and the number of links changed
+///
+/// # Examples
+/// ```rust
+/// use ora_core::links::retarget_links;
+/// use std::collections::HashSet;
+/// use std::path::Path;
+///
+/// let (content, changed) = retarget_links(
+///     "See [[Old Title|here]] and [file](Old%20Title.md).",
+///     Path::new("/shelf/Other.md"),
+///     Path::new("/shelf"),
+///     Path::new("/shelf/Old Title.md"),
+///     Path::new("/shelf/New Title.md"),
+///     &HashSet::new(),
+/// );
+/// assert_eq!(content, "See [[New Title|here]] and [file](New%20Title.md).");
+/// assert_eq!(changed, 2);
+/// ```
+pub fn retarget_links(
+    content: &str,
+    source: &Path,
+    root: &Path,
+    old_path: &Path,
+    new_path: &Path,
+    ambiguous: &HashSet<String>,
+) -> (String, usize) {
+    let old_path = normalize_path(old_path);
+    let old_title = old_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let old_keys = note_keys(old_title, &old_path, root);
+    let new_title = new_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let new_relative = shelf_relative(new_path, root);
+
+    let mut replacements = Vec::new();
+    for link in extract_links(content) {
+        let replacement = match link.kind {
+            LinkKind::Wiki => {
+                let (target, suffix) = match link.target.strip_suffix(".md") {
+                    Some(target) => (target, ".md"),
+                    None => (link.target.as_str(), ""),
+                };
+                let key = wiki_target_key(target);
+                if !old_keys.contains(&key) || ambiguous.contains(&key) {
+                    None
+                } else if target.contains('/') {
+                    Some(format!("{new_relative}{suffix}"))
+                } else {
+                    Some(format!("{new_title}{suffix}"))
+                }
+            }
+            LinkKind::Markdown => {
+                if link.resolve_path(source, root).as_deref() != Some(old_path.as_path()) {
+                    None
+                } else {
+                    let path = if link.target.starts_with('/') {
+                        format!("/{new_relative}.md")
+                    } else {
+                        let from = source.parent().unwrap_or(root);
+                        relative_path(from, new_path).display().to_string()
+                    };
+                    let in_angle = link.target_range.start > 0
+                        && content.as_bytes()[link.target_range.start - 1] == b'<';
+                    Some(if in_angle {
+                        path
+                    } else {
+                        percent_encode(&path)
+                    })
+                }
+            }
+        };
+
+        if let Some(replacement) = replacement
+            && content[link.target_range.clone()] != replacement
+        {
+            replacements.push((link.target_range, replacement));
+        }
+    }
+
+    let changed = replacements.len();
+    let mut rewritten = content.to_string();
+    for (range, replacement) in replacements.into_iter().rev() {
+        rewritten.replace_range(range, &replacement);
+    }
+    (rewritten, changed)
+}
+
+/// Rewrites the relative Markdown links of a note that moves from
+/// `old_source` to `new_source`, so they keep pointing at the same files.
+///
+/// Links starting with `/` are resolved against the shelf root and stay
+/// valid, and wikilinks are resolved by title; both are left untouched.
+///
+/// # Returns
+/// The rewritten content and the number of links changed
+///
+/// # Examples
+/// ```rust
+/// use ora_core::links::rebase_links;
+/// use std::path::Path;
+///
+/// let (content, changed) = rebase_links(
+///     "See [setup](guides/setup.md) and [[Setup]].",
+///     Path::new("/shelf/Note.md"),
+///     Path::new("/shelf/archive/Note.md"),
+/// );
+/// assert_eq!(content, "See [setup](../guides/setup.md) and [[Setup]].");
+/// assert_eq!(changed, 1);
+/// ```
+pub fn rebase_links(content: &str, old_source: &Path, new_source: &Path) -> (String, usize) {
+    let Some(new_dir) = new_source.parent() else {
+        return (content.to_string(), 0);
+    };
+
+    let mut replacements = Vec::new();
+    for link in extract_links(content) {
+        if link.kind != LinkKind::Markdown || link.target.starts_with('/') {
+            continue;
+        }
+        let Some(target) = old_source.parent().map(|dir| dir.join(&link.target)) else {
+            continue;
+        };
+        let path = relative_path(new_dir, &target).display().to_string();
+        if normalize_path(Path::new(&link.target)) == Path::new(&path) {
+            continue;
+        }
+
+        let in_angle =
+            link.target_range.start > 0 && content.as_bytes()[link.target_range.start - 1] == b'<';
+        let replacement = if in_angle {
+            path
+        } else {
+            percent_encode(&path)
+        };
+        replacements.push((link.target_range, replacement));
+    }
+
+    let changed = replacements.len();
+    let mut rewritten = content.to_string();
+    for (range, replacement) in replacements.into_iter().rev() {
+        rewritten.replace_range(range, &replacement);
+    }
+    (rewritten, changed)
+}
+
+/// Computes the relative path from directory `from` to `to`.
+///
+/// Both paths are normalized lexically first.
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from = normalize_path(from);
+    let to = normalize_path(to);
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut out = PathBuf::new();
+    for _ in common..from.len() {
+        out.push("..");
+    }
+    for component in &to[common..] {
+        out.push(component.as_os_str());
+    }
+    out
+}
+
+/// Returns the shelf-relative path of a note without its `.md` extension,
+/// using `/` as separator.
+fn shelf_relative(path: &Path, root: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path).with_extension("");
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns the keys a wikilink can use to point at the note `title` at
+/// `path`: its lower-cased title and the trailing parts of its path
+/// relative to `root` with at least two components.
+pub fn note_keys(title: &str, path: &Path, root: &Path) -> Vec<String> {
+    let relative = path.strip_prefix(root).unwrap_or(path).with_extension("");
+    let components: Vec<String> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    let mut keys = vec![title.to_lowercase()];
+    for start in 0..components.len().saturating_sub(1) {
+        keys.push(components[start..].join("/"));
+    }
+    keys
+}
+
+/// Returns the key a wikilink target is looked up by in [`note_keys`].
+///
+/// Titles match regardless of case, paths exactly.
+pub fn wiki_target_key(target: &str) -> String {
+    if target.contains('/') {
+        target.to_string()
+    } else {
+        target.to_lowercase()
+    }
+}
+
+/// Returns the key of a wikilink, with any `.md` suffix dropped, or `None`
+/// for Markdown links.
+pub fn wiki_link_key(link: &Link) -> Option<String> {
+    if link.kind != LinkKind::Wiki {
+        return None;
+    }
+    let target = link.target.strip_suffix(".md").unwrap_or(&link.target);
+    Some(wiki_target_key(target))
+}
+
+/// Returns `true` if `target` names a Markdown file rather than a URL.
+pub fn is_note_target(target: &str) -> bool {
+    !target.contains("://")
+        && !target.starts_with("mailto:")
+        && target.to_ascii_lowercase().ends_with(".md")
+}
+
+/// Lexically normalizes a path by resolving `.` and `..` components.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// Decodes `%XX` escapes in a Markdown link destination.
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = value.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Encodes characters that would break a Markdown link destination.
+pub fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ' ' => out.push_str("%20"),
+            '(' => out.push_str("%28"),
+            ')' => out.push_str("%29"),
+            '#' => out.push_str("%23"),
+            '%' => out.push_str("%25"),
+            '<' => out.push_str("%3C"),
+            '>' => out.push_str("%3E"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Scans a single line (outside fenced code) for links.
+fn scan_line(line: &str, offset: usize, links: &mut Vec<Link>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                // Skip inline code spans, matching the opening backtick run.
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let fence = &line[i..i + run];
+                i = match line[i + run..].find(fence) {
+                    Some(end) => i + run + end + run,
+                    None => i + run,
+                };
+            }
+            b'[' if bytes.get(i + 1) == Some(&b'[') => match parse_wikilink(line, i, offset) {
+                Some((link, end)) => {
+                    links.push(link);
+                    i = end;
+                }
+                None => i += 2,
+            },
+            b'[' => match parse_markdown_link(line, i, offset) {
+                Some((link, end)) => {
+                    links.push(link);
+                    i = end;
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+}
+
+/// Parses a `[[...]]` wikilink starting at `start`.
+fn parse_wikilink(line: &str, start: usize, offset: usize) -> Option<(Link, usize)> {
+    let inner_start = start + 2;
+    let inner_len = line[inner_start..].find("]]")?;
+    let inner = &line[inner_start..inner_start + inner_len];
+    if inner.contains('[') || inner.trim().is_empty() {
+        return None;
+    }
+
+    let (target_part, alias) = match inner.find('|') {
+        Some(bar) => (
+            inner[..bar].strip_suffix('\\').unwrap_or(&inner[..bar]),
+            Some(inner[bar + 1..].trim().to_string()),
+        ),
+        None => (inner, None),
+    };
+    let (target, fragment) = match target_part.find('#') {
+        Some(hash) => (
+            &target_part[..hash],
+            Some(target_part[hash + 1..].trim().to_string()),
+        ),
+        None => (target_part, None),
+    };
+
+    // Keep the range on the trimmed target so rewrites preserve spacing.
+    let leading = target.len() - target.trim_start().len();
+    let target_trimmed = target.trim();
+    let range_start = offset + inner_start + leading;
+
+    let link = Link {
+        kind: LinkKind::Wiki,
+        target: target_trimmed.to_string(),
+        fragment,
+        alias: alias.filter(|a| !a.is_empty()),
+        embed: start > 0 && line.as_bytes()[start - 1] == b'!',
+        target_range: range_start..range_start + target_trimmed.len(),
+    };
+    Some((link, inner_start + inner_len + 2))
+}
+
+/// Parses a `[text](destination)` link starting at `start`.
+///
+/// Returns `None` for images, URLs and links to non-Markdown files.
+fn parse_markdown_link(line: &str, start: usize, offset: usize) -> Option<(Link, usize)> {
+    if start > 0 && line.as_bytes()[start - 1] == b'!' {
+        return None;
+    }
+
+    let text_len = line[start + 1..].find(']')?;
+    let text = &line[start + 1..start + 1 + text_len];
+    let open = start + 1 + text_len + 1;
+    if line.as_bytes().get(open) != Some(&b'(') {
+        return None;
+    }
+
+    // Find the closing parenthesis, allowing balanced parentheses inside.
+    let dest_start = open + 1;
+    let mut depth = 0;
+    let mut close = None;
+    let mut in_angle = false;
+    for (i, c) in line[dest_start..].char_indices() {
+        match c {
+            '<' if i == 0 => in_angle = true,
+            '>' if in_angle => in_angle = false,
+            '(' if !in_angle => depth += 1,
+            ')' if !in_angle && depth == 0 => {
+                close = Some(dest_start + i);
+                break;
+            }
+            ')' if !in_angle => depth -= 1,
+            _ => {}
+        }
+    }
+    let close = close?;
+    let destination = &line[dest_start..close];
+
+    // Strip an optional link title: [text](file.md "Title")
+    let (raw_target, raw_start) = if let Some(inner) = destination.strip_prefix('<') {
+        let end = inner.find('>')?;
+        (&inner[..end], dest_start + 1)
+    } else {
+        let trimmed = destination.trim_start();
+        let lead = destination.len() - trimmed.len();
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        (&trimmed[..end], dest_start + lead)
+    };
+
+    let (raw_path, fragment) = match raw_target.find('#') {
+        Some(hash) => (
+            &raw_target[..hash],
+            Some(raw_target[hash + 1..].to_string()),
+        ),
+        None => (raw_target, None),
+    };
+
+    let target = percent_decode(raw_path);
+    if raw_path.is_empty() || !is_note_target(&target) {
+        return None;
+    }
+
+    let link = Link {
+        kind: LinkKind::Markdown,
+        target,
+        fragment,
+        alias: Some(text.to_string()).filter(|t| !t.is_empty()),
+        embed: false,
+        target_range: offset + raw_start..offset + raw_start + raw_path.len(),
+    };
+    Some((link, close + 1))
+}
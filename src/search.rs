@@ -9,9 +9,21 @@
 //! - **Full-text search**: Search across both title and content
 //! - **Field-specific search**: Search only titles or only content
 //! - **BM25 ranking**: Results are ranked by relevance using the BM25 algorithm
+//! - **Sorting**: Order results by relevance, optionally boosted for recently
+//!   updated notes, or by title, dates, path or size
 //! - **Snippets**: Extract highlighted text fragments around matches
 //! - **Pagination**: Support for limit/offset pagination
+//! - **Filters**: Restrict results by folder, creation and update time, tags
+//!   and length, applied in SQL so counts and pages stay accurate
 //! - **Suggestions**: Auto-complete suggestions for note titles
+//! - **Fuzzy titles**: fzf-style subsequence matching of titles with match
+//!   positions for highlighting, see [`fuzzy`]
+//! - **Tags**: List tags with counts, find notes by tag, and filter searches by tag
+//! - **Fuzzy mode**: Find substrings and misspelled words ("recieve" finds
+//!   "receive") after the BM25 matches, optionally narrowed down by a
+//!   trigram index, see [`SearchMode::Fuzzy`]
+//! - **Query syntax**: Phrases, exclusions, `OR` and field prefixes in user
+//!   queries, parsed by [`parser`] so that typos never reach FTS5 as syntax
 //! - **Advanced queries**: Support for complex FTS5 query syntax
 //!
 //! # Usage
@@ -41,11 +53,22 @@
 //! # }
 //! ```
 
+pub mod fuzzy;
+pub mod parser;
+
+use crate::domain::{NoteError, dir_prefix};
 use crate::error::OraError;
-use crate::watcher::index::{Index, IndexedNote};
-use rusqlite::{Connection, params};
-use std::path::PathBuf;
+use crate::front_matter::FrontMatter;
+use crate::search::fuzzy::{SCORE_MATCH, edit_distance, fuzzy_match, max_typos};
+use crate::search::parser::{Clause, Field, Term, UserQuery};
+use crate::tags::{normalize_tag, tag_with_ancestors};
+use crate::watcher::index::{Index, IndexedNote, NOTE_COLUMNS};
+use rusqlite::types::Value;
+use rusqlite::{Connection, params, params_from_iter};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Component, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// A search query interface for the note index.
 ///
@@ -54,6 +77,30 @@ use std::sync::{Arc, Mutex};
 /// search index and executes various types of searches.
 pub struct Query {
     conn: Arc<Mutex<Connection>>,
+    root: PathBuf,
+    trigram_index: bool,
+    /// The fuzzy matches of the last [`SearchMode::Fuzzy`] query, shared by
+    /// its pages and its count while the index is unchanged.
+    fuzzy_cache: Mutex<Option<FuzzyCache>>,
+}
+
+/// Maximum number of notes checked for a fuzzy search.
+const FUZZY_CANDIDATES: u32 = 500;
+
+/// The ID, kind of match and number of typos of a note found by a fuzzy
+/// search.
+type FuzzyHit = (i64, MatchKind, usize);
+
+/// Fuzzy matches remembered by a [`Query`].
+struct FuzzyCache {
+    query: UserQuery,
+    /// The statement selecting the candidates, and its parameters, which
+    /// include the filters.
+    sql: String,
+    values: Vec<Value>,
+    /// The [`index_version`] for which `matches` is valid.
+    version: (u64, i64),
+    matches: Arc<[FuzzyHit]>,
 }
 
 /// A single search result containing a matched note and metadata.
@@ -68,7 +115,9 @@ pub struct SearchResult {
     /// Relevance score calculated using the BM25 algorithm.
     ///
     /// Lower scores indicate better matches. The exact scale depends on
-    /// the FTS5 configuration and document statistics.
+    /// the FTS5 configuration and document statistics. Results of
+    /// [`SearchMode::Fuzzy`] that BM25 did not find are ranked
+    /// `-1 / (1 + typos)` instead.
     pub rank: f64,
 
     /// Optional highlighted snippet from the note title.
@@ -82,12 +131,220 @@ pub struct SearchResult {
     /// Contains the matched text surrounded by `<mark>` tags when
     /// snippets are requested. `None` if snippets are disabled.
     pub snippet_content: Option<String>,
+
+    /// How the note matched the query.
+    ///
+    /// Only [`SearchMode::Fuzzy`] returns matches other than
+    /// [`MatchKind::FullText`]; those never have snippets.
+    pub matched_by: MatchKind,
+}
+
+/// How a [`SearchResult`] matched its query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// The full-text index matched the query, ranked by BM25.
+    FullText,
+
+    /// Every term appears in the note, but inside other words, like
+    /// `ceiv` in "receive".
+    Substring,
+
+    /// Some terms only match words with typos, like `recieve` for
+    /// "receive".
+    Fuzzy,
+}
+
+/// How [`Query::search_with_options`] matches the terms of a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// Only whole words, or word prefixes for `term*`, as found by the
+    /// full-text index.
+    #[default]
+    Exact,
+
+    /// Whole words first, then notes where every term appears as a
+    /// substring or a word with up to [`max_typos`] typos.
+    ///
+    /// The full-text results come first in the requested order. They are
+    /// followed by the notes only matched this way, in the requested
+    /// order as well, where relevance means fewer typos. Excluded terms
+    /// drop notes containing them as a substring.
+    ///
+    /// At most 500 notes that pass the filters are checked: the ones
+    /// sharing the most three-character sequences with the query if the
+    /// shelf config enables `trigram_index`, or else the most recently
+    /// updated ones. The trigram index is needed to find older notes on
+    /// large shelves, though it may miss words with several typos.
+    ///
+    /// The matches are computed once per query and filters, and reused by
+    /// the other pages and [`Query::count_results_with_options`] until the
+    /// index changes.
+    Fuzzy,
+}
+
+/// A tag together with the number of notes carrying it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    /// The normalized tag, e.g. `project/ora`.
+    pub tag: String,
+
+    /// Number of notes tagged with this tag or any of its nested tags.
+    pub count: u64,
+}
+
+/// The order of search results or note listings.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortBy {
+    /// By BM25 relevance to the query, best matches first.
+    ///
+    /// Listings have no query to rank against and are ordered by path.
+    #[default]
+    Relevance,
+
+    /// By BM25 relevance, boosted for recently updated notes.
+    ///
+    /// A note updated just now ranks as if it matched twice as well; the
+    /// boost falls off with the time since the last update, to half of
+    /// that after `half_life` and towards none for old notes. Listings
+    /// are ordered by update time, most recent first.
+    RelevanceWithRecency {
+        /// Age at which a note keeps half of the boost.
+        half_life: Duration,
+    },
+
+    /// By title, ignoring ASCII case.
+    Title,
+
+    /// By the time the note was first indexed, or for listings, the
+    /// file's creation time where the platform records it.
+    Created,
+
+    /// By the time the note was last updated: the last indexed change,
+    /// or for listings, the file's modification time.
+    Updated,
+
+    /// By file path.
+    Path,
+
+    /// By file size in bytes.
+    Size,
+}
+
+/// Whether a [`SortBy`] order is applied as is or reversed.
+///
+/// Ascending relevance puts the best matches first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    /// Smallest, earliest or best first.
+    #[default]
+    Ascending,
+
+    /// Largest, latest or worst first.
+    Descending,
+}
+
+impl SortDirection {
+    /// Returns the SQL keyword for this direction.
+    fn as_sql(&self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "ASC",
+            SortDirection::Descending => "DESC",
+        }
+    }
+}
+
+/// Options for [`Query::fuzzy_titles`].
+#[derive(Debug, Clone)]
+pub struct FuzzyOptions {
+    /// Maximum number of matches to return.
+    ///
+    /// `None` returns all matches. Defaults to `Some(10)`.
+    pub limit: Option<u32>,
+
+    /// How quickly the bonus for recently updated notes fades.
+    ///
+    /// A note updated just now scores as if one more character had
+    /// matched; after `recency_half_life` half of that is left. `None`
+    /// ignores update times. Defaults to one week.
+    pub recency_half_life: Option<Duration>,
+}
+
+impl Default for FuzzyOptions {
+    fn default() -> Self {
+        Self {
+            limit: Some(10),
+            recency_half_life: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+        }
+    }
+}
+
+/// A note whose title matched a [`Query::fuzzy_titles`] pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TitleMatch {
+    /// The title of the note.
+    pub title: String,
+
+    /// The file path of the note.
+    pub path: PathBuf,
+
+    /// The stable ID of the note.
+    pub id: String,
+
+    /// When the note was last updated in the index.
+    pub updated_at: SystemTime,
+
+    /// The fuzzy match score plus the recency bonus; higher is better.
+    pub score: i64,
+
+    /// Character indices of the matched characters in `title`.
+    pub positions: Vec<usize>,
+}
+
+/// A span of time, open at either end.
+///
+/// The start is inclusive and the end exclusive. Times are compared with
+/// the index timestamps at a resolution of one second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRange {
+    /// Earliest time in the range, or `None` for no lower bound.
+    pub start: Option<SystemTime>,
+
+    /// Time the range ends before, or `None` for no upper bound.
+    pub end: Option<SystemTime>,
+}
+
+impl TimeRange {
+    /// Creates a range of all times from `start` on.
+    pub fn since(start: SystemTime) -> Self {
+        Self {
+            start: Some(start),
+            end: None,
+        }
+    }
+
+    /// Creates a range of all times before `end`.
+    pub fn before(end: SystemTime) -> Self {
+        Self {
+            start: None,
+            end: Some(end),
+        }
+    }
+
+    /// Creates a range from `start` up to, but not including, `end`.
+    pub fn between(start: SystemTime, end: SystemTime) -> Self {
+        Self {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
 }
 
 /// Configuration options for search queries.
 ///
 /// Controls how search results are returned, including pagination,
-/// snippet generation, and result limits.
+/// snippet generation, and result limits. The filters are applied in the
+/// database query, so [`Query::count_results_with_options`] and
+/// `limit`/`offset` pagination take them into account.
 #[derive(Debug, Clone)]
 pub struct SearchOptions {
     /// Maximum number of results to return.
@@ -114,6 +371,63 @@ pub struct SearchOptions {
     /// Only used when `include_snippets` is `true`.
     /// Defaults to `100`.
     pub snippet_length: u32,
+
+    /// Tags every result must carry.
+    ///
+    /// A tag also matches its nested tags, so `project` matches notes
+    /// tagged `project/ora`. Defaults to no tag filter.
+    pub tags: Vec<String>,
+
+    /// Tags no result may carry.
+    ///
+    /// As with `tags`, a tag also excludes its nested tags. Defaults to
+    /// no excluded tags.
+    pub exclude_tags: Vec<String>,
+
+    /// Folder every result must be in, at any depth.
+    ///
+    /// A path relative to the shelf root, without `.` or `..` components;
+    /// searches with any other folder fail with
+    /// [`NoteError::InvalidPath`].
+    /// Defaults to `None`, the whole shelf.
+    pub folder: Option<PathBuf>,
+
+    /// When results must have been first indexed.
+    ///
+    /// Defaults to no restriction.
+    pub created: TimeRange,
+
+    /// When results must have been last indexed with a change.
+    ///
+    /// Defaults to no restriction.
+    pub updated: TimeRange,
+
+    /// Minimum length of the note body in characters, front matter
+    /// excluded.
+    ///
+    /// Defaults to `None`.
+    pub min_length: Option<u64>,
+
+    /// Maximum length of the note body in characters, front matter
+    /// excluded.
+    ///
+    /// Defaults to `None`.
+    pub max_length: Option<u64>,
+
+    /// Order of the results.
+    ///
+    /// Defaults to [`SortBy::Relevance`].
+    pub sort_by: SortBy,
+
+    /// Whether `sort_by` is applied as is or reversed.
+    ///
+    /// Defaults to [`SortDirection::Ascending`].
+    pub sort_direction: SortDirection,
+
+    /// Whether terms must match exactly or may have typos.
+    ///
+    /// Defaults to [`SearchMode::Exact`].
+    pub mode: SearchMode,
 }
 
 impl Default for SearchOptions {
@@ -123,6 +437,16 @@ impl Default for SearchOptions {
             offset: Some(0),
             include_snippets: true,
             snippet_length: 100,
+            tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            folder: None,
+            created: TimeRange::default(),
+            updated: TimeRange::default(),
+            min_length: None,
+            max_length: None,
+            sort_by: SortBy::default(),
+            sort_direction: SortDirection::default(),
+            mode: SearchMode::default(),
         }
     }
 }
@@ -138,6 +462,9 @@ impl Query {
     pub fn new(index: &Index) -> Self {
         Self {
             conn: index.conn.clone(),
+            root: index.root().to_path_buf(),
+            trigram_index: index.config().trigram_index,
+            fuzzy_cache: Mutex::new(None),
         }
     }
 
@@ -147,11 +474,15 @@ impl Query {
     /// use `search_with_options`.
     ///
     /// # Arguments
-    /// * `query` - The search query string (supports FTS5 syntax)
+    /// * `query` - The search query, in the syntax described in [`parser`]
     ///
     /// # Returns
     /// A vector of search results ranked by relevance
     ///
+    /// # Errors
+    /// - [`OraError::Query`] if the query cannot be parsed
+    /// - [`OraError::Db`] if the database query fails
+    ///
     /// # Examples
     /// ```rust,no_run
     /// # use ora_core::search::Query;
@@ -170,28 +501,35 @@ impl Query {
 
     /// Performs a search with custom options.
     ///
-    /// This is the main search method that all other search methods
-    /// eventually call. It supports full FTS5 query syntax and provides
-    /// complete control over result formatting.
+    /// This is the main search method for user-typed queries. The query is
+    /// parsed and escaped before it reaches FTS5, so punctuation such as
+    /// `C++` or `don't` is searched for rather than rejected. Use
+    /// [`Query::advanced_search`] to pass raw FTS5 syntax.
     ///
     /// # Arguments
-    /// * `query` - The search query string (supports FTS5 syntax)
+    /// * `query` - The search query, in the syntax described in [`parser`]
     /// * `options` - Search configuration options
     ///
     /// # Returns
     /// A vector of search results ranked by relevance
     ///
-    /// # FTS5 Query Syntax
+    /// # Query Syntax
     ///
-    /// The query supports SQLite's FTS5 syntax including:
     /// - `term` - Simple term matching
     /// - `"phrase"` - Exact phrase matching
-    /// - `term1 AND term2` - Boolean AND
-    /// - `term1 OR term2` - Boolean OR
-    /// - `term NOT term2` - Boolean NOT
+    /// - `term*` - Prefix matching
+    /// - `term1 term2` or `term1 AND term2` - Both terms
+    /// - `term1 OR term2` - Either term
+    /// - `-term` or `NOT term` - Notes without the term
     /// - `title:term` - Search only title field
     /// - `content:term` - Search only content field
     ///
+    /// # Errors
+    /// - [`OraError::Query`] if the query cannot be parsed
+    /// - [`NoteError::InvalidPath`] if `options.folder` is not a plain
+    ///   relative path
+    /// - [`OraError::Db`] if the database query fails
+    ///
     /// # Examples
     /// ```rust,no_run
     /// # use ora_core::search::{Query, SearchOptions};
@@ -213,55 +551,278 @@ impl Query {
         &self,
         query: &str,
         options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>, OraError> {
+        self.search_query(&UserQuery::parse(query)?, options)
+    }
+
+    /// Runs a parsed query in the mode of `options`.
+    fn search_query(
+        &self,
+        query: &UserQuery,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>, OraError> {
+        let fts = query.to_fts5();
+        if options.mode == SearchMode::Exact {
+            return self.run_search(&fts, options);
+        }
+
+        // Fetch every full-text result up to the end of the page, then
+        // fill the rest of the page with fuzzy matches.
+        let limit = options.limit.unwrap_or(50);
+        let offset = options.offset.unwrap_or(0);
+        let end = offset.saturating_add(limit);
+        let full_text = SearchOptions {
+            limit: Some(end),
+            offset: Some(0),
+            ..options.clone()
+        };
+        let mut results = self.run_search(&fts, &full_text)?;
+        let found = results.len() as u32;
+        results.drain(..found.min(offset) as usize);
+        if found >= end {
+            return Ok(results);
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let matches = self.fuzzy_matches(&conn, query, &fts, options)?;
+        let skip = offset.saturating_sub(found);
+        let take = end - offset.max(found);
+        let before = conn.total_changes();
+        results.extend(self.fetch_fuzzy(&conn, &matches, options, take, skip)?);
+        // Loading the page wrote to a temporary table, which is not a change
+        // of the index. Only those rows are added to the cached version, so
+        // commits from other connections still invalidate it.
+        if let Some(cache) = self.fuzzy_cache.lock().unwrap().as_mut() {
+            cache.version.0 += conn.total_changes() - before;
+        }
+        Ok(results)
+    }
+
+    /// Finds the notes matching `query` with substrings or typos that the
+    /// full-text expression `fts` does not match.
+    ///
+    /// The candidates are the notes proposed by the trigram index, or the
+    /// most recently updated ones without it, at most
+    /// [`FUZZY_CANDIDATES`] either way. The result is remembered until the
+    /// query, the filters or the index change.
+    ///
+    /// # Returns
+    /// The ID, kind of match and number of typos of every such note that
+    /// passes the filters of `options`
+    fn fuzzy_matches(
+        &self,
+        conn: &Connection,
+        query: &UserQuery,
+        fts: &str,
+        options: &SearchOptions,
+    ) -> Result<Arc<[FuzzyHit]>, OraError> {
+        let mut values = vec![Value::from(fts.to_string())];
+        let mut candidates = String::new();
+        if self.trigram_index
+            && let Some(trigrams) = trigram_query(query)
+        {
+            candidates.push_str(
+                " AND n.id IN (SELECT rowid FROM trigrams WHERE trigrams MATCH ? \
+                 ORDER BY rank LIMIT ?)",
+            );
+            values.push(Value::from(trigrams));
+            values.push(Value::from(FUZZY_CANDIDATES));
+        }
+        let filters = self.filters(options, &mut values)?;
+        let limit = if candidates.is_empty() {
+            values.push(Value::from(FUZZY_CANDIDATES));
+            " ORDER BY n.updated_at DESC LIMIT ?"
+        } else {
+            ""
+        };
+        let sql = format!(
+            "SELECT n.id, n.title, n.content FROM notes n
+             WHERE n.id NOT IN (SELECT rowid FROM contents WHERE contents MATCH ?){candidates}{filters}{limit}"
+        );
+
+        let version = index_version(conn)?;
+        if let Some(cache) = self.fuzzy_cache.lock().unwrap().as_ref()
+            && cache.version == version
+            && cache.query == *query
+            && cache.sql == sql
+            && cache.values == values
+        {
+            return Ok(cache.matches.clone());
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(&values), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut matches = Vec::new();
+        for row in rows {
+            let (id, title, content) = row?;
+            if let Some((kind, typos)) = fuzzy_verify(query, &title, &content) {
+                matches.push((id, kind, typos));
+            }
+        }
+        let matches: Arc<[_]> = matches.into();
+        *self.fuzzy_cache.lock().unwrap() = Some(FuzzyCache {
+            query: query.clone(),
+            sql,
+            values,
+            version,
+            matches: matches.clone(),
+        });
+        Ok(matches)
+    }
+
+    /// Loads a page of the notes found by [`Query::fuzzy_matches`] in the
+    /// order of `options`.
+    fn fetch_fuzzy(
+        &self,
+        conn: &Connection,
+        matches: &[FuzzyHit],
+        options: &SearchOptions,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>, OraError> {
+        if matches.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let kinds: HashMap<i64, MatchKind> =
+            matches.iter().map(|&(id, kind, _)| (id, kind)).collect();
+
+        // The matches go through a temporary table of this connection so
+        // that SQL can order them like the full-text results.
+        conn.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS fuzzy_matches (
+                id INTEGER PRIMARY KEY,
+                typos INTEGER NOT NULL
+            );
+            DELETE FROM temp.fuzzy_matches;",
+        )?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut insert =
+                tx.prepare_cached("INSERT INTO temp.fuzzy_matches (id, typos) VALUES (?, ?)")?;
+            for &(id, _, typos) in matches {
+                insert.execute(params![id, typos as i64])?;
+            }
+        }
+        tx.commit()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT n.id, n.title, n.content, n.front_matter, n.path, n.uid,
+                    -1.0 / (1.0 + f.typos) AS rank
+             FROM temp.fuzzy_matches f
+             JOIN notes n ON n.id = f.id
+             ORDER BY {}
+             LIMIT ? OFFSET ?",
+            order_clause(options.sort_by, options.sort_direction)
+        ))?;
+        let rows = stmt.query_map(params![limit, offset], |row| {
+            let front_matter: String = row.get(3)?;
+            let path: String = row.get(4)?;
+            Ok(SearchResult {
+                note: IndexedNote {
+                    title: row.get(1)?,
+                    content: row.get(2)?,
+                    front_matter: FrontMatter::parse(&front_matter),
+                    path: PathBuf::from(path),
+                    id: row.get(5)?,
+                },
+                rank: row.get(6)?,
+                snippet_title: None,
+                snippet_content: None,
+                matched_by: kinds
+                    .get(&row.get::<_, i64>(0)?)
+                    .copied()
+                    .unwrap_or(MatchKind::Fuzzy),
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        drop(stmt);
+        conn.execute_batch("DELETE FROM temp.fuzzy_matches")?;
+        Ok(results)
+    }
+
+    /// Runs an FTS5 `MATCH` expression and collects the results.
+    fn run_search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
     ) -> Result<Vec<SearchResult>, OraError> {
         let conn = self.conn.lock().unwrap();
         let limit = options.limit.unwrap_or(50);
         let offset = options.offset.unwrap_or(0);
 
+        let mut values = vec![Value::from(query.to_string())];
+        let filters = self.filters(options, &mut values)?;
+        values.push(Value::from(limit));
+        values.push(Value::from(offset));
+
         let sql = if options.include_snippets {
             format!(
                 r#"
                 SELECT 
                     n.title,
                     n.content,
+                    n.front_matter,
                     n.path,
+                    n.uid,
                     bm25(contents) as rank,
                     snippet(contents, 0, '<mark>', '</mark>', '...', {}) as title_snippet,
                     snippet(contents, 1, '<mark>', '</mark>', '...', {}) as content_snippet
                 FROM contents
                 JOIN notes n ON n.id = contents.rowid
-                WHERE contents MATCH ?
-                ORDER BY rank
+                WHERE contents MATCH ?{}
+                ORDER BY {}
                 LIMIT ? OFFSET ?
                 "#,
-                options.snippet_length, options.snippet_length
+                options.snippet_length,
+                options.snippet_length,
+                filters,
+                order_clause(options.sort_by, options.sort_direction)
             )
         } else {
-            r#"
+            format!(
+                r#"
             SELECT 
                 n.title,
                 n.content,
+                n.front_matter,
                 n.path,
+                n.uid,
                 bm25(contents) as rank
             FROM contents
             JOIN notes n ON n.id = contents.rowid
-            WHERE contents MATCH ?
-            ORDER BY rank
+            WHERE contents MATCH ?{}
+            ORDER BY {}
             LIMIT ? OFFSET ?
-            "#
-            .to_string()
+            "#,
+                filters,
+                order_clause(options.sort_by, options.sort_direction)
+            )
         };
 
         let mut stmt = conn.prepare(&sql)?;
-        let rows = stmt.query_map(params![query, limit, offset], |row| {
+        let rows = stmt.query_map(params_from_iter(values), |row| {
             let title: String = row.get(0)?;
             let content: String = row.get(1)?;
-            let path_str: String = row.get(2)?;
-            let rank: f64 = row.get(3)?;
+            let front_matter: String = row.get(2)?;
+            let path_str: String = row.get(3)?;
+            let id: String = row.get(4)?;
+            let rank: f64 = row.get(5)?;
 
             let (title_snippet, content_snippet) = if options.include_snippets {
-                let title_snippet: Option<String> = row.get(4).ok();
-                let content_snippet: Option<String> = row.get(5).ok();
+                let title_snippet: Option<String> = row.get(6).ok();
+                let content_snippet: Option<String> = row.get(7).ok();
                 (title_snippet, content_snippet)
             } else {
                 (None, None)
@@ -271,11 +832,14 @@ impl Query {
                 note: IndexedNote {
                     title,
                     content,
+                    front_matter: FrontMatter::parse(&front_matter),
                     path: PathBuf::from(path_str),
+                    id,
                 },
                 rank,
                 snippet_title: title_snippet,
                 snippet_content: content_snippet,
+                matched_by: MatchKind::FullText,
             })
         })?;
 
@@ -303,22 +867,29 @@ impl Query {
 
     /// Searches only within note titles with custom options.
     ///
-    /// Uses FTS5 column-specific syntax to restrict the search to the title field.
+    /// Uses FTS5 column-specific syntax to restrict the search to the title
+    /// field. Terms with an explicit `content:` prefix still search the
+    /// content.
     ///
     /// # Arguments
-    /// * `query` - The search query string
+    /// * `query` - The search query, in the syntax described in [`parser`]
     /// * `options` - Search configuration options
     ///
     /// # Returns
     /// A vector of search results where the query matched in the title
+    ///
+    /// # Errors
+    /// - [`OraError::Query`] if the query cannot be parsed
+    /// - [`NoteError::InvalidPath`] if `options.folder` is not a plain
+    ///   relative path
+    /// - [`OraError::Db`] if the database query fails
     pub fn search_title_with_options(
         &self,
         query: &str,
         options: &SearchOptions,
     ) -> Result<Vec<SearchResult>, OraError> {
-        // For title-only search, we use FTS5 with column-specific syntax
-        let title_query = format!("title:{}", query);
-        self.search_with_options(&title_query, options)
+        let query = UserQuery::parse(query)?.in_field(Field::Title);
+        self.search_query(&query, options)
     }
 
     /// Searches only within note content.
@@ -337,29 +908,36 @@ impl Query {
 
     /// Searches only within note content with custom options.
     ///
-    /// Uses FTS5 column-specific syntax to restrict the search to the content field.
+    /// Uses FTS5 column-specific syntax to restrict the search to the
+    /// content field. Terms with an explicit `title:` prefix still search
+    /// the title.
     ///
     /// # Arguments
-    /// * `query` - The search query string
+    /// * `query` - The search query, in the syntax described in [`parser`]
     /// * `options` - Search configuration options
     ///
     /// # Returns
     /// A vector of search results where the query matched in the content
+    ///
+    /// # Errors
+    /// - [`OraError::Query`] if the query cannot be parsed
+    /// - [`NoteError::InvalidPath`] if `options.folder` is not a plain
+    ///   relative path
+    /// - [`OraError::Db`] if the database query fails
     pub fn search_content_with_options(
         &self,
         query: &str,
         options: &SearchOptions,
     ) -> Result<Vec<SearchResult>, OraError> {
-        // For content-only search, we use FTS5 with column-specific syntax
-        let content_query = format!("content:{}", query);
-        self.search_with_options(&content_query, options)
+        let query = UserQuery::parse(query)?.in_field(Field::Content);
+        self.search_query(&query, options)
     }
 
     /// Performs an advanced search using raw FTS5 query syntax.
     ///
     /// This method allows full control over the FTS5 query syntax for complex
     /// searches that might include boolean operators, phrase matching, and
-    /// column-specific searches.
+    /// column-specific searches. The query is passed to FTS5 unchanged.
     ///
     /// # Arguments
     /// * `query` - Raw FTS5 query string
@@ -368,6 +946,12 @@ impl Query {
     /// # Returns
     /// A vector of search results ranked by relevance
     ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if `options.folder` is not a plain
+    ///   relative path
+    /// - [`OraError::Db`] if the query is not valid FTS5 syntax or the
+    ///   database query fails
+    ///
     /// # Examples
     /// ```rust,no_run
     /// # use ora_core::search::{Query, SearchOptions};
@@ -389,7 +973,7 @@ impl Query {
         query: &str,
         options: &SearchOptions,
     ) -> Result<Vec<SearchResult>, OraError> {
-        self.search_with_options(query, options)
+        self.run_search(query, options)
     }
 
     /// Counts the total number of results for a query.
@@ -398,11 +982,15 @@ impl Query {
     /// total number of matches before fetching a specific page.
     ///
     /// # Arguments
-    /// * `query` - The search query string
+    /// * `query` - The search query, in the syntax described in [`parser`]
     ///
     /// # Returns
     /// The total number of matching notes
     ///
+    /// # Errors
+    /// - [`OraError::Query`] if the query cannot be parsed
+    /// - [`OraError::Db`] if the database query fails
+    ///
     /// # Examples
     /// ```rust,no_run
     /// # use ora_core::search::Query;
@@ -417,23 +1005,63 @@ impl Query {
     /// # }
     /// ```
     pub fn count_results(&self, query: &str) -> Result<u64, OraError> {
+        self.count_results_with_options(query, &SearchOptions::default())
+    }
+
+    /// Counts the total number of results for a query with the filters of
+    /// `options`.
+    ///
+    /// `limit`, `offset` and the snippet settings are ignored, so the count
+    /// is the number of results across all pages of
+    /// [`Query::search_with_options`] with the same options.
+    ///
+    /// # Arguments
+    /// * `query` - The search query, in the syntax described in [`parser`]
+    /// * `options` - Search options whose filters apply
+    ///
+    /// # Returns
+    /// The total number of matching notes
+    ///
+    /// # Errors
+    /// - [`OraError::Query`] if the query cannot be parsed
+    /// - [`NoteError::InvalidPath`] if `options.folder` is not a plain
+    ///   relative path
+    /// - [`OraError::Db`] if the database query fails
+    pub fn count_results_with_options(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<u64, OraError> {
+        let query = UserQuery::parse(query)?;
+        let fts = query.to_fts5();
+        let mut values = vec![Value::from(fts.clone())];
+        let filters = self.filters(options, &mut values)?;
+
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT COUNT(*) as count
-            FROM contents
-            WHERE contents MATCH ?
-            "#,
-        )?;
+        let count: i64 = conn
+            .prepare(&format!(
+                r#"
+                SELECT COUNT(*) as count
+                FROM contents
+                JOIN notes n ON n.id = contents.rowid
+                WHERE contents MATCH ?{filters}
+                "#
+            ))?
+            .query_row(params_from_iter(values), |row| row.get(0))?;
 
-        let count: i64 = stmt.query_row(params![query], |row| row.get(0))?;
-        Ok(count as u64)
+        let fuzzy = match options.mode {
+            SearchMode::Exact => 0,
+            SearchMode::Fuzzy => self.fuzzy_matches(&conn, &query, &fts, options)?.len(),
+        };
+        Ok(count as u64 + fuzzy as u64)
     }
 
     /// Provides auto-complete suggestions for note titles.
     ///
     /// Searches for note titles that start with the given prefix, useful
     /// for implementing auto-complete functionality in user interfaces.
+    /// For a quick switcher that forgives skipped characters, use
+    /// [`Query::fuzzy_titles`].
     ///
     /// # Arguments
     /// * `prefix` - The prefix to match against note titles
@@ -469,7 +1097,7 @@ impl Query {
             "#,
         )?;
 
-        let rows = stmt.query_map(params![prefix, limit], |row| Ok(row.get::<_, String>(0)?))?;
+        let rows = stmt.query_map(params![prefix, limit], |row| row.get::<_, String>(0))?;
 
         let mut suggestions = Vec::new();
         for row in rows {
@@ -478,4 +1106,450 @@ impl Query {
 
         Ok(suggestions)
     }
+
+    /// Finds notes whose title fuzzily matches `pattern`, best first.
+    ///
+    /// Titles match if they contain the characters of the pattern in
+    /// order, as described in [`fuzzy`]: `rsprog` finds "Rust Programming
+    /// Notes". Matches are ordered by score, which includes a bonus for
+    /// recently updated notes, then by update time, most recent first. An
+    /// empty pattern matches every note, so the most recent notes come
+    /// first.
+    ///
+    /// # Arguments
+    /// * `pattern` - The characters typed by the user
+    /// * `options` - Result limit and recency weighting
+    ///
+    /// # Returns
+    /// The matching notes with scores and matched character positions
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use ora_core::search::{FuzzyOptions, Query};
+    /// # use ora_core::watcher::index::Index;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let index = Index::new(Path::new("/path/to/shelf"))?;
+    /// # let query = Query::new(&index);
+    /// for found in query.fuzzy_titles("rsprog", &FuzzyOptions::default())? {
+    ///     println!("{} {:?}", found.title, found.positions);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fuzzy_titles(
+        &self,
+        pattern: &str,
+        options: &FuzzyOptions,
+    ) -> Result<Vec<TitleMatch>, OraError> {
+        let candidates: Vec<(String, String, String, i64)> = {
+            let conn = self.conn.lock().unwrap();
+            // Narrow down with LIKE, which only folds ASCII case.
+            let (filter, values) = if pattern.is_ascii() {
+                let mut like = String::from("%");
+                for c in pattern.chars().filter(|c| !c.is_whitespace()) {
+                    if matches!(c, '%' | '_' | '\\') {
+                        like.push('\\');
+                    }
+                    like.push(c);
+                    like.push('%');
+                }
+                (" WHERE title LIKE ? ESCAPE '\\'", vec![Value::from(like)])
+            } else {
+                ("", Vec::new())
+            };
+            let mut stmt = conn.prepare(&format!(
+                "SELECT title, path, uid, CAST(strftime('%s', updated_at) AS INTEGER)
+                 FROM notes{filter}"
+            ))?;
+            stmt.query_map(params_from_iter(values), |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<_, _>>()?
+        };
+
+        let now = SystemTime::now();
+        let mut matches: Vec<TitleMatch> = candidates
+            .into_iter()
+            .filter_map(|(title, path, id, updated)| {
+                let found = fuzzy_match(pattern, &title)?;
+                let updated_at = UNIX_EPOCH + Duration::from_secs(updated.max(0) as u64);
+                let recency = options.recency_half_life.map_or(0, |half_life| {
+                    let age = now.duration_since(updated_at).unwrap_or_default();
+                    let half_life = half_life.as_secs_f64().max(1.0);
+                    (SCORE_MATCH as f64 * half_life / (half_life + age.as_secs_f64())).round()
+                        as i64
+                });
+                Some(TitleMatch {
+                    title,
+                    path: PathBuf::from(path),
+                    id,
+                    updated_at,
+                    score: found.score + recency,
+                    positions: found.positions,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(b.updated_at.cmp(&a.updated_at))
+                .then(a.title.len().cmp(&b.title.len()))
+                .then(a.path.cmp(&b.path))
+        });
+        if let Some(limit) = options.limit {
+            matches.truncate(limit as usize);
+        }
+        Ok(matches)
+    }
+
+    /// Lists all tags in the index with the number of notes carrying them.
+    ///
+    /// Nested tags also count towards their parents, so a note tagged
+    /// `project/ora` is counted for both `project/ora` and `project`.
+    ///
+    /// # Returns
+    /// All tags sorted alphabetically
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use ora_core::search::Query;
+    /// # use ora_core::watcher::index::Index;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let index = Index::new(Path::new("/path/to/shelf"))?;
+    /// # let query = Query::new(&index);
+    /// for tag in query.tags()? {
+    ///     println!("#{} ({})", tag.tag, tag.count);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tags(&self) -> Result<Vec<TagCount>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT tag, note_id FROM note_tags")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+
+        let mut notes_by_tag: BTreeMap<String, HashSet<i64>> = BTreeMap::new();
+        for row in rows {
+            let (tag, note_id) = row?;
+            for tag in tag_with_ancestors(&tag) {
+                notes_by_tag
+                    .entry(tag.to_string())
+                    .or_default()
+                    .insert(note_id);
+            }
+        }
+
+        Ok(notes_by_tag
+            .into_iter()
+            .map(|(tag, notes)| TagCount {
+                tag,
+                count: notes.len() as u64,
+            })
+            .collect())
+    }
+
+    /// Lists all notes carrying `tag` or any of its nested tags.
+    ///
+    /// The tag is normalized first, so `#Project` and `project` are
+    /// equivalent.
+    ///
+    /// # Arguments
+    /// * `tag` - The tag to look for, with or without a leading `#`
+    ///
+    /// # Returns
+    /// Matching notes ordered by title
+    pub fn notes_with_tag(&self, tag: &str) -> Result<Vec<IndexedNote>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut values = Vec::new();
+        let filters = tag_filters(&[tag.to_string()], &mut values);
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE 1{filters}
+             ORDER BY n.title"
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values), IndexedNote::from_row)?;
+
+        let mut notes = Vec::new();
+        for row in rows {
+            notes.push(row?);
+        }
+        Ok(notes)
+    }
+
+    /// Builds `AND ...` clauses on note `n` for the filters of `options`.
+    ///
+    /// Pushes the bound values onto `values` in order.
+    ///
+    /// # Errors
+    /// Returns [`NoteError::InvalidPath`] if the folder is not a plain
+    /// relative path.
+    fn filters(
+        &self,
+        options: &SearchOptions,
+        values: &mut Vec<Value>,
+    ) -> Result<String, NoteError> {
+        let mut sql = tag_filters(&options.tags, values);
+        sql.push_str(&excluded_tag_filters(&options.exclude_tags, values));
+
+        if let Some(folder) = &options.folder {
+            if !folder
+                .components()
+                .all(|part| matches!(part, Component::Normal(_)))
+            {
+                return Err(NoteError::InvalidPath);
+            }
+            let dir = self.root.join(folder);
+            sql.push_str(" AND substr(n.path, 1, length(?)) = ?");
+            values.extend(std::iter::repeat_n(Value::from(dir_prefix(&dir)), 2));
+        }
+
+        sql.push_str(&time_filters("n.created_at", &options.created, values));
+        sql.push_str(&time_filters("n.updated_at", &options.updated, values));
+
+        if let Some(min) = options.min_length {
+            sql.push_str(" AND length(n.content) >= ?");
+            values.push(Value::from(min as i64));
+        }
+        if let Some(max) = options.max_length {
+            sql.push_str(" AND length(n.content) <= ?");
+            values.push(Value::from(max as i64));
+        }
+        Ok(sql)
+    }
+}
+
+/// Identifies the state of the index as seen through `conn`: the rows
+/// changed through `conn` itself, and the data version, which changes when
+/// other connections commit.
+fn index_version(conn: &Connection) -> rusqlite::Result<(u64, i64)> {
+    let data_version = conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+    Ok((conn.total_changes(), data_version))
+}
+
+/// Builds an FTS5 expression for the `trigrams` table matching notes that
+/// share three-character sequences with `query`.
+///
+/// Only groups whose every clause has three characters or more narrow
+/// down the candidates; `None` if there is no such group.
+fn trigram_query(query: &UserQuery) -> Option<String> {
+    let mut trigrams = BTreeSet::new();
+    for group in query.groups() {
+        let group_trigrams: Vec<Vec<String>> = group
+            .iter()
+            .map(|clause| {
+                let chars: Vec<char> = clause_text(clause).to_lowercase().chars().collect();
+                chars
+                    .windows(3)
+                    .map(|window| window.iter().collect::<String>())
+                    .filter(|trigram| !trigram.contains(char::is_whitespace))
+                    .collect()
+            })
+            .collect();
+        if group_trigrams.iter().all(|t| !t.is_empty()) {
+            trigrams.extend(group_trigrams.into_iter().flatten());
+        }
+    }
+    if trigrams.is_empty() {
+        return None;
+    }
+
+    let quoted: Vec<String> = trigrams
+        .iter()
+        .map(|trigram| format!("\"{}\"", trigram.replace('"', "\"\"")))
+        .collect();
+    Some(quoted.join(" OR "))
+}
+
+/// Checks whether a note matches every group of `query` with substrings
+/// or typos and none of its excluded clauses as a substring.
+///
+/// # Returns
+/// The kind of match and the total number of typos, or `None` if the
+/// note does not match
+fn fuzzy_verify(query: &UserQuery, title: &str, content: &str) -> Option<(MatchKind, usize)> {
+    let title = title.to_lowercase();
+    let content = content.to_lowercase();
+    let title_words = words(&title);
+    let content_words = words(&content);
+
+    let typos = |clause: &Clause| {
+        let fields = match clause.field.or(query.default_field()) {
+            Some(Field::Title) => vec![(&title, &title_words)],
+            Some(Field::Content) => vec![(&content, &content_words)],
+            None => vec![(&title, &title_words), (&content, &content_words)],
+        };
+        fields
+            .into_iter()
+            .filter_map(|(text, text_words)| clause_typos(clause, text, text_words))
+            .min()
+    };
+
+    if query
+        .excluded()
+        .iter()
+        .any(|clause| typos(clause) == Some(0))
+    {
+        return None;
+    }
+
+    let mut total = 0;
+    for group in query.groups() {
+        total += group.iter().filter_map(typos).min()?;
+    }
+    let kind = if total == 0 {
+        MatchKind::Substring
+    } else {
+        MatchKind::Fuzzy
+    };
+    Some((kind, total))
+}
+
+/// Returns the fewest typos with which `clause` matches a lower-cased
+/// field, 0 if it appears as a substring.
+///
+/// Phrases only match as substrings; every word of other terms must match
+/// a word of the field within [`max_typos`], or for `term*` the start of
+/// one.
+fn clause_typos(clause: &Clause, text: &str, text_words: &[&str]) -> Option<usize> {
+    let term = clause_text(clause).to_lowercase();
+    if text.contains(&term) {
+        return Some(0);
+    }
+    if matches!(clause.term, Term::Phrase(_)) {
+        return None;
+    }
+
+    let mut total = 0;
+    for word in words(&term) {
+        let len = word.chars().count();
+        let allowed = max_typos(len);
+        total += text_words
+            .iter()
+            .filter_map(|candidate| {
+                let candidate = match clause.term {
+                    Term::Prefix(_) => match candidate.char_indices().nth(len) {
+                        Some((end, _)) => &candidate[..end],
+                        None => candidate,
+                    },
+                    _ => candidate,
+                };
+                if candidate.chars().count().abs_diff(len) > allowed {
+                    return None;
+                }
+                Some(edit_distance(word, candidate)).filter(|&typos| typos <= allowed)
+            })
+            .min()?;
+    }
+    Some(total)
+}
+
+/// Returns the text a clause searches for.
+fn clause_text(clause: &Clause) -> &str {
+    match &clause.term {
+        Term::Word(text) | Term::Prefix(text) | Term::Phrase(text) => text,
+    }
+}
+
+/// Splits text into its words: runs of letters and digits.
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Builds `AND EXISTS (...)` clauses requiring every tag in `tags`.
+///
+/// Pushes the bound values onto `values` in order. Tags that are not valid
+/// after normalization match nothing.
+fn tag_filters(tags: &[String], values: &mut Vec<Value>) -> String {
+    let mut sql = String::new();
+    for tag in tags {
+        let Some(tag) = normalize_tag(tag) else {
+            sql.push_str(" AND 0");
+            continue;
+        };
+
+        sql.push_str(
+            " AND EXISTS (SELECT 1 FROM note_tags t WHERE t.note_id = n.id \
+             AND (t.tag = ? OR substr(t.tag, 1, length(?) + 1) = ? || '/'))",
+        );
+        values.extend(std::iter::repeat_n(Value::from(tag), 3));
+    }
+    sql
+}
+
+/// Builds the `ORDER BY` expressions of a search for `sort_by`.
+///
+/// Ties are broken by path so that pages do not overlap.
+fn order_clause(sort_by: SortBy, direction: SortDirection) -> String {
+    let key = match sort_by {
+        SortBy::Relevance => "rank".to_string(),
+        SortBy::RelevanceWithRecency { half_life } => {
+            // BM25 ranks are negative, so scaling by 1 to 2 moves recent
+            // notes towards the top. Hyperbolic decay needs no SQLite math
+            // functions.
+            let half_life = half_life.as_secs_f64().max(1.0);
+            format!(
+                "rank * (1.0 + {half_life:.1} / ({half_life:.1} + \
+                 max(0.0, (julianday('now') - julianday(n.updated_at)) * 86400.0)))"
+            )
+        }
+        SortBy::Title => "n.title COLLATE NOCASE".to_string(),
+        SortBy::Created => "n.created_at".to_string(),
+        SortBy::Updated => "n.updated_at".to_string(),
+        SortBy::Path => "n.path".to_string(),
+        SortBy::Size => "COALESCE(n.size, length(CAST(n.content AS BLOB)))".to_string(),
+    };
+    let direction = direction.as_sql();
+    format!("{key} {direction}, n.path {direction}")
+}
+
+/// Builds `AND NOT EXISTS (...)` clauses rejecting every tag in `tags`.
+///
+/// Pushes the bound values onto `values` in order. Tags that are not valid
+/// after normalization exclude nothing.
+fn excluded_tag_filters(tags: &[String], values: &mut Vec<Value>) -> String {
+    let mut sql = String::new();
+    for tag in tags.iter().filter_map(|tag| normalize_tag(tag)) {
+        sql.push_str(
+            " AND NOT EXISTS (SELECT 1 FROM note_tags t WHERE t.note_id = n.id \
+             AND (t.tag = ? OR substr(t.tag, 1, length(?) + 1) = ? || '/'))",
+        );
+        values.extend(std::iter::repeat_n(Value::from(tag), 3));
+    }
+    sql
+}
+
+/// Builds clauses keeping the timestamp `column` within `range`.
+///
+/// Pushes the bound values onto `values` in order.
+fn time_filters(column: &str, range: &TimeRange, values: &mut Vec<Value>) -> String {
+    let mut sql = String::new();
+    for (bound, operator) in [(range.start, ">="), (range.end, "<")] {
+        if let Some(time) = bound {
+            sql.push_str(&format!(
+                " AND {column} {operator} datetime(?, 'unixepoch')"
+            ));
+            values.push(Value::from(unix_seconds(time)));
+        }
+    }
+    sql
+}
+
+/// Converts a time to whole seconds since the Unix epoch, negative before it.
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
 }
@@ -0,0 +1,241 @@
+//! Fuzzy matching of note titles, as in a quick switcher.
+//!
+//! A pattern matches a text if its characters appear in the text in order,
+//! not necessarily next to each other: `rsprog` matches
+//! "**R**u**s**t **Prog**ramming Notes". Whitespace in the pattern is
+//! ignored. Matching ignores case unless the pattern contains an upper-case
+//! letter.
+//!
+//! Among all ways to match, [`fuzzy_match`] picks the one with the highest
+//! score, in the style of fzf:
+//!
+//! - Every matched character scores [`SCORE_MATCH`]
+//! - Characters at the start of a word get a bonus, the first character of
+//!   the text and camelCase humps included
+//! - Runs of consecutive matched characters get a bonus, at least as large
+//!   as that of the character starting the run
+//! - Gaps between matched characters cost a penalty for starting the gap
+//!   and a smaller one for each further character skipped
+//!
+//! [`Query::fuzzy_titles`](crate::search::Query::fuzzy_titles) applies this
+//! to the titles in the index and weighs in how recently notes were updated.
+//!
+//! For misspelled words, [`edit_distance`] counts the typos between two
+//! words; [`SearchMode::Fuzzy`](crate::search::SearchMode::Fuzzy) accepts
+//! up to [`max_typos`] of them per search term.
+
+/// Score of a matched character.
+pub const SCORE_MATCH: i64 = 16;
+
+/// Penalty for the first character of a gap between matches.
+const GAP_START: i64 = -3;
+
+/// Penalty for each further character of a gap.
+const GAP_EXTENSION: i64 = -1;
+
+/// Bonus for matching the first character of a word.
+const BONUS_BOUNDARY: i64 = 8;
+
+/// Bonus for matching an upper-case letter after a lower-case one, or a
+/// digit after a letter.
+const BONUS_CAMEL: i64 = 7;
+
+/// Minimum bonus of a character matched right after the previous one.
+const BONUS_CONSECUTIVE: i64 = 4;
+
+/// Multiplier of the bonus of the first pattern character.
+const FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// The result of a successful [`fuzzy_match`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// How well the pattern matched; higher is better.
+    pub score: i64,
+
+    /// Indices of the matched characters in the text, in characters (not
+    /// bytes), in increasing order. Useful for highlighting.
+    pub positions: Vec<usize>,
+}
+
+/// Matches `pattern` against `text` as a subsequence.
+///
+/// # Returns
+/// The best-scoring match, or `None` if the pattern is not a subsequence
+/// of the text. An empty pattern matches any text with a score of zero.
+///
+/// # Examples
+/// ```rust
+/// use ora_core::search::fuzzy::fuzzy_match;
+///
+/// let found = fuzzy_match("rsprog", "Rust Programming Notes").unwrap();
+/// assert_eq!(found.positions, vec![0, 2, 5, 6, 7, 8]);
+/// assert!(fuzzy_match("rsprog", "Python Notes").is_none());
+/// ```
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.chars().collect();
+    let (m, n) = (pattern.len(), text.len());
+    if m == 0 {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    if m > n {
+        return None;
+    }
+
+    let case_sensitive = pattern.iter().any(|c| c.is_uppercase());
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let pattern: Vec<char> = pattern.into_iter().map(fold).collect();
+    let folded: Vec<char> = text.iter().copied().map(fold).collect();
+
+    // Quick rejection before the quadratic table.
+    let mut next = 0;
+    for c in &folded {
+        if next < m && *c == pattern[next] {
+            next += 1;
+        }
+    }
+    if next < m {
+        return None;
+    }
+
+    let bonus: Vec<i64> = (0..n)
+        .map(|j| char_bonus(j.checked_sub(1).map(|k| text[k]), text[j]))
+        .collect();
+
+    // score[i][j]: best score with pattern[..=i] matched and pattern[i] at
+    // text[j]. from[i][j]: where pattern[i - 1] was matched on that path.
+    // run[i][j]: bonus of the first character of the run of consecutive
+    // matches ending there.
+    let mut score = vec![vec![None::<i64>; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+    let mut run = vec![vec![0i64; n]; m];
+    for i in 0..m {
+        // Best previous-row score that a gap can reach the current
+        // column from, with the gap penalty applied, and its column.
+        let mut gap: Option<(i64, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let start = score[i - 1][j - 2].map(|s| (s + GAP_START, j - 2));
+                gap = match (gap.map(|(s, k)| (s + GAP_EXTENSION, k)), start) {
+                    (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+                    (a, b) => a.or(b),
+                };
+            }
+            if folded[j] != pattern[i] {
+                continue;
+            }
+
+            run[i][j] = bonus[j];
+            if i == 0 {
+                score[i][j] = Some(SCORE_MATCH + bonus[j] * FIRST_CHAR_MULTIPLIER);
+                continue;
+            }
+            let run_bonus = run[i - 1][j - 1].max(BONUS_CONSECUTIVE);
+            let consecutive =
+                score[i - 1][j - 1].map(|s| (s + SCORE_MATCH + bonus[j].max(run_bonus), j - 1));
+            let gapped = gap.map(|(s, k)| (s + SCORE_MATCH + bonus[j], k));
+            let best = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((s, k)) = best {
+                score[i][j] = Some(s);
+                from[i][j] = k;
+                if k + 1 == j {
+                    run[i][j] = run[i - 1][j - 1];
+                }
+            }
+        }
+    }
+
+    let (best, end) = (0..n)
+        .filter_map(|j| score[m - 1][j].map(|s| (s, j)))
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+
+    let mut positions = vec![0; m];
+    let mut j = end;
+    for i in (0..m).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+/// Returns the bonus for matching `current`, given the character before it.
+fn char_bonus(previous: Option<char>, current: char) -> i64 {
+    let Some(previous) = previous else {
+        return BONUS_BOUNDARY;
+    };
+    if !current.is_alphanumeric() {
+        0
+    } else if !previous.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if (previous.is_lowercase() && current.is_uppercase())
+        || (previous.is_alphabetic() && current.is_numeric())
+    {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// Counts the edits turning `a` into `b`.
+///
+/// An edit inserts, deletes or replaces one character, or swaps two
+/// adjacent ones (optimal string alignment distance). Characters are
+/// compared as they are; lower-case both words to ignore case.
+///
+/// # Examples
+/// ```rust
+/// use ora_core::search::fuzzy::edit_distance;
+///
+/// assert_eq!(edit_distance("recieve", "receive"), 1);
+/// assert_eq!(edit_distance("kitten", "sitting"), 3);
+/// ```
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Rows i - 2, i - 1 and i of the distance table.
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// Returns how many typos a search term of `len` characters may contain.
+///
+/// Terms of up to three characters must match exactly, terms of up to
+/// seven may have one typo and longer terms two.
+pub fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
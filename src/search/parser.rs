@@ -0,0 +1,417 @@
+//! Parser for user-typed search queries.
+//!
+//! [`Query::search`](crate::search::Query::search) and friends do not pass
+//! their input to FTS5 as is: characters like `+`, `'`, `:` or an unbalanced
+//! `"` are FTS5 syntax and would make the database reject the query. Instead
+//! the input is parsed into a [`UserQuery`] and rendered as an FTS5
+//! expression in which every term is quoted.
+//!
+//! # Syntax
+//!
+//! - `rust programming` - notes containing both words
+//! - `"exact phrase"` - the words in this order
+//! - `prog*` - words starting with `prog`
+//! - `rust OR go` - either word; binds tighter than the implicit AND, so
+//!   `notes rust OR go` means `notes AND (rust OR go)`
+//! - `-draft` or `NOT draft` - notes not containing the word
+//! - `title:rust`, `content:"exact phrase"` - restrict a term to a field
+//! - `AND` between terms is allowed and changes nothing
+//!
+//! Operators must be written in upper case; `or` is an ordinary word. A
+//! `field:` prefix with any other field name is part of the word, so
+//! `foo:bar` searches for `foo` and `bar`. Words without any letter or
+//! digit, like `++`, are ignored.
+//!
+//! Input that cannot be understood is reported as a [`QueryParseError`]
+//! holding the byte position of the problem.
+
+use std::fmt;
+use thiserror::Error;
+
+/// An error in a user search query.
+///
+/// Every variant carries the byte offset in the input where the problem
+/// was found.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum QueryParseError {
+    /// The query has no terms to search for.
+    #[error("query is empty")]
+    Empty,
+
+    /// A `"` phrase is not closed.
+    #[error("unterminated quote at position {position}")]
+    UnterminatedQuote { position: usize },
+
+    /// A `-`, `NOT` or `field:` prefix is not followed by a term.
+    #[error("missing search term at position {position}")]
+    MissingTerm { position: usize },
+
+    /// An `AND` or `OR` without a term on both sides.
+    #[error("{operator} needs a term on both sides at position {position}")]
+    MisplacedOperator {
+        operator: &'static str,
+        position: usize,
+    },
+
+    /// An excluded term inside an `OR` group, as in `rust OR -go`.
+    #[error("excluded terms cannot be combined with OR at position {position}")]
+    ExclusionInOr { position: usize },
+
+    /// The query only excludes terms, which FTS5 cannot search for.
+    #[error("query only excludes terms at position {position}")]
+    OnlyExclusions { position: usize },
+}
+
+impl QueryParseError {
+    /// Returns the byte offset in the input where the error was found.
+    pub fn position(&self) -> usize {
+        match self {
+            QueryParseError::Empty => 0,
+            QueryParseError::UnterminatedQuote { position }
+            | QueryParseError::MissingTerm { position }
+            | QueryParseError::MisplacedOperator { position, .. }
+            | QueryParseError::ExclusionInOr { position }
+            | QueryParseError::OnlyExclusions { position } => *position,
+        }
+    }
+}
+
+/// A searchable field of a note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// The note title.
+    Title,
+
+    /// The note body.
+    Content,
+}
+
+impl Field {
+    /// Returns the FTS5 column name of this field.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Content => "content",
+        }
+    }
+
+    /// Parses a field name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("title") {
+            Some(Field::Title)
+        } else if name.eq_ignore_ascii_case("content") {
+            Some(Field::Content)
+        } else {
+            None
+        }
+    }
+}
+
+/// What a [`Clause`] matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    /// A single word as typed, which may still contain punctuation.
+    Word(String),
+
+    /// Words starting with the given text (`prog*`).
+    Prefix(String),
+
+    /// Words in this exact order (`"exact phrase"`).
+    Phrase(String),
+}
+
+/// A single term of a [`UserQuery`], optionally restricted to one field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clause {
+    /// Field the term must appear in, or `None` for any field.
+    pub field: Option<Field>,
+
+    /// The term to match.
+    pub term: Term,
+}
+
+impl Clause {
+    /// Renders the clause as an FTS5 expression with the term quoted.
+    fn to_fts5(&self, default_field: Option<Field>) -> String {
+        let term = match &self.term {
+            Term::Word(text) | Term::Phrase(text) => quote(text),
+            Term::Prefix(text) => format!("{}*", quote(text)),
+        };
+        match self.field.or(default_field) {
+            Some(field) => format!("{} : {term}", field.as_str()),
+            None => term,
+        }
+    }
+}
+
+/// A parsed user search query.
+///
+/// A note matches if it matches at least one clause of every group and
+/// none of the excluded clauses.
+///
+/// # Examples
+/// ```rust
+/// use ora_core::search::parser::UserQuery;
+///
+/// let query = UserQuery::parse("C++ \"don't panic\" -draft")?;
+/// assert_eq!(query.to_fts5(), "(\"C++\" AND \"don't panic\") NOT \"draft\"");
+/// # Ok::<(), ora_core::search::parser::QueryParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserQuery {
+    groups: Vec<Vec<Clause>>,
+    excluded: Vec<Clause>,
+    default_field: Option<Field>,
+}
+
+/// A token of the query syntax.
+enum Token {
+    Clause { clause: Clause, negated: bool },
+    And,
+    Or,
+}
+
+/// What came before the current token.
+enum Previous {
+    Start,
+    Included,
+    Excluded,
+    Operator(&'static str, usize),
+}
+
+impl UserQuery {
+    /// Parses a query in the syntax described in the [module
+    /// documentation](self).
+    ///
+    /// # Errors
+    /// Returns a [`QueryParseError`] pointing at the first problem in
+    /// `input`
+    pub fn parse(input: &str) -> Result<Self, QueryParseError> {
+        let mut groups: Vec<Vec<Clause>> = Vec::new();
+        let mut excluded = Vec::new();
+        let mut first_exclusion = None;
+        let mut previous = Previous::Start;
+
+        for (position, token) in tokenize(input)? {
+            match token {
+                Token::And | Token::Or => {
+                    let operator = if matches!(token, Token::Or) {
+                        "OR"
+                    } else {
+                        "AND"
+                    };
+                    if matches!(previous, Previous::Start | Previous::Operator(..)) {
+                        return Err(QueryParseError::MisplacedOperator { operator, position });
+                    }
+                    if operator == "OR" && matches!(previous, Previous::Excluded) {
+                        return Err(QueryParseError::ExclusionInOr { position });
+                    }
+                    previous = Previous::Operator(operator, position);
+                }
+                Token::Clause { clause, negated } => {
+                    let after_or = matches!(previous, Previous::Operator("OR", _));
+                    if negated {
+                        if after_or {
+                            return Err(QueryParseError::ExclusionInOr { position });
+                        }
+                        first_exclusion.get_or_insert(position);
+                        excluded.push(clause);
+                        previous = Previous::Excluded;
+                    } else {
+                        match groups.last_mut() {
+                            Some(group) if after_or => group.push(clause),
+                            _ => groups.push(vec![clause]),
+                        }
+                        previous = Previous::Included;
+                    }
+                }
+            }
+        }
+
+        if let Previous::Operator(operator, position) = previous {
+            return Err(QueryParseError::MisplacedOperator { operator, position });
+        }
+        if groups.is_empty() {
+            return Err(match first_exclusion {
+                Some(position) => QueryParseError::OnlyExclusions { position },
+                None => QueryParseError::Empty,
+            });
+        }
+
+        Ok(UserQuery {
+            groups,
+            excluded,
+            default_field: None,
+        })
+    }
+
+    /// Returns the groups of alternatives a note must match, one clause of
+    /// each group.
+    pub fn groups(&self) -> &[Vec<Clause>] {
+        &self.groups
+    }
+
+    /// Returns the clauses a note must not match.
+    pub fn excluded(&self) -> &[Clause] {
+        &self.excluded
+    }
+
+    /// Returns the field that clauses without a `field:` prefix are
+    /// restricted to, or `None` for any field.
+    pub fn default_field(&self) -> Option<Field> {
+        self.default_field
+    }
+
+    /// Restricts clauses without a `field:` prefix to `field`.
+    pub fn in_field(mut self, field: Field) -> Self {
+        self.default_field = Some(field);
+        self
+    }
+
+    /// Renders the query as an FTS5 `MATCH` expression.
+    ///
+    /// Every term is quoted, so the result is always valid FTS5 syntax.
+    pub fn to_fts5(&self) -> String {
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| {
+                let alternatives: Vec<String> = group
+                    .iter()
+                    .map(|clause| clause.to_fts5(self.default_field))
+                    .collect();
+                if alternatives.len() == 1 {
+                    alternatives.into_iter().next().unwrap_or_default()
+                } else {
+                    format!("({})", alternatives.join(" OR "))
+                }
+            })
+            .collect();
+
+        let included = groups.join(" AND ");
+        if self.excluded.is_empty() {
+            return included;
+        }
+
+        let mut fts = if groups.len() > 1 {
+            format!("({included})")
+        } else {
+            included
+        };
+        for clause in &self.excluded {
+            fts.push_str(" NOT ");
+            fts.push_str(&clause.to_fts5(self.default_field));
+        }
+        fts
+    }
+}
+
+impl fmt::Display for UserQuery {
+    /// Formats the query as its FTS5 expression.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_fts5())
+    }
+}
+
+/// Splits the input into tokens, each with its byte position.
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, QueryParseError> {
+    let mut tokens = Vec::new();
+    let mut negate_next: Option<usize> = None;
+    let mut i = 0;
+
+    while i < input.len() {
+        let rest = &input[i..];
+        let Some(c) = rest.chars().next() else { break };
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+
+        let start = negate_next.unwrap_or(i);
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..word_end];
+
+        // Operators are whole upper-case words.
+        match word {
+            "AND" | "OR" if negate_next.is_none() => {
+                let token = if word == "OR" { Token::Or } else { Token::And };
+                tokens.push((i, token));
+                i += word_end;
+                continue;
+            }
+            "NOT" if negate_next.is_none() => {
+                negate_next = Some(i);
+                i += word_end;
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut negated = negate_next.take().is_some();
+        let mut j = i;
+        if !negated && c == '-' {
+            negated = true;
+            j += 1;
+        }
+
+        let mut field = None;
+        if let Some((name, _)) = input[j..].split_once(':')
+            && let Some(parsed) = Field::from_name(name)
+        {
+            field = Some(parsed);
+            j += name.len() + 1;
+        }
+
+        let term = if input[j..].starts_with('"') {
+            let Some(len) = input[j + 1..].find('"') else {
+                return Err(QueryParseError::UnterminatedQuote { position: j });
+            };
+            let phrase = &input[j + 1..j + 1 + len];
+            j += len + 2;
+            Term::Phrase(phrase.to_string())
+        } else {
+            let len = input[j..]
+                .find(char::is_whitespace)
+                .unwrap_or(input.len() - j);
+            let word = &input[j..j + len];
+            j += len;
+            match word.strip_suffix('*') {
+                Some(prefix) if has_word_chars(prefix) => Term::Prefix(prefix.to_string()),
+                _ => Term::Word(word.to_string()),
+            }
+        };
+        i = j;
+
+        let text = match &term {
+            Term::Word(text) | Term::Prefix(text) | Term::Phrase(text) => text,
+        };
+        if !has_word_chars(text) {
+            if negated || field.is_some() {
+                return Err(QueryParseError::MissingTerm { position: start });
+            }
+            continue;
+        }
+        tokens.push((
+            start,
+            Token::Clause {
+                clause: Clause { field, term },
+                negated,
+            },
+        ));
+    }
+
+    if let Some(position) = negate_next {
+        return Err(QueryParseError::MissingTerm { position });
+    }
+    Ok(tokens)
+}
+
+/// Returns `true` if `text` has a letter or digit for FTS5 to index.
+fn has_word_chars(text: &str) -> bool {
+    text.chars().any(char::is_alphanumeric)
+}
+
+/// Quotes `text` as an FTS5 string, doubling any `"` inside.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
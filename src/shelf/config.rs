@@ -0,0 +1,665 @@
+//! Per-shelf configuration stored in `.shelf.toml`.
+//!
+//! Every shelf may carry a `.shelf.toml` file in its root with settings
+//! that apply to that shelf only:
+//!
+//! ```toml
+//! # Paths to leave out of the index, in .gitignore syntax.
+//! ignore = ["drafts/", "*.tmp.md"]
+//!
+//! # Content of notes created without content; {{title}} is replaced.
+//! template = "# {{title}}\n\n"
+//!
+//! # How note filenames are derived from titles: "title" or "slug".
+//! filename_style = "slug"
+//!
+//! # Full-text search tokenizer: "unicode61", "porter" or "trigram".
+//! tokenizer = "porter"
+//!
+//! # Keep a trigram index for typo-tolerant fuzzy search.
+//! trigram_index = true
+//!
+//! # Record a revision of each note on every save, keeping up to 50.
+//! history = true
+//! history_limit = 50
+//! ```
+//!
+//! [`Shelf::new`](crate::shelf::storage::Shelf::new) writes the file with
+//! default values and [`Shelf::open`](crate::shelf::storage::Shelf::open)
+//! loads it. A missing file or missing keys mean the defaults. Keys the
+//! library does not know are ignored, so newer files still load.
+//!
+//! The file is read when a shelf, [`Index`](crate::watcher::index::Index)
+//! or [`WatcherService`](crate::watcher::service::WatcherService) is
+//! opened; changes take effect the next time they are opened.
+//!
+//! # Format
+//!
+//! The parser understands the subset of TOML these settings need: `key =
+//! value` pairs with basic (`"..."`), literal (`'...'`) and multi-line
+//! (`"""..."""`, `'''...'''`) strings, booleans, integers, arrays (which
+//! may span lines) and `#` comments. Tables are rejected.
+
+use crate::shelf::storage::ShelfError;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Name of the configuration file in the shelf root.
+pub const CONFIG_FILE: &str = ".shelf.toml";
+
+/// Placeholder in [`ShelfConfig::template`] replaced by the note title.
+pub const TITLE_PLACEHOLDER: &str = "{{title}}";
+
+/// How note filenames are derived from note titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilenameStyle {
+    /// The title as written: `My Note.md`.
+    #[default]
+    Title,
+
+    /// Lowercase words joined by dashes: `my-note.md`.
+    Slug,
+}
+
+impl FilenameStyle {
+    /// Returns the name used for this style in `.shelf.toml`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilenameStyle::Title => "title",
+            FilenameStyle::Slug => "slug",
+        }
+    }
+
+    /// Parses a style name as written in `.shelf.toml`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "title" => Some(FilenameStyle::Title),
+            "slug" => Some(FilenameStyle::Slug),
+            _ => None,
+        }
+    }
+
+    /// Returns the filename (without `.md`) for a note title.
+    ///
+    /// Empty results become "Untitled" (or `untitled` for slugs).
+    pub fn file_stem(&self, title: &str) -> String {
+        let title = title.trim();
+        match self {
+            FilenameStyle::Title if title.is_empty() => "Untitled".to_string(),
+            FilenameStyle::Title => title.to_string(),
+            FilenameStyle::Slug => {
+                let slug = title
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_lowercase)
+                    .collect::<Vec<_>>()
+                    .join("-");
+                if slug.is_empty() {
+                    "untitled".to_string()
+                } else {
+                    slug
+                }
+            }
+        }
+    }
+}
+
+/// The FTS5 tokenizer used for the shelf's full-text index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tokenizer {
+    /// Unicode-aware word tokenizer that folds case and diacritics.
+    #[default]
+    Unicode61,
+
+    /// `unicode61` plus English stemming, so "running" matches "run".
+    Porter,
+
+    /// Indexes every three-character sequence, enabling substring matches.
+    Trigram,
+}
+
+impl Tokenizer {
+    /// Returns the name used for this tokenizer in `.shelf.toml`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tokenizer::Unicode61 => "unicode61",
+            Tokenizer::Porter => "porter",
+            Tokenizer::Trigram => "trigram",
+        }
+    }
+
+    /// Parses a tokenizer name as written in `.shelf.toml`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unicode61" => Some(Tokenizer::Unicode61),
+            "porter" => Some(Tokenizer::Porter),
+            "trigram" => Some(Tokenizer::Trigram),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the FTS5 `tokenize` option for this tokenizer.
+    pub fn fts5_option(&self) -> &'static str {
+        match self {
+            Tokenizer::Unicode61 => "unicode61",
+            Tokenizer::Porter => "porter unicode61",
+            Tokenizer::Trigram => "trigram",
+        }
+    }
+}
+
+/// Settings of a single shelf, loaded from its `.shelf.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShelfConfig {
+    /// Patterns of paths to leave out of the index, listings and watcher,
+    /// in `.gitignore` syntax relative to the shelf root.
+    pub ignore: Vec<String>,
+
+    /// Content for notes created without content. [`TITLE_PLACEHOLDER`]
+    /// is replaced with the note title.
+    pub template: Option<String>,
+
+    /// How filenames of new and renamed notes are derived from titles.
+    pub filename_style: FilenameStyle,
+
+    /// Tokenizer of the full-text index.
+    pub tokenizer: Tokenizer,
+
+    /// Keep a secondary full-text table with the trigram tokenizer, used
+    /// by [`SearchMode::Fuzzy`](crate::search::SearchMode::Fuzzy) to find
+    /// candidates for substring and misspelled matches.
+    pub trigram_index: bool,
+
+    /// Record a revision of a note on every save, see [`crate::history`].
+    pub history: bool,
+
+    /// Maximum number of revisions kept per note; older ones are dropped.
+    /// `None` keeps all of them.
+    pub history_limit: Option<usize>,
+}
+
+impl ShelfConfig {
+    /// Loads the configuration of the shelf at `shelf_root`.
+    ///
+    /// Returns the defaults if the shelf has no `.shelf.toml`.
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidConfig`] if the file cannot be parsed
+    /// - [`ShelfError::Io`] if the file exists but cannot be read
+    pub fn load(shelf_root: &Path) -> Result<Self, ShelfError> {
+        match fs::read_to_string(shelf_root.join(CONFIG_FILE)) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes this configuration to `.shelf.toml` in `shelf_root`.
+    ///
+    /// # Errors
+    /// - [`ShelfError::Io`] if the file cannot be written
+    pub fn save(&self, shelf_root: &Path) -> Result<(), ShelfError> {
+        fs::write(shelf_root.join(CONFIG_FILE), self.render())?;
+        Ok(())
+    }
+
+    /// Parses the contents of a `.shelf.toml` file.
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidConfig`] with the offending line if the text
+    ///   is not valid, or a known key has a value of the wrong type
+    pub fn parse(text: &str) -> Result<Self, ShelfError> {
+        let mut config = ShelfConfig::default();
+        let mut seen = HashSet::new();
+        let mut parser = Parser::new(text);
+
+        while let Some((line, key, value)) = parser.next_entry()? {
+            if !seen.insert(key.clone()) {
+                return Err(invalid(line, format!("duplicate key `{key}`")));
+            }
+            match key.as_str() {
+                "ignore" => {
+                    config.ignore = value
+                        .into_strings()
+                        .ok_or_else(|| invalid(line, "`ignore` must be an array of strings"))?;
+                }
+                "template" => {
+                    let template = value
+                        .into_string()
+                        .ok_or_else(|| invalid(line, "`template` must be a string"))?;
+                    config.template = Some(template).filter(|t| !t.is_empty());
+                }
+                "filename_style" => {
+                    config.filename_style = value
+                        .into_string()
+                        .and_then(|name| FilenameStyle::from_name(&name))
+                        .ok_or_else(|| {
+                            invalid(line, "`filename_style` must be \"title\" or \"slug\"")
+                        })?;
+                }
+                "tokenizer" => {
+                    config.tokenizer = value
+                        .into_string()
+                        .and_then(|name| Tokenizer::from_name(&name))
+                        .ok_or_else(|| {
+                            invalid(
+                                line,
+                                "`tokenizer` must be \"unicode61\", \"porter\" or \"trigram\"",
+                            )
+                        })?;
+                }
+                "trigram_index" => {
+                    config.trigram_index = value
+                        .into_bool()
+                        .ok_or_else(|| invalid(line, "`trigram_index` must be a boolean"))?;
+                }
+                "history" => {
+                    config.history = value
+                        .into_bool()
+                        .ok_or_else(|| invalid(line, "`history` must be a boolean"))?;
+                }
+                "history_limit" => {
+                    let limit = value
+                        .into_integer()
+                        .and_then(|limit| usize::try_from(limit).ok())
+                        .ok_or_else(|| {
+                            invalid(line, "`history_limit` must be a non-negative integer")
+                        })?;
+                    config.history_limit = Some(limit).filter(|&limit| limit > 0);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Renders this configuration as the contents of a `.shelf.toml` file.
+    pub fn render(&self) -> String {
+        let ignore = self
+            .ignore
+            .iter()
+            .map(|pattern| quote(pattern))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "# Ora shelf configuration.\n\
+             \n\
+             # Paths to leave out of the index, in .gitignore syntax.\n\
+             ignore = [{ignore}]\n\
+             \n\
+             # Content of notes created without content; {TITLE_PLACEHOLDER} is replaced.\n\
+             template = {}\n\
+             \n\
+             # How note filenames are derived from titles: \"title\" or \"slug\".\n\
+             filename_style = {}\n\
+             \n\
+             # Full-text search tokenizer: \"unicode61\", \"porter\" or \"trigram\".\n\
+             tokenizer = {}\n\
+             \n\
+             # Keep a trigram index for typo-tolerant fuzzy search.\n\
+             trigram_index = {}\n\
+             \n\
+             # Record a revision of each note on every save.\n\
+             history = {}\n\
+             \n\
+             # Revisions kept per note; 0 keeps all of them.\n\
+             history_limit = {}\n",
+            quote(self.template.as_deref().unwrap_or("")),
+            quote(self.filename_style.as_str()),
+            quote(self.tokenizer.as_str()),
+            self.trigram_index,
+            self.history,
+            self.history_limit.unwrap_or(0),
+        )
+    }
+
+    /// Returns the content for a new note titled `title` created without
+    /// content, or `None` if the shelf has no template.
+    pub fn render_template(&self, title: &str) -> Option<String> {
+        self.template
+            .as_ref()
+            .map(|template| template.replace(TITLE_PLACEHOLDER, title))
+    }
+}
+
+fn invalid(line: usize, message: impl Into<String>) -> ShelfError {
+    ShelfError::InvalidConfig {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Quotes a string as a TOML basic string.
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A parsed TOML value.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Bool(bool),
+    Integer(i64),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn into_string(self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn into_bool(self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn into_integer(self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    fn into_strings(self) -> Option<Vec<String>> {
+        match self {
+            Value::Array(items) => items.into_iter().map(Value::into_string).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Cursor over the TOML text that tracks the current line.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            text,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            for _ in prefix.chars() {
+                self.bump();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ShelfError {
+        invalid(self.line, message)
+    }
+
+    /// Skips spaces and tabs, and a trailing comment if `comments` is set.
+    fn skip_inline(&mut self, comments: bool) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+        if comments && self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_inline(true);
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.bump();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Parses the next `key = value` line.
+    ///
+    /// # Returns
+    /// The line of the key, the key and the value, or `None` at the end
+    fn next_entry(&mut self) -> Result<Option<(usize, String, Value)>, ShelfError> {
+        self.skip_blank();
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+        let line = self.line;
+
+        let key = match c {
+            '[' => return Err(self.error("tables are not supported")),
+            '"' => {
+                self.bump();
+                self.basic_string()?
+            }
+            '\'' => {
+                self.bump();
+                self.literal_string()?
+            }
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.bump();
+                }
+                if start == self.pos {
+                    return Err(self.error(format!("unexpected character `{c}`")));
+                }
+                self.text[start..self.pos].to_string()
+            }
+        };
+
+        self.skip_inline(false);
+        if !self.eat("=") {
+            return Err(self.error(format!("expected `=` after `{key}`")));
+        }
+        self.skip_inline(false);
+        let value = self.value()?;
+
+        self.skip_inline(true);
+        self.eat("\r");
+        if !matches!(self.peek(), None | Some('\n')) {
+            return Err(self.error("expected a new line after the value"));
+        }
+
+        Ok(Some((line, key, value)))
+    }
+
+    fn value(&mut self) -> Result<Value, ShelfError> {
+        if self.eat("\"\"\"") {
+            return self.multiline_basic_string().map(Value::String);
+        }
+        if self.eat("'''") {
+            return self.multiline_literal_string().map(Value::String);
+        }
+        if self.eat("\"") {
+            return self.basic_string().map(Value::String);
+        }
+        if self.eat("'") {
+            return self.literal_string().map(Value::String);
+        }
+        if self.eat("[") {
+            return self.array();
+        }
+        if self.eat("true") {
+            return Ok(Value::Bool(true));
+        }
+        if self.eat("false") {
+            return Ok(Value::Bool(false));
+        }
+
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '_'))
+        {
+            self.bump();
+        }
+        self.text[start..self.pos]
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| self.error("expected a value"))
+    }
+
+    fn array(&mut self) -> Result<Value, ShelfError> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(Value::Array(items));
+            }
+            if self.peek().is_none() {
+                return Err(self.error("unterminated array"));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            if !self.eat(",") && self.peek() != Some(']') {
+                return Err(self.error("expected `,` or `]` in array"));
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, ShelfError> {
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(out),
+                Some('\\') => out.push(self.escape()?),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, ShelfError> {
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => {
+                    let value = self.text[start..self.pos].to_string();
+                    self.bump();
+                    return Ok(value);
+                }
+                Some(_) => {
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn multiline_basic_string(&mut self) -> Result<String, ShelfError> {
+        // A newline right after the opening quotes is trimmed.
+        self.eat("\r");
+        self.eat("\n");
+        let mut out = String::new();
+        loop {
+            if self.eat("\"\"\"") {
+                return Ok(out);
+            }
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') if matches!(self.peek(), Some('\n' | '\r' | ' ' | '\t')) => {
+                    // Line-ending backslash: skip the newline and indentation.
+                    while matches!(self.peek(), Some('\n' | '\r' | ' ' | '\t')) {
+                        self.bump();
+                    }
+                }
+                Some('\\') => out.push(self.escape()?),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn multiline_literal_string(&mut self) -> Result<String, ShelfError> {
+        self.eat("\r");
+        self.eat("\n");
+        let start = self.pos;
+        loop {
+            if self.rest().starts_with("'''") {
+                let value = self.text[start..self.pos].to_string();
+                self.eat("'''");
+                return Ok(value);
+            }
+            if self.bump().is_none() {
+                return Err(self.error("unterminated string"));
+            }
+        }
+    }
+
+    /// Parses the escape sequence after a backslash.
+    fn escape(&mut self) -> Result<char, ShelfError> {
+        let c = match self.bump() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(kind @ ('u' | 'U')) => {
+                let len = if kind == 'u' { 4 } else { 8 };
+                let hex = self.rest().get(..len).unwrap_or("");
+                let c = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+                self.pos += len;
+                c
+            }
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        Ok(c)
+    }
+}
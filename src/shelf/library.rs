@@ -0,0 +1,223 @@
+//! The base directory that holds all shelves.
+//!
+//! A [`Library`] owns the directory under which every [`Shelf`] lives and
+//! performs the shelf-level operations (create, open, list, rename)
+//! relative to it. This keeps shelves off the user's Documents folder when
+//! that is not wanted, e.g. on headless servers, on another disk, or in
+//! tests.
+//!
+//! # Locating the Library
+//!
+//! The base directory is chosen, in order of precedence, from:
+//! 1. An explicit path passed to [`Library::new`]
+//! 2. The [`SHELVES_DIR_ENV`] environment variable, via [`Library::locate`]
+//!    (or a custom lookup, via [`Library::locate_with`])
+//! 3. The default `~/Documents/shelves`, via [`Library::locate`]
+//!
+//! The associated functions on [`Shelf`] (`Shelf::new`, `Shelf::open`,
+//! `Shelf::list_shelves`, ...) use [`Library::locate`].
+
+use crate::shelf::config::ShelfConfig;
+use crate::shelf::storage::{Shelf, ShelfError, valid_shelf};
+use crate::shelf::trash::Trash;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the default shelves directory.
+pub const SHELVES_DIR_ENV: &str = "ORA_SHELVES_DIR";
+
+/// A directory containing shelves, one subdirectory per shelf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    root: PathBuf,
+}
+
+impl Library {
+    /// Creates a library rooted at an explicit directory.
+    ///
+    /// The directory does not need to exist yet; it is created along with
+    /// the first shelf.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Locates the library from the environment.
+    ///
+    /// Uses the directory named by [`SHELVES_DIR_ENV`] if it is set and not
+    /// empty, and `~/Documents/shelves` otherwise.
+    ///
+    /// # Errors
+    /// - [`ShelfError::NotFound`] if the variable is unset and the user's
+    ///   documents directory cannot be determined
+    pub fn locate() -> Result<Self, ShelfError> {
+        Self::locate_with(|name| env::var_os(name))
+    }
+
+    /// Locates the library like [`Library::locate`], reading variables
+    /// through `lookup` instead of the process environment.
+    ///
+    /// # Errors
+    /// - [`ShelfError::NotFound`] if `lookup` has no [`SHELVES_DIR_ENV`] and
+    ///   the user's documents directory cannot be determined
+    pub fn locate_with(lookup: impl Fn(&str) -> Option<OsString>) -> Result<Self, ShelfError> {
+        if let Some(dir) = lookup(SHELVES_DIR_ENV)
+            && !dir.is_empty()
+        {
+            return Ok(Self::new(dir));
+        }
+        Self::default_location()
+    }
+
+    /// Returns the library at the default location, `~/Documents/shelves`.
+    ///
+    /// Ignores [`SHELVES_DIR_ENV`].
+    ///
+    /// # Errors
+    /// - [`ShelfError::NotFound`] if the user's documents directory cannot be determined
+    pub fn default_location() -> Result<Self, ShelfError> {
+        let docs = dirs::document_dir()
+            .ok_or_else(|| ShelfError::NotFound("documents directory".into()))?;
+        Ok(Self::new(docs.join("shelves")))
+    }
+
+    /// Returns the base directory of this library.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves a shelf name into its directory in this library.
+    ///
+    /// Returns the library root itself when `name` is `None`.
+    pub fn shelf_path(&self, name: Option<&str>) -> PathBuf {
+        match name {
+            Some(name) => self.root.join(name),
+            None => self.root.clone(),
+        }
+    }
+
+    /// Creates a brand-new shelf directory in this library.
+    ///
+    /// Writes a `.shelf.toml` with the default [`ShelfConfig`].
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidInput`] if the name is empty, hidden or has
+    ///   invalid characters
+    /// - [`ShelfError::AlreadyExists`] if the directory already exists
+    /// - [`ShelfError::Io`] for any underlying filesystem error
+    pub fn create_shelf(&self, name: &str) -> Result<Shelf, ShelfError> {
+        let shelf_name = valid_shelf(name)?;
+        let root = self.shelf_path(Some(&shelf_name));
+
+        if root.exists() {
+            return Err(ShelfError::AlreadyExists(shelf_name));
+        }
+
+        fs::create_dir_all(&root)?;
+        let config = ShelfConfig::default();
+        config.save(&root)?;
+
+        Ok(Shelf {
+            root,
+            name: shelf_name,
+            config,
+        })
+    }
+
+    /// Opens an existing shelf in this library, loading its `.shelf.toml`.
+    ///
+    /// # Errors
+    /// - [`ShelfError::NotFound`] if the shelf does not exist
+    /// - [`ShelfError::InvalidInput`] if the path exists but is not a directory
+    /// - [`ShelfError::InvalidConfig`] if the shelf's `.shelf.toml` is invalid
+    pub fn open_shelf(&self, name: &str) -> Result<Shelf, ShelfError> {
+        let root = self.shelf_path(Some(name));
+
+        if !root.exists() {
+            return Err(ShelfError::NotFound(name.to_string()));
+        }
+
+        if !root.is_dir() {
+            return Err(ShelfError::InvalidInput);
+        }
+
+        let config = ShelfConfig::load(&root)?;
+
+        Ok(Shelf {
+            root,
+            name: name.to_string(),
+            config,
+        })
+    }
+
+    /// Opens the shelf with the given `name`, creating it if missing.
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidInput`] if the name is empty, hidden or has
+    ///   invalid characters
+    /// - [`ShelfError::Io`] if the directory creation fails
+    pub fn ensure_shelf(&self, name: &str) -> Result<Shelf, ShelfError> {
+        valid_shelf(name)?;
+        if !self.shelf_path(Some(name)).exists() {
+            return self.create_shelf(name);
+        }
+
+        self.open_shelf(name)
+    }
+
+    /// Lists the names of all shelves in this library.
+    ///
+    /// Hidden directories, such as the trash, are not shelves.
+    ///
+    /// # Errors
+    /// - [`ShelfError::Io`] if the library directory cannot be read
+    pub fn list_shelves(&self) -> Result<Vec<String>, ShelfError> {
+        let names = fs::read_dir(&self.root)?
+            .filter_map(|res| {
+                let entry = res.ok()?;
+                let ft = entry.file_type().ok()?;
+                if ft.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+                    Some(entry.file_name().to_string_lossy().into_owned())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(names)
+    }
+
+    /// Returns the trash of this library, holding deleted shelves.
+    ///
+    /// Restoring an entry brings the shelf back under its old name, or
+    /// under a numbered name if a shelf with that name exists again.
+    pub fn trash(&self) -> Trash {
+        Trash::new(&self.root)
+    }
+
+    /// Renames `shelf` to `new_name` within this library.
+    ///
+    /// Updates both the directory on the filesystem and the in‑memory
+    /// `name` and `root` fields if successful.
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidInput`] if the new name is invalid
+    /// - [`ShelfError::AlreadyExists`] if a shelf with the target name already exists
+    /// - [`ShelfError::Io`] if the rename operation fails
+    pub fn rename_shelf(&self, shelf: &mut Shelf, new_name: &str) -> Result<(), ShelfError> {
+        let valid_new_name = valid_shelf(new_name)?;
+        let new_path = self.shelf_path(Some(&valid_new_name));
+
+        if new_path.exists() {
+            return Err(ShelfError::AlreadyExists(valid_new_name));
+        }
+
+        fs::rename(&shelf.root, &new_path)?;
+
+        shelf.name = valid_new_name;
+        shelf.root = new_path;
+
+        Ok(())
+    }
+}
@@ -1,20 +1,209 @@
-use crate::domain::LocalNote;
+use crate::domain::{LocalNote, NoteError, create_unique_filename, write_atomic};
 use crate::error::OraError;
-use crate::shelf::storage::Shelf;
+use crate::history::{DiffLine, History, Revision, RevisionSource};
+use crate::ignore::{IgnoreRules, is_hidden, markdown_files};
+use crate::links::{extract_links, note_keys, rebase_links, retarget_links, wiki_link_key};
+use crate::search::{SortBy, SortDirection};
+use crate::shelf::config::FilenameStyle;
+use crate::shelf::storage::{Shelf, ShelfError};
+use crate::shelf::trash::{Trash, TrashItem};
+use crate::watcher::index::Index;
+use crate::watcher::service::WatcherService;
+use crate::watcher::subscription::{NoteEvent, Subscribers};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// Options controlling [`ShelfManager::rename_note`] and
+/// [`ShelfManager::move_note`].
+#[derive(Debug, Clone, Default)]
+pub struct RenameOptions {
+    /// Rewrite wikilinks and relative Markdown links in other notes that
+    /// point at the renamed note.
+    pub update_links: bool,
+
+    /// Compute the report without touching any file.
+    pub dry_run: bool,
+}
+
+/// A file whose links were (or would be) rewritten by a rename or move.
+#[derive(Debug, Clone)]
+pub struct LinkRewrite {
+    /// Path of the rewritten file, as it is after the rename.
+    pub path: PathBuf,
+
+    /// Number of links changed in the file.
+    pub links: usize,
+
+    /// The full file content after rewriting.
+    pub new_content: String,
+}
+
+/// A wikilink left unchanged by a rename or move because its target also
+/// names another note.
+#[derive(Debug, Clone)]
+pub struct AmbiguousLink {
+    /// Path of the file holding the link, as it is after the rename.
+    pub path: PathBuf,
+
+    /// The link target as written, such as `Plan` for `[[Plan]]`.
+    pub target: String,
+}
+
+/// Outcome of [`ShelfManager::rename_note`] and [`ShelfManager::move_note`].
+#[derive(Debug, Clone)]
+pub struct RenameReport {
+    /// Path of the note before the rename.
+    pub old_path: PathBuf,
+
+    /// Path of the note after the rename.
+    pub new_path: PathBuf,
+
+    /// Files whose links were rewritten, including the renamed note itself
+    /// if it links to itself.
+    pub rewrites: Vec<LinkRewrite>,
+
+    /// Wikilinks that may point at the note but were not rewritten, because
+    /// another note has the same title or path suffix.
+    pub ambiguous: Vec<AmbiguousLink>,
+
+    /// Failures to bring the history and index in line after the files
+    /// were moved. The rename itself happened; a reconcile of the index
+    /// picks up what it missed.
+    pub sync_errors: Vec<String>,
+
+    /// `true` if nothing was written to disk.
+    pub dry_run: bool,
+}
+
+impl RenameReport {
+    /// Returns the paths of all files whose links changed.
+    pub fn changed_files(&self) -> Vec<&Path> {
+        self.rewrites.iter().map(|r| r.path.as_path()).collect()
+    }
+}
+
+/// Whether [`ShelfManager::transfer_note`] and
+/// [`ShelfManager::transfer_folder`] move or copy notes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransferMode {
+    /// Remove the notes from the source shelf.
+    #[default]
+    Move,
+
+    /// Leave the notes in the source shelf as well.
+    Copy,
+}
+
+/// What to do when a transferred note would replace an existing file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Add a number suffix to the new note, as when creating notes.
+    #[default]
+    Rename,
+
+    /// Replace the existing note.
+    Overwrite,
+
+    /// Leave the existing note and skip the transferred one.
+    Skip,
+
+    /// Stop with an [`std::io::ErrorKind::AlreadyExists`] error.
+    Fail,
+}
+
+/// Options controlling [`ShelfManager::transfer_note`] and
+/// [`ShelfManager::transfer_folder`].
+#[derive(Debug, Clone, Default)]
+pub struct TransferOptions {
+    /// Move or copy the notes.
+    pub mode: TransferMode,
+
+    /// How to resolve name collisions in the target shelf.
+    pub on_conflict: ConflictPolicy,
+}
+
+/// Outcome of a transfer between shelves.
+#[derive(Debug, Clone, Default)]
+pub struct TransferReport {
+    /// Source and destination path of every transferred note.
+    pub transferred: Vec<(PathBuf, PathBuf)>,
+
+    /// Source paths of notes skipped under [`ConflictPolicy::Skip`].
+    pub skipped: Vec<PathBuf>,
+}
 
 /// A manager providing high‑level operations for notes inside a single [`Shelf`].
 ///
 /// Wraps a reference to a [`Shelf`] and exposes helper methods for creating,
 /// reading, listing, deleting, and updating [`LocalNote`]s within that shelf.
+///
+/// A manager created with [`ShelfManager::with_index`] also keeps the
+/// shelf's [`Index`] in sync with every change it makes, which preserves
+/// note IDs across renames and allows looking notes up by ID.
+///
+/// A manager created with [`ShelfManager::with_watcher`] additionally
+/// publishes each change to the watcher's subscribers as a single
+/// [`NoteEvent`]; the watcher recognizes the resulting file system events as
+/// echoes and does not report them again.
+///
+/// The manager follows the shelf's [`ShelfConfig`](crate::shelf::config::ShelfConfig):
+/// new and renamed notes get filenames in the configured style, notes
+/// created without content start from the template, and ignored paths are
+/// left out of listings and link rewrites.
 pub struct ShelfManager<'a> {
     shelf: &'a Shelf,
+    index: Option<Index>,
+    subscribers: Option<Subscribers>,
+    /// History opened by a manager without an index, on first use.
+    history: OnceLock<History>,
 }
 
 impl<'a> ShelfManager<'a> {
     /// Creates a new manager for the given [`Shelf`].
     pub fn new(shelf: &'a Shelf) -> Self {
-        ShelfManager { shelf }
+        ShelfManager {
+            shelf,
+            index: None,
+            subscribers: None,
+            history: OnceLock::new(),
+        }
+    }
+
+    /// Creates a new manager that keeps `index` up to date.
+    ///
+    /// Every note created, updated, renamed, or deleted through the manager
+    /// is re-indexed immediately.
+    pub fn with_index(shelf: &'a Shelf, index: Index) -> Self {
+        ShelfManager {
+            shelf,
+            index: Some(index),
+            subscribers: None,
+            history: OnceLock::new(),
+        }
+    }
+
+    /// Creates a new manager that cooperates with a running watcher.
+    ///
+    /// Changes are indexed synchronously in the watcher's index and
+    /// published to its subscribers, so subscribers see exactly one event
+    /// per change and the watcher skips the file system echoes of the
+    /// manager's own writes.
+    pub fn with_watcher(shelf: &'a Shelf, watcher: &WatcherService) -> Self {
+        ShelfManager {
+            shelf,
+            index: Some(watcher.index().clone()),
+            subscribers: Some(watcher.subscribers().clone()),
+            history: OnceLock::new(),
+        }
+    }
+
+    /// Returns the index kept in sync by this manager, if any.
+    pub fn index(&self) -> Option<&Index> {
+        self.index.as_ref()
     }
 
     /// Returns the name of the managed shelf.
@@ -22,98 +211,1178 @@ impl<'a> ShelfManager<'a> {
         &self.shelf.name
     }
 
-    /// Retrieves a note by its title (filename without `.md`).
+    /// Retrieves a note by its title or shelf-relative path.
     ///
-    /// Constructs the path `{shelf_root}/{title}.md` and attempts to open it.
+    /// `title` is the filename without `.md`, optionally preceded by the
+    /// folders it lives in, e.g. `"Plan"` or `"projects/2024/Plan"`; a
+    /// trailing `.md` is accepted. With the `slug` filename style the title
+    /// is slugified first, so both "My Note" and "my-note" find `my-note.md`.
     /// The note's title is extracted from the filename.
     ///
     /// # Errors
-    /// Returns [`OraError`] if the note cannot be read or parsed.
+    /// - [`NoteError::InvalidPath`] if the path is empty or has `.`, `..` or
+    ///   hidden components
+    /// - [`OraError`] if the note cannot be read or parsed
     pub fn get_note(&self, title: &str) -> Result<LocalNote, OraError> {
-        let note_path = self.shelf.root.join(format!("{title}.md"));
-        Ok(LocalNote::open(&note_path)?)
+        Ok(LocalNote::open(&self.note_path(title)?)?)
     }
 
-    /// Lists all notes in the shelf.
+    /// Retrieves a note by its stable ID.
     ///
-    /// Scans the shelf directory for `*.md` files, deserializes each into a
-    /// [`LocalNote`], and returns them as a vector.
+    /// With an index, this finds notes by front matter `id:` as well as by
+    /// index-assigned IDs. Without one, only front matter IDs are found by
+    /// scanning the shelf.
+    ///
+    /// # Errors
+    /// - [`NoteError::NotFound`] if no note has that ID
+    /// - [`OraError`] if the note cannot be read
+    pub fn get_note_by_id(&self, id: &str) -> Result<LocalNote, OraError> {
+        if let Some(index) = &self.index {
+            return match index.get_by_id(id)? {
+                Some(indexed) => Ok(LocalNote::open(&indexed.path)?),
+                None => Err(NoteError::NotFound(id.to_string()).into()),
+            };
+        }
+
+        for path in markdown_files(&self.shelf.root, &self.shelf.root, &self.ignore_rules()?)? {
+            let note = LocalNote::open(&path)?;
+            if note.id().as_deref() == Some(id) {
+                return Ok(note);
+            }
+        }
+        Err(NoteError::NotFound(id.to_string()).into())
+    }
+
+    /// Lists the notes in the shelf root.
+    ///
+    /// Equivalent to `list_notes_in("", false)`; notes in folders are not
+    /// included.
     ///
     /// # Errors
     /// Returns [`OraError`] if the directory or any note file cannot be read.
     pub fn list_notes(&self) -> Result<Vec<LocalNote>, OraError> {
-        let mut notes = Vec::new();
-        for entry in fs::read_dir(&self.shelf.root)? {
-            let entry = entry?;
-            let path = entry.path();
+        self.list_notes_in("", false)
+    }
 
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md") {
-                let note = LocalNote::open(&path)?;
-                notes.push(note);
+    /// Lists the notes in a folder of the shelf.
+    ///
+    /// Scans the folder for `*.md` files, deserializes each into a
+    /// [`LocalNote`], and returns them sorted by path. Hidden files and
+    /// files matching the shelf's ignore rules (see [`crate::ignore`]) are
+    /// skipped.
+    ///
+    /// # Arguments
+    /// * `folder` - Shelf-relative folder, e.g. `"projects/2024"`; empty for the root
+    /// * `recursive` - Whether to include notes in subfolders at any depth
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the folder path is invalid
+    /// - [`OraError`] if the directory or any note file cannot be read
+    pub fn list_notes_in(&self, folder: &str, recursive: bool) -> Result<Vec<LocalNote>, OraError> {
+        let dir = self.folder_path(folder)?;
+        let ignore = self.ignore_rules()?;
+        let mut paths = if recursive {
+            markdown_files(&self.shelf.root, &dir, &ignore)?
+        } else {
+            let mut paths = Vec::new();
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_file()
+                    && path.extension().and_then(|s| s.to_str()) == Some("md")
+                    && !is_hidden(&path)
+                    && !ignore.is_ignored_in(&self.shelf.root, &path)
+                {
+                    paths.push(path);
+                }
             }
+            paths
+        };
+        paths.sort();
+
+        let mut notes = Vec::with_capacity(paths.len());
+        for path in paths {
+            notes.push(LocalNote::open(&path)?);
         }
         Ok(notes)
     }
 
+    /// Lists the notes in a folder of the shelf in the given order.
+    ///
+    /// Like [`ShelfManager::list_notes_in`], but sorted by `sort_by`, with
+    /// dates and sizes taken from the files. Ties are broken by path.
+    ///
+    /// # Arguments
+    /// * `folder` - Shelf-relative folder, e.g. `"projects/2024"`; empty for the root
+    /// * `recursive` - Whether to include notes in subfolders at any depth
+    /// * `sort_by` - The order; see [`SortBy`] for how relevance orders
+    ///   apply to listings
+    /// * `direction` - Whether the order is applied as is or reversed
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the folder path is invalid
+    /// - [`OraError`] if the directory or any note file cannot be read
+    pub fn list_notes_sorted(
+        &self,
+        folder: &str,
+        recursive: bool,
+        sort_by: SortBy,
+        direction: SortDirection,
+    ) -> Result<Vec<LocalNote>, OraError> {
+        let notes = self.list_notes_in(folder, recursive)?;
+
+        let mut keyed = Vec::with_capacity(notes.len());
+        for note in notes {
+            let metadata = fs::metadata(&note.path)?;
+            let modified = metadata.modified()?;
+            let key = match sort_by {
+                SortBy::Relevance | SortBy::Path => ListKey::None,
+                // Without a query, the most recently modified note ranks best.
+                SortBy::RelevanceWithRecency { .. } => ListKey::Recency(Reverse(modified)),
+                SortBy::Title => ListKey::Text(note.title.to_ascii_lowercase()),
+                SortBy::Created => ListKey::Time(metadata.created().unwrap_or(modified)),
+                SortBy::Updated => ListKey::Time(modified),
+                SortBy::Size => ListKey::Size(metadata.len()),
+            };
+            keyed.push((key, note));
+        }
+
+        keyed.sort_by(|(a_key, a), (b_key, b)| a_key.cmp(b_key).then_with(|| a.path.cmp(&b.path)));
+        if direction == SortDirection::Descending {
+            keyed.reverse();
+        }
+        Ok(keyed.into_iter().map(|(_, note)| note).collect())
+    }
+
+    /// Lists the folders of the shelf, relative to the shelf root.
+    ///
+    /// Hidden and ignored folders are skipped. The result is sorted.
+    ///
+    /// # Arguments
+    /// * `folder` - Folder to list, empty for the root
+    /// * `recursive` - Whether to include nested folders at any depth
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the folder path is invalid
+    /// - [`OraError`] if a directory cannot be read
+    pub fn list_folders(&self, folder: &str, recursive: bool) -> Result<Vec<PathBuf>, OraError> {
+        let ignore = self.ignore_rules()?;
+        let mut pending = vec![self.folder_path(folder)?];
+        let mut folders = Vec::new();
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if !path.is_dir()
+                    || is_hidden(&path)
+                    || ignore.is_ignored_in(&self.shelf.root, &path)
+                {
+                    continue;
+                }
+                if recursive {
+                    pending.push(path.clone());
+                }
+                if let Ok(relative) = path.strip_prefix(&self.shelf.root) {
+                    folders.push(relative.to_path_buf());
+                }
+            }
+        }
+        folders.sort();
+        Ok(folders)
+    }
+
+    /// Creates a folder, along with any missing parent folders.
+    ///
+    /// Creating a folder that already exists is not an error.
+    ///
+    /// # Returns
+    /// The absolute path of the folder
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the path is empty or invalid
+    /// - [`OraError`] if the directory cannot be created
+    pub fn create_folder(&self, folder: &str) -> Result<PathBuf, OraError> {
+        let dir = self.folder_path(folder)?;
+        if dir == self.shelf.root {
+            return Err(NoteError::InvalidPath.into());
+        }
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Renames or moves a folder, with all notes inside it.
+    ///
+    /// Missing parents of the target are created. Note IDs are preserved,
+    /// and a watcher gets one [`NoteEvent::NoteRenamed`] per moved note.
+    /// Links to or from the moved notes are not rewritten.
+    ///
+    /// # Returns
+    /// The absolute path of the folder after the rename
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if either path is invalid, or `to` is
+    ///   inside `from`
+    /// - [`NoteError::NotFound`] if `from` is not an existing folder
+    /// - [`OraError::NoChanges`] if both paths are the same
+    /// - [`OraError`] if `to` already exists or the rename fails
+    pub fn rename_folder(&self, from: &str, to: &str) -> Result<PathBuf, OraError> {
+        let old_dir = self.folder_path(from)?;
+        let new_dir = self.folder_path(to)?;
+        if old_dir == self.shelf.root || new_dir == self.shelf.root {
+            return Err(NoteError::InvalidPath.into());
+        }
+        if !old_dir.is_dir() {
+            return Err(NoteError::NotFound(from.to_string()).into());
+        }
+        if old_dir == new_dir {
+            return Err(OraError::NoChanges);
+        }
+        if new_dir.starts_with(&old_dir) {
+            return Err(NoteError::InvalidPath.into());
+        }
+        if new_dir.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists", new_dir.display()),
+            )
+            .into());
+        }
+
+        if let Some(parent) = new_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&old_dir, &new_dir)?;
+        if let Some(history) = self.history()? {
+            history.rename_dir(&old_dir, &new_dir)?;
+        }
+
+        if let Some(index) = &self.index {
+            for (from, to) in index.rename_dir(&old_dir, &new_dir)? {
+                self.publish(&to, |id| NoteEvent::NoteRenamed {
+                    from: from.clone(),
+                    to: to.clone(),
+                    id,
+                })?;
+            }
+        }
+        Ok(new_dir)
+    }
+
+    /// Deletes a folder by moving it to the shelf's trash.
+    ///
+    /// Without `recursive`, only an empty folder can be deleted. With it,
+    /// the folder is trashed with everything inside it, and a watcher gets
+    /// one [`NoteEvent::NoteRemoved`] per indexed note. Use
+    /// [`ShelfManager::restore`] to bring it back.
+    ///
+    /// # Returns
+    /// The trash entry holding the folder
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the path is empty or invalid
+    /// - [`NoteError::NotFound`] if the folder does not exist
+    /// - [`OraError`] if the folder is not empty (without `recursive`) or
+    ///   cannot be moved
+    pub fn delete_folder(&self, folder: &str, recursive: bool) -> Result<TrashItem, OraError> {
+        let dir = self.folder_path(folder)?;
+        if dir == self.shelf.root {
+            return Err(NoteError::InvalidPath.into());
+        }
+        if !dir.is_dir() {
+            return Err(NoteError::NotFound(folder.to_string()).into());
+        }
+        if !recursive && fs::read_dir(&dir)?.next().is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::DirectoryNotEmpty,
+                format!("{} is not empty", dir.display()),
+            )
+            .into());
+        }
+
+        let mut removed = Vec::new();
+        if let Some(index) = &self.index {
+            for path in index.paths_under(&dir)? {
+                let id = index.get_by_path(&path)?.map(|n| n.id);
+                removed.push((path, id));
+            }
+        }
+
+        let item = self.shelf.trash().put(&dir)?;
+
+        if let Some(index) = &self.index {
+            for (path, id) in removed {
+                index.remove_note(&LocalNote::at_path(&path))?;
+                if let (Some(subscribers), Some(id)) = (&self.subscribers, id) {
+                    subscribers.publish(NoteEvent::NoteRemoved { path, id });
+                }
+            }
+        }
+        Ok(item)
+    }
+
+    /// Returns the trash of the managed shelf.
+    ///
+    /// Use it to list deleted notes and folders and to purge old entries;
+    /// trashed files are not indexed, so this needs no index updates. To
+    /// restore an entry, use [`ShelfManager::restore`].
+    pub fn trash(&self) -> Trash {
+        self.shelf.trash()
+    }
+
+    /// Restores a note or folder from the shelf's trash.
+    ///
+    /// The entry goes back to its original path; if that is taken, it gets
+    /// a number suffix (`Plan 1.md`). Restored notes are indexed again and
+    /// published as [`NoteEvent::NoteCreated`]. Stable IDs come back only
+    /// if they are in the notes' front matter.
+    ///
+    /// # Returns
+    /// The path the entry was restored to
+    ///
+    /// # Errors
+    /// - [`ShelfError::TrashItemNotFound`] if there is no entry with that ID
+    /// - [`OraError`] if the entry cannot be moved or a note cannot be read
+    pub fn restore(&self, id: &str) -> Result<PathBuf, OraError> {
+        let restored = self.shelf.trash().restore(id)?;
+
+        if let Some(index) = &self.index {
+            let paths = if restored.is_dir() {
+                markdown_files(&self.shelf.root, &restored, &index.ignore_rules())?
+            } else if restored.extension().and_then(|s| s.to_str()) == Some("md")
+                && !index.is_ignored(&restored)
+            {
+                vec![restored.clone()]
+            } else {
+                Vec::new()
+            };
+
+            for path in paths {
+                let note = LocalNote::open(&path)?;
+                index.index_note(&note)?;
+                self.publish(&path, |id| NoteEvent::NoteCreated {
+                    path: path.clone(),
+                    id,
+                })?;
+            }
+        }
+        Ok(restored)
+    }
+
     /// Creates a new note inside the shelf.
     ///
     /// Uses the given `title` and `content`. The title is used as the filename
-    /// (with .md extension) and empty titles become "Untitled". If a file with
-    /// the same name exists, a number suffix is added.
+    /// (with .md extension), styled per the shelf's `filename_style`, and
+    /// empty titles become "Untitled". If a file with the same name exists,
+    /// a number suffix is added.
+    ///
+    /// A title with folders, such as `"projects/Plan"`, creates the note in
+    /// that folder, creating the folder if needed.
+    ///
+    /// If `content` is empty and the shelf has a `template`, the note is
+    /// created from the template with the title filled in.
     ///
     /// # Errors
-    /// Returns [`OraError`] if the note cannot be created on disk.
+    /// - [`NoteError::InvalidPath`] if the folder part of `title` is invalid,
+    ///   or the note name would be hidden
+    /// - [`OraError`] if the note cannot be created on disk
     pub fn create_note(&self, title: &str, content: &str) -> Result<LocalNote, OraError> {
-        Ok(LocalNote::create(title, content, &self.shelf.root)?)
+        let config = &self.shelf.config;
+        let (folder, title) = split_folder(title);
+        let dir = self.folder_path(folder)?;
+        let content = match config.render_template(title.trim()) {
+            Some(template) if content.is_empty() => template,
+            _ => content.to_string(),
+        };
+        let stem = config.filename_style.file_stem(title);
+        check_stem(&stem)?;
+        fs::create_dir_all(&dir)?;
+        let note = LocalNote::create(&stem, &content, &dir)?;
+        self.record_revision(&note)?;
+        if let Some(index) = &self.index {
+            index.index_note(&note)?;
+            self.publish(&note.path, |id| NoteEvent::NoteCreated {
+                path: note.path.clone(),
+                id,
+            })?;
+        }
+        Ok(note)
     }
 
-    /// Deletes a note in the shelf by title.
+    /// Deletes a note in the shelf by title or shelf-relative path.
+    ///
+    /// Constructs `{shelf_root}/{path}.md`, then moves it to the shelf's
+    /// trash (see [`crate::shelf::trash`]). Use [`ShelfManager::restore`] to
+    /// bring it back.
     ///
-    /// Constructs `{shelf_root}/{title}.md`, then removes it from disk.
+    /// # Returns
+    /// The trash entry holding the note
     ///
     /// # Errors
-    /// Returns [`OraError`] if the filesystem operation fails.
-    pub fn delete_note(&self, title: &str) -> Result<(), OraError> {
-        let note_path = self.shelf.root.join(format!("{title}.md"));
+    /// - [`NoteError::InvalidPath`] if the path is invalid
+    /// - [`OraError`] if the filesystem operation fails
+    pub fn delete_note(&self, title: &str) -> Result<TrashItem, OraError> {
+        let note_path = self.note_path(title)?;
 
-        let note_to_delete = LocalNote {
-            title: String::new(),
-            content: String::new(),
-            path: note_path,
+        let id = match &self.index {
+            Some(index) => index.get_by_path(&note_path)?.map(|n| n.id),
+            None => None,
         };
 
-        note_to_delete.delete()?;
-        Ok(())
+        let item = self.shelf.trash().put(&note_path)?;
+        if let Some(index) = &self.index {
+            index.remove_note(&LocalNote::at_path(&note_path))?;
+            if let (Some(subscribers), Some(id)) = (&self.subscribers, id) {
+                subscribers.publish(NoteEvent::NoteRemoved {
+                    path: note_path,
+                    id,
+                });
+            }
+        }
+        Ok(item)
     }
 
     /// Updates an existing note in the shelf.
     ///
     /// - If `new_content` is set, replaces the note's content.  
-    /// - If `new_title` is set, updates the filename and title field.  
+    /// - If `new_title` is set, updates the filename and title field; the
+    ///   note stays in its folder.  
     /// - Saves the modified note to disk, replacing or renaming the old file as needed.  
     ///
+    /// Links to the note in other files are left untouched; use
+    /// [`ShelfManager::rename_note`] to rewrite them as part of a rename.
+    ///
     /// # Errors
-    /// Returns [`OraError`] if reading, writing, or deleting underlying files fails.
+    /// - [`NoteError::InvalidPath`] if the new note name would be hidden
+    /// - [`OraError`] if reading, writing, or deleting underlying files fails
     pub fn update_note(
         &self,
         title: &str,
         new_title: Option<&str>,
         new_content: Option<&str>,
     ) -> Result<LocalNote, OraError> {
+        let new_stem = new_title.map(|title| self.shelf.config.filename_style.file_stem(title));
+        if let Some(stem) = &new_stem {
+            check_stem(stem)?;
+        }
         let mut final_note = self.get_note(title)?;
+        let old_path = final_note.path.clone();
 
         if let Some(content) = new_content {
             final_note = final_note.with_content(content);
         }
 
-        if let Some(new_title) = new_title {
-            final_note.save_as(new_title)?;
+        if let Some(stem) = &new_stem {
+            final_note.save_as(stem)?;
         } else {
             final_note.save()?;
         }
 
+        if final_note.path != old_path
+            && let Some(history) = self.history()?
+        {
+            history.rename(&old_path, &final_note.path)?;
+        }
+        self.record_revision(&final_note)?;
+
+        if let Some(index) = &self.index {
+            index.rename_note(&old_path, &final_note)?;
+            self.publish(&final_note.path, |id| {
+                if final_note.path == old_path {
+                    NoteEvent::NoteModified {
+                        path: old_path.clone(),
+                        id,
+                    }
+                } else {
+                    NoteEvent::NoteRenamed {
+                        from: old_path.clone(),
+                        to: final_note.path.clone(),
+                        id,
+                    }
+                }
+            })?;
+        }
+
         final_note.reload()?;
 
         Ok(final_note)
     }
+
+    /// Renames a note, optionally rewriting links to it across the shelf.
+    ///
+    /// The note stays in its folder. With [`RenameOptions::update_links`]
+    /// set, every `[[Old Title]]` wikilink and relative Markdown link to the
+    /// note in any shelf file is rewritten to the new name. Aliases and
+    /// `#heading` fragments are kept. Wikilinks are matched the way the
+    /// index resolves them; a bare title or path that also names another
+    /// note is left alone and listed in [`RenameReport::ambiguous`]. All files are written with atomic
+    /// writes; if any write fails, files already rewritten are restored and
+    /// the note keeps its old name. Once the files are moved, failures to
+    /// update the history or index are listed in
+    /// [`RenameReport::sync_errors`] instead of failing the rename.
+    ///
+    /// With [`RenameOptions::dry_run`] set, nothing is written and the
+    /// report shows what would change.
+    ///
+    /// # Errors
+    /// - [`OraError::NoChanges`] if the new title equals the current one
+    /// - [`NoteError::InvalidPath`] if the new note name would be hidden
+    /// - [`ShelfError::RollbackFailed`] if a write failed and some rewritten
+    ///   files could not be restored
+    /// - [`OraError`] if the note cannot be read or any file cannot be written
+    pub fn rename_note(
+        &self,
+        title: &str,
+        new_title: &str,
+        options: &RenameOptions,
+    ) -> Result<RenameReport, OraError> {
+        let note = self.get_note(title)?;
+        let new_title = self.shelf.config.filename_style.file_stem(new_title);
+        check_stem(&new_title)?;
+        if new_title == note.title {
+            return Err(OraError::NoChanges);
+        }
+
+        let dir = note.path.parent().unwrap_or(&self.shelf.root).to_path_buf();
+        let new_path = create_unique_filename(&new_title, &dir);
+        self.relocate(note, new_path, options)
+    }
+
+    /// Moves a note into another folder, keeping its title.
+    ///
+    /// If the target folder already has a note with that title, a number
+    /// suffix is added. With [`RenameOptions::update_links`] set, links to
+    /// the note are rewritten as in [`ShelfManager::rename_note`], and the
+    /// note's own relative Markdown links are adjusted to its new location.
+    /// The note keeps its ID.
+    ///
+    /// # Arguments
+    /// * `title` - Title or shelf-relative path of the note
+    /// * `folder` - Existing target folder, empty for the shelf root
+    /// * `options` - Link rewriting and dry run options
+    ///
+    /// # Errors
+    /// - [`OraError::NoChanges`] if the note is already in `folder`
+    /// - [`NoteError::NotFound`] if the target folder does not exist
+    /// - [`ShelfError::RollbackFailed`] if a write failed and some rewritten
+    ///   files could not be restored
+    /// - [`OraError`] if the note cannot be read or any file cannot be written
+    pub fn move_note(
+        &self,
+        title: &str,
+        folder: &str,
+        options: &RenameOptions,
+    ) -> Result<RenameReport, OraError> {
+        let note = self.get_note(title)?;
+        let dir = self.folder_path(folder)?;
+        if !dir.is_dir() {
+            return Err(NoteError::NotFound(folder.to_string()).into());
+        }
+        if note.path.parent() == Some(dir.as_path()) {
+            return Err(OraError::NoChanges);
+        }
+
+        let new_path = create_unique_filename(&note.title, &dir);
+        self.relocate(note, new_path, options)
+    }
+
+    /// Moves `note` to `new_path`, rewriting links per `options`, and keeps
+    /// the index and subscribers in sync.
+    fn relocate(
+        &self,
+        mut note: LocalNote,
+        new_path: PathBuf,
+        options: &RenameOptions,
+    ) -> Result<RenameReport, OraError> {
+        let old_path = note.path.clone();
+
+        // (path, original content, rewrite)
+        let mut planned = Vec::new();
+        let mut ambiguous = Vec::new();
+        if options.update_links {
+            let files = markdown_files(&self.shelf.root, &self.shelf.root, &self.ignore_rules()?)?;
+            let ambiguous_keys = self.ambiguous_keys(&note, &files);
+            for path in files {
+                let original = fs::read_to_string(&path)?;
+                let (source, content, rebased) = if path == old_path {
+                    let (content, rebased) = rebase_links(&original, &old_path, &new_path);
+                    (new_path.clone(), content, rebased)
+                } else {
+                    (path.clone(), original.clone(), 0)
+                };
+                for link in extract_links(&content) {
+                    if wiki_link_key(&link).is_some_and(|key| ambiguous_keys.contains(&key)) {
+                        ambiguous.push(AmbiguousLink {
+                            path: source.clone(),
+                            target: link.target,
+                        });
+                    }
+                }
+                let (new_content, links) = retarget_links(
+                    &content,
+                    &source,
+                    &self.shelf.root,
+                    &old_path,
+                    &new_path,
+                    &ambiguous_keys,
+                );
+                if links + rebased > 0 {
+                    planned.push((
+                        path,
+                        original,
+                        LinkRewrite {
+                            path: source,
+                            links: links + rebased,
+                            new_content,
+                        },
+                    ));
+                }
+            }
+        }
+
+        let mut report = RenameReport {
+            old_path: old_path.clone(),
+            new_path: new_path.clone(),
+            rewrites: planned.iter().map(|(_, _, r)| r.clone()).collect(),
+            ambiguous,
+            sync_errors: Vec::new(),
+            dry_run: options.dry_run,
+        };
+        if options.dry_run {
+            return Ok(report);
+        }
+
+        let mut written: Vec<(&Path, &str)> = Vec::new();
+        let mut result = Ok(());
+        for (path, original, rewrite) in &planned {
+            if *path == old_path {
+                note = note.with_content(&rewrite.new_content);
+                continue;
+            }
+            result = write_atomic(path, rewrite.new_content.as_bytes());
+            if result.is_err() {
+                break;
+            }
+            written.push((path, original));
+        }
+
+        if result.is_ok() {
+            result = write_atomic(&new_path, note.content.as_bytes()).and_then(|()| {
+                fs::remove_file(&old_path).map_err(|e| {
+                    let _ = fs::remove_file(&new_path);
+                    NoteError::from(e)
+                })
+            });
+        }
+
+        if let Err(source) = result {
+            let unrestored: Vec<(PathBuf, NoteError)> = written
+                .into_iter()
+                .rev()
+                .filter_map(|(path, original)| {
+                    write_atomic(path, original.as_bytes())
+                        .err()
+                        .map(|e| (path.to_path_buf(), e))
+                })
+                .collect();
+            if unrestored.is_empty() {
+                return Err(source.into());
+            }
+            return Err(ShelfError::RollbackFailed { source, unrestored }.into());
+        }
+
+        // The files are moved; from here on, failures only leave the
+        // history or index behind and are reported, not returned.
+        if let Err(e) = self.sync_relocated_history(&old_path, &new_path, &note.content, &planned) {
+            report.sync_errors.push(e.to_string());
+        }
+        if let Err(e) = self.sync_relocated_index(&old_path, &new_path, &planned) {
+            report.sync_errors.push(e.to_string());
+        }
+
+        Ok(report)
+    }
+
+    /// Records the moved note and the files whose links were rewritten in
+    /// the history, moving the note's revisions along.
+    fn sync_relocated_history(
+        &self,
+        old_path: &Path,
+        new_path: &Path,
+        content: &str,
+        planned: &[(PathBuf, String, LinkRewrite)],
+    ) -> Result<(), OraError> {
+        let Some(history) = self.history()? else {
+            return Ok(());
+        };
+
+        history.rename(old_path, new_path)?;
+        history.record(new_path, content, RevisionSource::Library)?;
+        for (path, _, rewrite) in planned {
+            if path != old_path {
+                history.record(path, &rewrite.new_content, RevisionSource::Library)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the note in the index, re-indexes the files whose links were
+    /// rewritten and publishes the changes.
+    fn sync_relocated_index(
+        &self,
+        old_path: &Path,
+        new_path: &Path,
+        planned: &[(PathBuf, String, LinkRewrite)],
+    ) -> Result<(), OraError> {
+        let Some(index) = &self.index else {
+            return Ok(());
+        };
+
+        let note = LocalNote::open(new_path)?;
+        index.rename_note(old_path, &note)?;
+        self.publish(&note.path, |id| NoteEvent::NoteRenamed {
+            from: old_path.to_path_buf(),
+            to: note.path.clone(),
+            id,
+        })?;
+        for (path, _, _) in planned {
+            if path != old_path {
+                let rewritten = LocalNote::open(path)?;
+                index.index_note(&rewritten)?;
+                self.publish(&rewritten.path, |id| NoteEvent::NoteModified {
+                    path: rewritten.path.clone(),
+                    id,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the wikilink keys of `note` that other notes among `files`
+    /// share, so links using them cannot be attributed to `note` alone.
+    fn ambiguous_keys(&self, note: &LocalNote, files: &[PathBuf]) -> HashSet<String> {
+        let own = note_keys(&note.title, &note.path, &self.shelf.root);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for path in files {
+            let Some(title) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            for key in note_keys(title, path, &self.shelf.root) {
+                if own.contains(&key) {
+                    *counts.entry(key).or_default() += 1;
+                }
+            }
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Moves or copies a note into a folder of another shelf.
+    ///
+    /// The note keeps its filename and its file timestamps. Both shelves'
+    /// indexes are kept in sync: on a move, the note leaves the source
+    /// index and keeps its stable ID and index timestamps (`created_at`,
+    /// `updated_at`) in the target index. Subscribers of
+    /// the source see [`NoteEvent::NoteRemoved`] (on a move) and those of
+    /// the target [`NoteEvent::NoteCreated`].
+    ///
+    /// If the source shelf keeps a history, the note's revisions are copied
+    /// to the target history (and dropped from the source on a move). If
+    /// the target keeps one, the transferred content is recorded there as a
+    /// library revision.
+    ///
+    /// Links are not rewritten: links to the note from the source shelf and
+    /// relative links inside it may stop resolving.
+    ///
+    /// # Arguments
+    /// * `title` - Title or shelf-relative path of the note in this shelf
+    /// * `target` - Manager of the target shelf
+    /// * `folder` - Folder in the target shelf, empty for its root; created if missing
+    /// * `options` - Move or copy, and the [`ConflictPolicy`]
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if the note path or folder is invalid
+    /// - [`OraError::NoChanges`] if the note would be transferred onto itself
+    /// - [`OraError`] if a file cannot be read or written, or on a conflict
+    ///   under [`ConflictPolicy::Fail`]
+    pub fn transfer_note(
+        &self,
+        title: &str,
+        target: &ShelfManager,
+        folder: &str,
+        options: &TransferOptions,
+    ) -> Result<TransferReport, OraError> {
+        let path = self.note_path(title)?;
+        if !path.is_file() {
+            return Err(NoteError::NotFound(title.to_string()).into());
+        }
+        let dir = target.folder_path(folder)?;
+
+        let mut report = TransferReport::default();
+        match self.transfer_file(&path, target, &dir, options)? {
+            Some(destination) => report.transferred.push((path, destination)),
+            None => report.skipped.push(path),
+        }
+        Ok(report)
+    }
+
+    /// Moves or copies a folder, with the notes inside it, into another
+    /// shelf.
+    ///
+    /// The folder is placed inside `target_folder` under its own name and
+    /// merged with a folder of that name if one exists; collisions are
+    /// resolved note by note. Each note is transferred as with
+    /// [`ShelfManager::transfer_note`]. Only notes are transferred; on a
+    /// move, source folders left empty are removed and folders still
+    /// holding other files are kept.
+    ///
+    /// If a note fails to transfer, the notes transferred before it stay in
+    /// the target shelf.
+    ///
+    /// # Errors
+    /// - [`NoteError::InvalidPath`] if either folder path is invalid, the
+    ///   folder is the shelf root, or the target is inside the folder
+    /// - [`NoteError::NotFound`] if the folder does not exist
+    /// - [`OraError`] as for [`ShelfManager::transfer_note`]
+    pub fn transfer_folder(
+        &self,
+        folder: &str,
+        target: &ShelfManager,
+        target_folder: &str,
+        options: &TransferOptions,
+    ) -> Result<TransferReport, OraError> {
+        let source = self.folder_path(folder)?;
+        let name = match source.file_name() {
+            Some(name) if source != self.shelf.root => name,
+            _ => return Err(NoteError::InvalidPath.into()),
+        };
+        if !source.is_dir() {
+            return Err(NoteError::NotFound(folder.to_string()).into());
+        }
+        let destination = target.folder_path(target_folder)?.join(name);
+        if destination.starts_with(&source) {
+            return Err(NoteError::InvalidPath.into());
+        }
+
+        let mut paths = markdown_files(&self.shelf.root, &source, &self.ignore_rules()?)?;
+        paths.sort();
+
+        let mut report = TransferReport::default();
+        for path in paths {
+            let relative = path.strip_prefix(&source).unwrap_or(&path);
+            let dir = match relative.parent() {
+                Some(parent) => destination.join(parent),
+                None => destination.clone(),
+            };
+            match self.transfer_file(&path, target, &dir, options)? {
+                Some(moved) => report.transferred.push((path, moved)),
+                None => report.skipped.push(path),
+            }
+        }
+
+        if options.mode == TransferMode::Move {
+            remove_empty_dirs(&source);
+        }
+        Ok(report)
+    }
+
+    /// Transfers the note at `path` into `dir` of the `target` shelf.
+    ///
+    /// # Returns
+    /// The note's new path, or `None` if it was skipped
+    fn transfer_file(
+        &self,
+        path: &Path,
+        target: &ShelfManager,
+        dir: &Path,
+        options: &TransferOptions,
+    ) -> Result<Option<PathBuf>, OraError> {
+        let file_name = path.file_name().ok_or(NoteError::InvalidPath)?;
+        let mut destination = dir.join(file_name);
+        if destination == path {
+            return Err(OraError::NoChanges);
+        }
+        let mut replaced = None;
+        if destination.exists() {
+            match options.on_conflict {
+                ConflictPolicy::Rename => {
+                    let title = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                    destination = create_unique_filename(title, dir);
+                }
+                ConflictPolicy::Overwrite => {
+                    if let Some(index) = &target.index {
+                        replaced = index.get_by_path(&destination)?.map(|n| n.id);
+                    }
+                }
+                ConflictPolicy::Skip => return Ok(None),
+                ConflictPolicy::Fail => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", destination.display()),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let origin = match &self.index {
+            Some(index) => index.origin(path)?,
+            None => None,
+        };
+
+        fs::create_dir_all(dir)?;
+        match options.mode {
+            TransferMode::Move => {
+                if fs::rename(path, &destination).is_err() {
+                    copy_file(path, &destination)?;
+                    fs::remove_file(path)?;
+                }
+            }
+            TransferMode::Copy => copy_file(path, &destination)?,
+        }
+
+        let target_history = target.history()?;
+        if let Some(history) = self.history()? {
+            if let Some(target_history) = &target_history {
+                target_history.import(&destination, &history.revisions(path)?)?;
+            }
+            if options.mode == TransferMode::Move {
+                history.forget(path)?;
+            }
+        }
+        if let Some(target_history) = &target_history {
+            let content = fs::read_to_string(&destination)?;
+            target_history.record(&destination, &content, RevisionSource::Library)?;
+        }
+
+        if options.mode == TransferMode::Move
+            && let Some(index) = &self.index
+        {
+            let removed = LocalNote::at_path(path);
+            index.remove_note(&removed)?;
+            if let (Some(subscribers), Some(origin)) = (&self.subscribers, &origin) {
+                subscribers.publish(NoteEvent::NoteRemoved {
+                    path: removed.path,
+                    id: origin.id.clone(),
+                });
+            }
+        }
+
+        if let Some(index) = &target.index
+            && !index.is_ignored(&destination)
+        {
+            if let (Some(subscribers), Some(old_id)) = (&target.subscribers, replaced) {
+                subscribers.publish(NoteEvent::NoteRemoved {
+                    path: destination.clone(),
+                    id: old_id,
+                });
+            }
+            let note = LocalNote::open(&destination)?;
+            match (&origin, options.mode) {
+                (Some(origin), TransferMode::Move) => index.import_note(&note, origin)?,
+                _ => {
+                    index.remove_note(&note)?;
+                    index.index_note(&note)?;
+                }
+            }
+            target.publish(&destination, |id| NoteEvent::NoteCreated {
+                path: destination.clone(),
+                id,
+            })?;
+        }
+        Ok(Some(destination))
+    }
+
+    /// Returns the revision store of the shelf, or `None` if the shelf
+    /// config does not enable history (see [`crate::history`]).
+    ///
+    /// A manager without an index opens the history database on first use
+    /// and keeps it open for its lifetime.
+    ///
+    /// # Errors
+    /// Returns [`OraError`] if the history database cannot be opened
+    pub fn history(&self) -> Result<Option<History>, OraError> {
+        if let Some(index) = &self.index {
+            return Ok(index.history().cloned());
+        }
+        if !self.shelf.config.history {
+            return Ok(None);
+        }
+        if let Some(history) = self.history.get() {
+            return Ok(Some(history.clone()));
+        }
+        let history = History::open(&self.shelf.root, self.shelf.config.history_limit)?;
+        Ok(Some(self.history.get_or_init(|| history).clone()))
+    }
+
+    /// Lists the recorded revisions of a note, newest first.
+    ///
+    /// # Errors
+    /// - [`ShelfError::HistoryDisabled`] if the shelf keeps no history
+    /// - [`NoteError::InvalidPath`] if the note path is invalid
+    /// - [`OraError`] if the history cannot be read
+    pub fn revisions(&self, title: &str) -> Result<Vec<Revision>, OraError> {
+        let path = self.note_path(title)?;
+        self.require_history()?.revisions(&path)
+    }
+
+    /// Compares two revisions line by line; see [`diff_lines`](crate::history::diff_lines).
+    ///
+    /// # Arguments
+    /// * `from` - ID of the old revision
+    /// * `to` - ID of the new revision
+    ///
+    /// # Errors
+    /// - [`ShelfError::HistoryDisabled`] if the shelf keeps no history
+    /// - [`NoteError::NotFound`] if either revision does not exist
+    pub fn diff_revisions(&self, from: i64, to: i64) -> Result<Vec<DiffLine>, OraError> {
+        self.require_history()?.diff(from, to)
+    }
+
+    /// Restores an older revision of a note by saving its content as the
+    /// note's current content.
+    ///
+    /// This is a regular save: it records a new revision, and the restored
+    /// revision and everything after it stay in the history.
+    ///
+    /// # Errors
+    /// - [`ShelfError::HistoryDisabled`] if the shelf keeps no history
+    /// - [`NoteError::NotFound`] if the revision does not belong to the note
+    /// - [`OraError::NoChanges`] if the note already has that content
+    /// - [`OraError`] if the note cannot be read or written
+    pub fn restore_revision(&self, title: &str, revision: i64) -> Result<LocalNote, OraError> {
+        let path = self.note_path(title)?;
+        let found = self
+            .require_history()?
+            .get(revision)?
+            .filter(|found| found.path == path)
+            .ok_or_else(|| NoteError::NotFound(format!("revision {revision}")))?;
+        self.update_note(title, None, Some(&found.content))
+    }
+
+    /// Returns the revision store, failing if the shelf keeps no history.
+    fn require_history(&self) -> Result<History, OraError> {
+        self.history()?
+            .ok_or_else(|| ShelfError::HistoryDisabled(self.shelf.name.clone()).into())
+    }
+
+    /// Records a library save of `note` if the shelf keeps a history.
+    fn record_revision(&self, note: &LocalNote) -> Result<(), OraError> {
+        if let Some(history) = self.history()? {
+            history.record(&note.path, &note.content, RevisionSource::Library)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the ignore rules of the shelf: the index's rules if the
+    /// manager has one, otherwise freshly loaded from disk.
+    fn ignore_rules(&self) -> Result<IgnoreRules, OraError> {
+        match &self.index {
+            Some(index) => Ok(index.ignore_rules()),
+            None => Ok(IgnoreRules::load(
+                &self.shelf.root,
+                &self.shelf.config.ignore,
+            )?),
+        }
+    }
+
+    /// Returns the path of the note at the shelf-relative `path`, following
+    /// the shelf's filename style for the last component.
+    ///
+    /// Rejects the same folder components as [`ShelfManager::folder_path`],
+    /// and empty or hidden note names.
+    fn note_path(&self, path: &str) -> Result<PathBuf, NoteError> {
+        let path = path.strip_suffix(".md").unwrap_or(path);
+        let (folder, title) = split_folder(path);
+        let stem = match self.shelf.config.filename_style {
+            FilenameStyle::Title => title.to_string(),
+            style => style.file_stem(title),
+        };
+        check_stem(&stem)?;
+        Ok(self.folder_path(folder)?.join(format!("{stem}.md")))
+    }
+
+    /// Resolves a shelf-relative folder path, empty for the shelf root.
+    ///
+    /// Rejects `.`, `..` and hidden components so paths cannot leave the
+    /// shelf or reach hidden directories.
+    fn folder_path(&self, folder: &str) -> Result<PathBuf, NoteError> {
+        let mut path = self.shelf.root.clone();
+        for part in folder.split('/').filter(|part| !part.is_empty()) {
+            if part.starts_with('.') || part.contains('\\') {
+                return Err(NoteError::InvalidPath);
+            }
+            path.push(part);
+        }
+        Ok(path)
+    }
+
+    /// Publishes an event for the note just indexed at `path`, if a watcher
+    /// is connected. `event` receives the note's stable ID.
+    fn publish<F>(&self, path: &Path, event: F) -> Result<(), OraError>
+    where
+        F: FnOnce(String) -> NoteEvent,
+    {
+        let (Some(index), Some(subscribers)) = (&self.index, &self.subscribers) else {
+            return Ok(());
+        };
+
+        if let Some(indexed) = index.get_by_path(path)? {
+            subscribers.publish(event(indexed.id));
+        }
+        Ok(())
+    }
+}
+
+/// Sort key of a note in [`ShelfManager::list_notes_sorted`].
+///
+/// All notes of one listing get the same variant.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum ListKey {
+    None,
+    Text(String),
+    Time(SystemTime),
+    Recency(Reverse<SystemTime>),
+    Size(u64),
+}
+
+/// Rejects note file stems that are empty or would make the note hidden,
+/// since listings, the index and the watcher skip hidden files.
+fn check_stem(stem: &str) -> Result<(), NoteError> {
+    if stem.trim().is_empty() || stem.starts_with('.') || stem.contains('\\') {
+        return Err(NoteError::InvalidPath);
+    }
+    Ok(())
+}
+
+/// Splits a shelf-relative note path into its folder and title parts.
+fn split_folder(path: &str) -> (&str, &str) {
+    let path = path.trim_matches('/');
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+/// Copies a file, keeping its modification and access times.
+fn copy_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = fs::metadata(from)?;
+    fs::copy(from, to)?;
+
+    let mut times = fs::FileTimes::new().set_modified(metadata.modified()?);
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    fs::File::options().write(true).open(to)?.set_times(times)
+}
+
+/// Removes `dir` and its subdirectories, deepest first, where they are
+/// empty. Directories that still hold files are kept.
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                remove_empty_dirs(&path);
+            }
+        }
+    }
+    let _ = fs::remove_dir(dir);
 }
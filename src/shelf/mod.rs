@@ -1,2 +1,5 @@
+pub mod config;
+pub mod library;
 pub mod manager;
 pub mod storage;
+pub mod trash;
@@ -1,8 +1,9 @@
 //! Shelf storage and management functionality.
 //!
 //! This module provides the core storage abstraction for organizing notes
-//! in shelf-based directories. Shelves are stored under `~/Documents/shelves/`
-//! and provide a clean way to separate different note collections.
+//! in shelf-based directories. Shelves are stored in a [`Library`] directory,
+//! `~/Documents/shelves/` by default, and provide a clean way to separate
+//! different note collections.
 //!
 //! # Shelf Structure
 //!
@@ -10,14 +11,17 @@
 //! - Markdown notes (`.md` files)
 //! - Optional subdirectories for organization
 //! - A hidden SQLite database (`.shelf.db`) for search indexing
+//! - A hidden configuration file (`.shelf.toml`), see [`crate::shelf::config`]
+//! - A hidden trash directory (`.trash`) for deleted notes, see [`crate::shelf::trash`]
 //!
 //! # Error Handling
 //!
 //! All shelf operations return [`ShelfError`] which provides specific
 //! error types for different failure conditions.
 
-use dirs;
-use std::fs;
+use crate::shelf::config::ShelfConfig;
+use crate::shelf::library::Library;
+use crate::shelf::trash::{Trash, TrashItem};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -36,6 +40,28 @@ pub enum ShelfError {
     #[error("invalid shelf name")]
     InvalidInput,
 
+    /// No entry with the given ID is in the trash.
+    #[error("trash item not found: {0}")]
+    TrashItemNotFound(String),
+
+    /// The shelf does not keep a revision history.
+    #[error("history is not enabled for shelf: {0}")]
+    HistoryDisabled(String),
+
+    /// The shelf's `.shelf.toml` could not be parsed.
+    #[error("invalid shelf config at line {line}: {message}")]
+    InvalidConfig { line: usize, message: String },
+
+    /// A rename failed, and so did restoring some of the files it had
+    /// already rewritten; those keep their rewritten links.
+    #[error("{source} (could not restore {} rewritten file(s))", .unrestored.len())]
+    RollbackFailed {
+        /// The error that made the rename fail.
+        source: crate::domain::NoteError,
+        /// Each file that could not be restored, with the reason.
+        unrestored: Vec<(PathBuf, crate::domain::NoteError)>,
+    },
+
     /// Permission denied when accessing shelf directory.
     #[error("permission denied")]
     PermissionDenied,
@@ -55,80 +81,46 @@ pub struct Shelf {
 
     /// The human-readable name of the shelf.
     pub name: String,
+
+    /// Settings loaded from the shelf's `.shelf.toml`.
+    pub config: ShelfConfig,
 }
 
 impl Shelf {
-    /// Creates a brand-new shelf directory under `~/Documents/shelves/{name}`.
+    /// Creates a brand-new shelf directory in the default [`Library`].
+    ///
+    /// See [`Library::create_shelf`]; the library is found with [`Library::locate`].
     ///
     /// - Validates the shelf name.
     /// - Returns [`ShelfError::AlreadyExists`] if the directory already exists.
     /// - Returns [`ShelfError::InvalidInput`] if the name is empty or has invalid characters.
     /// - Returns [`ShelfError::Io`] for any underlying filesystem error.
-
     pub fn new(name: &str) -> Result<Self, ShelfError> {
-        let shelf_name = Self::valid_shelf(name)?;
-        let root = Shelf::shelf_path(Some(&shelf_name))?;
-
-        if root.exists() {
-            return Err(ShelfError::AlreadyExists(shelf_name));
-        }
-
-        fs::create_dir_all(&root)?; // converted to ShelfError::Io automatically
-
-        Ok(Self {
-            root,
-            name: shelf_name,
-        })
+        Library::locate()?.create_shelf(name)
     }
 
-    /// Opens an existing shelf directory under `~/Documents/shelves/{name}`.
+    /// Opens an existing shelf directory in the default [`Library`].
     ///
     /// - Returns [`ShelfError::NotFound`] if the shelf does not exist.
     /// - Returns [`ShelfError::InvalidInput`] if the path exists but is not a directory.
     /// - Returns [`ShelfError::Io`] for other filesystem errors.
     pub fn open(name: &str) -> Result<Self, ShelfError> {
-        let root = Self::shelf_path(Some(name))?;
-
-        if !root.exists() {
-            return Err(ShelfError::NotFound(name.to_string()));
-        }
-
-        if !root.is_dir() {
-            return Err(ShelfError::InvalidInput);
-        }
-
-        Ok(Self {
-            root,
-            name: name.to_string(),
-        })
+        Library::locate()?.open_shelf(name)
     }
-    /// Lists all shelves under `~/Documents/shelves`.
+
+    /// Lists all shelves in the default [`Library`].
     ///
-    /// Reads the `shelves` base directory and collects all subdirectories
+    /// Reads the shelves base directory and collects all subdirectories
     /// as shelf names.  
     ///
     /// - Returns a vector of valid shelf names.  
     /// - Returns [`ShelfError::Io`] if the directory cannot be read.  
     /// - Returns [`ShelfError::NotFound`] if the base documents directory is missing.
     pub fn list_shelves() -> Result<Vec<String>, ShelfError> {
-        let shelf_base = Shelf::shelf_path(None)?;
-
-        let names = fs::read_dir(&shelf_base)?
-            .filter_map(|res| {
-                let entry = res.ok()?;
-                let ft = entry.file_type().ok()?;
-                if ft.is_dir() {
-                    Some(entry.file_name().to_string_lossy().into_owned())
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        Ok(names)
+        Library::locate()?.list_shelves()
     }
 
-    /// Ensures that the shelf with the given `name` exists.
+    /// Ensures that the shelf with the given `name` exists in the default [`Library`].
     ///
     /// If missing, creates a new shelf directory;  
     /// if it already exists, simply opens it.  
@@ -137,16 +129,21 @@ impl Shelf {
     /// - [`ShelfError::InvalidInput`] if the name is empty or has invalid characters
     /// - [`ShelfError::Io`] if the directory creation fails
     pub fn ensure_exists(name: &str) -> Result<Self, ShelfError> {
-        let root = Shelf::shelf_path(Some(name))?;
-        if !root.exists() {
-            fs::create_dir_all(&root)?; // auto Io -> ShelfError
-        }
+        Library::locate()?.ensure_shelf(name)
+    }
 
-        Shelf::open(name)
+    /// Returns the [`Library`] this shelf lives in: its parent directory.
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidInput`] if the shelf root has no parent directory
+    pub fn library(&self) -> Result<Library, ShelfError> {
+        let parent = self.root.parent().ok_or(ShelfError::InvalidInput)?;
+        Ok(Library::new(parent))
     }
 
     /// Renames this shelf on disk to a new name.
     ///
+    /// The shelf stays in the library it lives in (see [`Shelf::library`]).
     /// Updates both the directory on the filesystem and the in‑memory
     /// `name` and `root` fields if successful.
     ///
@@ -155,63 +152,62 @@ impl Shelf {
     /// - [`ShelfError::AlreadyExists`] if a shelf with the target name already exists
     /// - [`ShelfError::Io`] if the rename operation fails
     pub fn rename(&mut self, new_name: &str) -> Result<(), ShelfError> {
-        let valid_new_name = Shelf::valid_shelf(new_name)?;
-        let new_path = Self::shelf_path(Some(&valid_new_name))?;
-
-        if new_path.exists() {
-            return Err(ShelfError::AlreadyExists(valid_new_name));
-        }
-
-        fs::rename(&self.root, &new_path)?; // propagates Io
-
-        self.name = valid_new_name;
-        self.root = new_path;
-
-        Ok(())
+        self.library()?.rename_shelf(self, new_name)
     }
 
-    /// Deletes this shelf and all its contents from disk.
+    /// Deletes this shelf and all its contents.
+    ///
+    /// The shelf directory is moved into the trash of its library (see
+    /// [`Library::trash`]), from where it can be restored until the trash
+    /// is purged.
+    ///
+    /// # Returns
+    /// The trash entry holding the shelf
     ///
-    /// Permanently removes the directory at `self.root`.
-    /// Returns an error if removal fails (e.g. permissions, in use).
-    pub fn delete_shelf(&self) -> Result<(), ShelfError> {
-        fs::remove_dir_all(&self.root)?;
-        Ok(())
+    /// # Errors
+    /// Returns an error if the move fails (e.g. permissions, in use).
+    pub fn delete_shelf(&self) -> Result<TrashItem, ShelfError> {
+        self.library()?.trash().put(&self.root)
+    }
+
+    /// Returns the trash of this shelf, holding its deleted notes and
+    /// folders.
+    pub fn trash(&self) -> Trash {
+        Trash::new(&self.root)
     }
 
-    /// Resolves a given shelf name into a full path under `~/Documents/shelves/{name}`.
+    /// Resolves a given shelf name into a full path in the default [`Library`].
     ///
     /// # Errors
     /// - [`ShelfError::NotFound`] if the user's documents directory cannot be determined
     pub fn shelf_path(name: Option<&str>) -> Result<PathBuf, ShelfError> {
-        let docs = dirs::document_dir()
-            .ok_or_else(|| ShelfError::NotFound("documents directory".into()))?;
+        Ok(Library::locate()?.shelf_path(name))
+    }
+}
 
-        let shelves = docs.join("shelves");
+/// Validates a proposed shelf name for filesystem safety.
+///
+/// - Trims whitespace and ensures it is not empty
+/// - Rejects forbidden characters (`/`, `\`, `:`, `"`, `*`, `?`, `<`, `>`, `|`)
+/// - Rejects hidden names starting with `.`, including `.`, `..` and the
+///   library's `.trash`, which shelf listings skip
+///
+/// Returns the sanitized name on success,
+/// or [`ShelfError::InvalidInput`] if validation fails.
+pub(crate) fn valid_shelf(shelf: &str) -> Result<String, ShelfError> {
+    let trimmed = shelf.trim();
 
-        Ok(match name {
-            Some(name) => shelves.join(name),
-            None => shelves,
-        })
+    if trimmed.is_empty() {
+        return Err(ShelfError::InvalidInput);
     }
-    /// Validates a proposed shelf name for filesystem safety.
-    ///
-    /// - Trims whitespace and ensures it is not empty
-    /// - Rejects forbidden characters (`/`, `\`, `:`, `"`, `*`, `?`, `<`, `>`, `|`)
-    ///
-    /// Returns the sanitized name on success,
-    /// or [`ShelfError::InvalidInput`] if validation fails.
-    fn valid_shelf(shelf: &str) -> Result<String, ShelfError> {
-        let trimmed = shelf.trim();
-
-        if trimmed.is_empty() {
-            return Err(ShelfError::InvalidInput);
-        }
 
-        if trimmed.contains(&['/', '\\', ':', '"', '*', '?', '<', '>', '|'][..]) {
-            return Err(ShelfError::InvalidInput);
-        }
+    if trimmed.contains(&['/', '\\', ':', '"', '*', '?', '<', '>', '|'][..]) {
+        return Err(ShelfError::InvalidInput);
+    }
 
-        Ok(trimmed.to_owned())
+    if trimmed.starts_with('.') {
+        return Err(ShelfError::InvalidInput);
     }
+
+    Ok(trimmed.to_owned())
 }
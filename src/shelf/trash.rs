@@ -0,0 +1,314 @@
+//! Soft deletion into a trash directory.
+//!
+//! Deleted notes and folders are moved into a hidden `.trash` directory
+//! instead of being removed, so they can be restored later. Each shelf has
+//! its own trash in its root, and a [`Library`](crate::shelf::library::Library)
+//! has one for deleted shelves.
+//!
+//! # Layout
+//!
+//! The trash follows the layout of the freedesktop.org trash:
+//!
+//! ```text
+//! .trash/
+//!   files/<id>            the deleted file or directory
+//!   info/<id>.trashinfo   where it came from and when it was deleted
+//! ```
+//!
+//! An info file holds two `key=value` lines: `path`, the original path
+//! relative to the trash's owner, and `deleted`, the deletion time in
+//! seconds since the Unix epoch. Entries whose `path` is absolute or
+//! contains `..` are skipped, so a tampered info file cannot restore
+//! outside the owner.
+//!
+//! Being hidden, the trash is skipped by the index, the watcher and note
+//! listings like any other hidden directory.
+
+use crate::domain::create_unique_filename;
+use crate::shelf::storage::ShelfError;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Name of the trash directory inside a shelf or library.
+pub const TRASH_DIR: &str = ".trash";
+
+const INFO_EXTENSION: &str = "trashinfo";
+
+/// An entry in a [`Trash`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashItem {
+    /// Identifier of the entry within its trash.
+    pub id: String,
+
+    /// Absolute path the file or directory was deleted from.
+    pub original_path: PathBuf,
+
+    /// When the entry was moved to the trash.
+    pub deleted_at: SystemTime,
+
+    /// Current location of the file or directory inside the trash.
+    pub path: PathBuf,
+
+    /// `true` if the entry is a directory.
+    pub is_dir: bool,
+}
+
+/// The trash of a shelf or library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trash {
+    root: PathBuf,
+}
+
+impl Trash {
+    /// Creates a handle to the trash of the directory `root`.
+    ///
+    /// The trash directory itself is created on first use.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the trash directory, `{root}/.trash`.
+    pub fn dir(&self) -> PathBuf {
+        self.root.join(TRASH_DIR)
+    }
+
+    /// Moves a file or directory inside the root into the trash.
+    ///
+    /// # Arguments
+    /// * `path` - Absolute path of the file or directory to delete
+    ///
+    /// # Returns
+    /// The new trash entry
+    ///
+    /// # Errors
+    /// - [`ShelfError::InvalidInput`] if `path` is not inside the root, is
+    ///   the root itself, or is inside the trash
+    /// - [`ShelfError::Io`] if `path` does not exist or cannot be moved
+    pub fn put(&self, path: &Path) -> Result<TrashItem, ShelfError> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .map_err(|_| ShelfError::InvalidInput)?;
+        if relative.as_os_str().is_empty() || path.starts_with(self.dir()) {
+            return Err(ShelfError::InvalidInput);
+        }
+        let is_dir = fs::symlink_metadata(path)?.is_dir();
+
+        fs::create_dir_all(self.files_dir())?;
+        fs::create_dir_all(self.info_dir())?;
+
+        let id = Uuid::new_v4().simple().to_string();
+        let deleted_at = SystemTime::now();
+        let seconds = deleted_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let info = format!(
+            "path={}\ndeleted={seconds}\n",
+            relative.display().to_string().replace('\\', "/")
+        );
+        fs::write(self.info_path(&id), info)?;
+
+        let trashed = self.files_dir().join(&id);
+        if let Err(e) = fs::rename(path, &trashed) {
+            let _ = fs::remove_file(self.info_path(&id));
+            return Err(e.into());
+        }
+
+        Ok(TrashItem {
+            id,
+            original_path: path.to_path_buf(),
+            deleted_at: UNIX_EPOCH + Duration::from_secs(seconds),
+            path: trashed,
+            is_dir,
+        })
+    }
+
+    /// Lists the entries in the trash, most recently deleted first.
+    ///
+    /// Info files without a matching entry, and entries without a readable
+    /// info file, are skipped.
+    ///
+    /// # Errors
+    /// - [`ShelfError::Io`] if the trash directory cannot be read
+    pub fn list(&self) -> Result<Vec<TrashItem>, ShelfError> {
+        let entries = match fs::read_dir(self.info_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut items = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some(INFO_EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str())
+                && let Some(item) = self.read_item(id)
+            {
+                items.push(item);
+            }
+        }
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then(a.id.cmp(&b.id)));
+        Ok(items)
+    }
+
+    /// Looks up a trash entry by its ID.
+    ///
+    /// # Errors
+    /// - [`ShelfError::TrashItemNotFound`] if there is no such entry
+    pub fn get(&self, id: &str) -> Result<TrashItem, ShelfError> {
+        self.read_item(id)
+            .ok_or_else(|| ShelfError::TrashItemNotFound(id.to_string()))
+    }
+
+    /// Moves an entry back to where it was deleted from.
+    ///
+    /// Missing parent directories are recreated. If something now exists at
+    /// the original path, the entry is restored next to it under a name
+    /// with a number suffix, as when creating notes (`Plan 1.md`).
+    ///
+    /// # Returns
+    /// The path the entry was restored to
+    ///
+    /// # Errors
+    /// - [`ShelfError::TrashItemNotFound`] if there is no such entry
+    /// - [`ShelfError::Io`] if the entry cannot be moved
+    pub fn restore(&self, id: &str) -> Result<PathBuf, ShelfError> {
+        let item = self.get(id)?;
+        let destination = unique_path(&item.original_path, item.is_dir);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&item.path, &destination)?;
+        fs::remove_file(self.info_path(id))?;
+        Ok(destination)
+    }
+
+    /// Permanently deletes an entry.
+    ///
+    /// # Errors
+    /// - [`ShelfError::TrashItemNotFound`] if there is no such entry
+    /// - [`ShelfError::Io`] if the entry cannot be removed
+    pub fn remove(&self, id: &str) -> Result<TrashItem, ShelfError> {
+        let item = self.get(id)?;
+        if item.is_dir {
+            fs::remove_dir_all(&item.path)?;
+        } else {
+            fs::remove_file(&item.path)?;
+        }
+        fs::remove_file(self.info_path(id))?;
+        Ok(item)
+    }
+
+    /// Permanently deletes entries that have been in the trash for longer
+    /// than `retention`.
+    ///
+    /// # Returns
+    /// The entries that were deleted
+    ///
+    /// # Errors
+    /// - [`ShelfError::Io`] if the trash cannot be read or an entry cannot
+    ///   be removed
+    pub fn purge_older_than(&self, retention: Duration) -> Result<Vec<TrashItem>, ShelfError> {
+        let cutoff = SystemTime::now()
+            .checked_sub(retention)
+            .unwrap_or(UNIX_EPOCH);
+        let mut purged = Vec::new();
+        for item in self.list()? {
+            if item.deleted_at < cutoff {
+                purged.push(self.remove(&item.id)?);
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Permanently deletes every entry in the trash.
+    ///
+    /// # Returns
+    /// The entries that were deleted
+    ///
+    /// # Errors
+    /// - [`ShelfError::Io`] if the trash cannot be read or an entry cannot
+    ///   be removed
+    pub fn empty(&self) -> Result<Vec<TrashItem>, ShelfError> {
+        self.list()?
+            .into_iter()
+            .map(|item| self.remove(&item.id))
+            .collect()
+    }
+
+    /// Reads the entry `id`, or `None` if it is missing or its info file is
+    /// malformed.
+    fn read_item(&self, id: &str) -> Option<TrashItem> {
+        if id.is_empty() || id.contains(['/', '\\', '.']) {
+            return None;
+        }
+        let text = fs::read_to_string(self.info_path(id)).ok()?;
+        let mut original = None;
+        let mut deleted = None;
+        for line in text.lines() {
+            match line.split_once('=') {
+                Some(("path", value)) => original = relative_path(value).map(|p| self.root.join(p)),
+                Some(("deleted", value)) => deleted = value.trim().parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+
+        let path = self.files_dir().join(id);
+        let metadata = fs::symlink_metadata(&path).ok()?;
+        Some(TrashItem {
+            id: id.to_string(),
+            original_path: original?,
+            deleted_at: UNIX_EPOCH + Duration::from_secs(deleted?),
+            path,
+            is_dir: metadata.is_dir(),
+        })
+    }
+
+    fn files_dir(&self) -> PathBuf {
+        self.dir().join("files")
+    }
+
+    fn info_dir(&self) -> PathBuf {
+        self.dir().join("info")
+    }
+
+    fn info_path(&self, id: &str) -> PathBuf {
+        self.info_dir().join(format!("{id}.{INFO_EXTENSION}"))
+    }
+}
+
+/// Returns `path` if it is free, otherwise the first free variant with a
+/// number suffix: `Plan 1.md`, `Plan 2.md`, ... or `folder 1`, ...
+fn unique_path(path: &Path, is_dir: bool) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let extension = path.extension().and_then(|s| s.to_str());
+    if !is_dir && extension == Some("md") {
+        return create_unique_filename(stem, dir);
+    }
+
+    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+    let (base, extension) = match extension {
+        Some(extension) if !is_dir => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|count| dir.join(format!("{base} {count}{extension}")))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+/// Parses the `path` of an info file, which must stay inside the trash's
+/// owner: a non-empty relative path of plain names, without `..`.
+fn relative_path(value: &str) -> Option<&Path> {
+    let path = Path::new(value);
+    let plain = path.components().all(|c| matches!(c, Component::Normal(_)));
+    (plain && !value.is_empty()).then_some(path)
+}
@@ -0,0 +1,131 @@
+//! Tag extraction for notes.
+//!
+//! Tags come from two places:
+//! - The `tags:` (or `tag:`) key of the front matter header, as a list or a
+//!   comma/space separated scalar
+//! - Inline `#hashtags` in the note body
+//!
+//! Tags may be nested with `/`, e.g. `#project/ora` is a child of `#project`.
+//! All tags are normalized to lowercase without the leading `#`, so
+//! `#Project/Ora` and `tags: [project/ora]` yield the same tag.
+//!
+//! Hashtags inside fenced code blocks and inline code spans are ignored, as
+//! are Markdown headings (`# Title`) and purely numeric tags such as issue
+//! references (`#123`).
+
+use crate::front_matter::FrontMatter;
+use std::collections::BTreeSet;
+
+/// Front matter keys that hold tags.
+const TAG_KEYS: [&str; 2] = ["tags", "tag"];
+
+/// Extracts all tags from note content (front matter and body).
+///
+/// Returns the normalized tags, sorted and without duplicates.
+///
+/// # Examples
+/// ```rust
+/// use ora_core::tags::extract_tags;
+///
+/// let tags = extract_tags("---\ntags: [Rust]\n---\nWorking on #project/ora today.");
+/// assert_eq!(tags, vec!["project/ora", "rust"]);
+/// ```
+pub fn extract_tags(content: &str) -> Vec<String> {
+    let (front_matter, body) = FrontMatter::split(content);
+
+    let mut tags = BTreeSet::new();
+    for key in TAG_KEYS {
+        if let Some(value) = front_matter.get(key) {
+            for item in value.values() {
+                for tag in item.split([',', ' ']) {
+                    if let Some(tag) = normalize_tag(tag) {
+                        tags.insert(tag);
+                    }
+                }
+            }
+        }
+    }
+
+    tags.extend(inline_tags(body));
+    tags.into_iter().collect()
+}
+
+/// Normalizes a single tag.
+///
+/// Strips the leading `#` and surrounding `/`, lowercases it, and rejects
+/// empty or purely numeric tags as well as tags containing characters other
+/// than letters, digits, `_`, `-` and `/`.
+///
+/// # Returns
+/// The normalized tag, or `None` if the input is not a valid tag
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_matches('/');
+
+    if tag.is_empty()
+        || !tag.chars().all(is_tag_char)
+        || tag.chars().all(|c| c.is_ascii_digit() || c == '/')
+        || tag.contains("//")
+    {
+        return None;
+    }
+
+    Some(tag.to_lowercase())
+}
+
+/// Returns the tag itself followed by all of its ancestors.
+///
+/// `project/ora/core` yields `project/ora/core`, `project/ora`, `project`.
+pub fn tag_with_ancestors(tag: &str) -> impl Iterator<Item = &str> {
+    let ends = tag
+        .char_indices()
+        .filter(|(_, c)| *c == '/')
+        .map(|(i, _)| i)
+        .rev();
+    std::iter::once(tag).chain(ends.map(move |end| &tag[..end]))
+}
+
+/// Characters allowed inside a tag.
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Collects `#hashtags` from a Markdown body, skipping code.
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+            continue;
+        }
+        if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut prev = ' ';
+        for (i, c) in line.char_indices() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && (prev.is_whitespace() || prev == '(') {
+                let rest = &line[i + 1..];
+                let end = rest.find(|c| !is_tag_char(c)).unwrap_or(rest.len());
+                if let Some(tag) = normalize_tag(&rest[..end]) {
+                    tags.push(tag);
+                }
+            }
+            prev = c;
+        }
+    }
+
+    tags
+}
@@ -7,10 +7,17 @@
 //!
 //! # Debouncing Logic
 //!
-//! - When an event is received for a file, any existing timer is cancelled
-//! - A new timer is started for the configured duration
-//! - If the timer completes without being cancelled, the event is forwarded
-//! - If another event arrives, the timer is reset
+//! - When an event is received for a file, it is merged with any pending
+//!   event for that file (see [`FsEvent::merge`])
+//! - The file's deadline is pushed back to the configured duration from now
+//! - Once the deadline passes without further events, the event is forwarded
+//! - A file that keeps changing is forwarded at the latest after the max
+//!   wait, counted from its first pending event
+//!
+//! All pending events live in a single deadline queue ordered by time and
+//! keyed by path, served by the thread calling [`Debouncer::run`]. No
+//! threads are spawned per event, so bulk changes such as a `git checkout`
+//! touching thousands of notes cost one queue entry per file.
 //!
 //! # Performance Benefits
 //!
@@ -19,14 +26,56 @@
 //! - Preventing excessive search index updates
 //! - Smoothing out bursty file system activity
 
-use notify::EventKind;
-use std::{collections::HashMap, path::PathBuf, sync::mpsc, thread, time::Duration};
+use crate::watcher::event::FsEvent;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// What to do with pending events when the debouncer is shut down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownMode {
+    /// Forward all pending events immediately before stopping.
+    #[default]
+    Flush,
+
+    /// Drop all pending events.
+    Discard,
+}
+
+/// A message accepted by [`Debouncer::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebouncerMessage {
+    /// A file system event to debounce.
+    Event(FsEvent),
+
+    /// Stop the debouncer, handling pending events as requested.
+    Shutdown(ShutdownMode),
+}
+
+impl From<FsEvent> for DebouncerMessage {
+    fn from(event: FsEvent) -> Self {
+        DebouncerMessage::Event(event)
+    }
+}
+
+/// How many debounce durations a continuously changing file may wait by default.
+const DEFAULT_MAX_WAIT_FACTOR: u32 = 10;
+
+/// An event waiting for its deadline.
+struct Pending {
+    event: FsEvent,
+    first_seen: Instant,
+    deadline: Instant,
+}
 
 /// Debounces file system events to prevent excessive processing.
 ///
-/// The `Debouncer` maintains a collection of active timers, one for each
-/// file path that has pending events. When multiple events arrive for the
-/// same file, only the final event is processed after the debounce delay.
+/// The `Debouncer` keeps one pending event per file path and a queue of
+/// their deadlines. When multiple events arrive for the same file, only the
+/// final (merged) event is processed after the debounce delay.
 ///
 /// # Thread Safety
 ///
@@ -34,72 +83,177 @@ use std::{collections::HashMap, path::PathBuf, sync::mpsc, thread, time::Duratio
 /// via channels. It's not thread-safe for concurrent access from multiple
 /// threads.
 pub struct Debouncer {
-    /// Map of file paths to their timer cancellation channels.
-    active_timers: HashMap<PathBuf, mpsc::Sender<()>>,
+    /// Pending events by file path.
+    pending: HashMap<PathBuf, Pending>,
+
+    /// Deadlines of pending events, earliest first.
+    deadlines: BTreeSet<(Instant, PathBuf)>,
 
     /// Channel for sending debounced events to the handler.
-    output_tx: mpsc::Sender<(EventKind, PathBuf)>,
+    output_tx: mpsc::Sender<FsEvent>,
 
     /// Duration to wait before forwarding events.
     duration: Duration,
+
+    /// Longest time an event may be held back by further events.
+    max_wait: Duration,
 }
 
 impl Debouncer {
     /// Creates a new debouncer with the specified output channel and duration.
     ///
+    /// The max wait defaults to ten times `duration`.
+    ///
     /// # Arguments
     /// * `output_tx` - Channel for sending debounced events
     /// * `duration` - Time to wait before forwarding events
     ///
     /// # Returns
     /// A new `Debouncer` instance
-    pub fn new(output_tx: mpsc::Sender<(EventKind, PathBuf)>, duration: Duration) -> Self {
+    pub fn new(output_tx: mpsc::Sender<FsEvent>, duration: Duration) -> Self {
         Debouncer {
-            active_timers: HashMap::new(),
+            pending: HashMap::new(),
+            deadlines: BTreeSet::new(),
             output_tx,
             duration,
+            max_wait: duration * DEFAULT_MAX_WAIT_FACTOR,
         }
     }
 
-    /// Runs the debouncer, processing events from the input channel.
+    /// Sets the longest time a continuously changing file is held back.
     ///
-    /// This method blocks until the input channel is closed, processing
-    /// events as they arrive. For each event, it cancels any existing
-    /// timer for that file and starts a new one.
-    ///
-    /// # Arguments
-    /// * `input_rx` - Channel to receive raw file system events
+    /// Values below the debounce duration are raised to it.
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait.max(self.duration);
+        self
+    }
+
+    /// Returns the number of files with a pending event.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the earliest deadline of any pending event.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.first().map(|(deadline, _)| *deadline)
+    }
+
+    /// Queues an event received at `now`.
     ///
-    /// # Behavior
-    /// - Blocks until input channel is closed
-    /// - Spawns a new thread for each debounced timer
-    /// - Forwards events after the debounce delay if not cancelled
+    /// Merges it with the pending event for the same path, if any, and
+    /// moves that path's deadline to `now + duration`, capped at the max
+    /// wait after the first pending event.
+    pub fn push(&mut self, event: FsEvent, now: Instant) {
+        let path = event.path().to_path_buf();
+
+        let (event, first_seen) = match self.pending.remove(&path) {
+            Some(pending) => {
+                self.deadlines.remove(&(pending.deadline, path.clone()));
+                (pending.event.merge(event), pending.first_seen)
+            }
+            None => (event, now),
+        };
+
+        let deadline = (now + self.duration).min(first_seen + self.max_wait);
+        self.deadlines.insert((deadline, path.clone()));
+        self.pending.insert(
+            path,
+            Pending {
+                event,
+                first_seen,
+                deadline,
+            },
+        );
+    }
+
+    /// Forwards every event whose deadline is at or before `now`.
     ///
-    /// # Thread Management
+    /// # Returns
+    /// The number of events forwarded
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let mut count = 0;
+        while let Some((deadline, _)) = self.deadlines.first()
+            && *deadline <= now
+        {
+            let (_, path) = self.deadlines.pop_first().unwrap();
+            if let Some(pending) = self.pending.remove(&path) {
+                let _ = self.output_tx.send(pending.event);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Forwards every pending event immediately, in deadline order.
     ///
-    /// Each pending event spawns a short-lived timer thread. These threads
-    /// automatically terminate when either the timer expires or is cancelled.
-    pub fn run(&mut self, input_rx: mpsc::Receiver<(EventKind, PathBuf)>) {
-        while let Ok((event, path)) = input_rx.recv() {
-            // NOTE: ec is the canceller
-            if let Some(ec) = self.active_timers.remove(&path) {
-                let _ = ec.send(());
+    /// # Returns
+    /// The number of events forwarded
+    pub fn flush(&mut self) -> usize {
+        match self.deadlines.last() {
+            Some((last, _)) => {
+                let last = *last;
+                self.expire(last)
             }
+            None => 0,
+        }
+    }
 
-            // NOTE: c_tx is cancel sender, c_rx is cancel reciever
-            let (c_tx, c_rx) = mpsc::channel();
-            let output_tx = self.output_tx.clone();
-            let dur = self.duration;
+    /// Drops every pending event without forwarding it.
+    ///
+    /// # Returns
+    /// The number of events dropped
+    pub fn discard(&mut self) -> usize {
+        let count = self.pending.len();
+        self.pending.clear();
+        self.deadlines.clear();
+        count
+    }
 
-            let key_path = path.clone();
+    /// Runs the debouncer, processing events from the input channel.
+    ///
+    /// This method blocks until a [`DebouncerMessage::Shutdown`] arrives or
+    /// the input channel is closed, processing events as they arrive and
+    /// forwarding them as their deadlines pass. Events are keyed by
+    /// [`FsEvent::path`], so a rename is keyed by its new path.
+    ///
+    /// # Arguments
+    /// * `input_rx` - Channel to receive raw file system events and shutdown requests
+    ///
+    /// # Behavior
+    /// - Blocks until shutdown is requested or the input channel is closed
+    /// - Sleeps until the next message or the earliest deadline
+    /// - On shutdown, flushes or discards pending events per [`ShutdownMode`]
+    /// - Forwards all still pending events if the input channel closes
+    pub fn run(&mut self, input_rx: mpsc::Receiver<DebouncerMessage>) {
+        loop {
+            let received = match self.next_deadline() {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    input_rx.recv_timeout(timeout)
+                }
+                None => input_rx
+                    .recv()
+                    .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
 
-            thread::spawn(move || {
-                if let Err(mpsc::RecvTimeoutError::Timeout) = c_rx.recv_timeout(dur) {
-                    let _ = output_tx.send((event, path));
+            match received {
+                Ok(DebouncerMessage::Event(event)) => self.push(event, Instant::now()),
+                Ok(DebouncerMessage::Shutdown(ShutdownMode::Flush)) => {
+                    self.flush();
+                    return;
                 }
-            });
+                Ok(DebouncerMessage::Shutdown(ShutdownMode::Discard)) => {
+                    self.discard();
+                    return;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    self.flush();
+                    return;
+                }
+            }
 
-            self.active_timers.insert(key_path, c_tx);
+            self.expire(Instant::now());
         }
     }
 }
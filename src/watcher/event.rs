@@ -1,8 +1,10 @@
 //! Low-level file system event setup.
 //!
 //! This module provides the foundation for file system monitoring using
-//! the `notify` crate. It sets up a recursive file system watcher that
-//! sends events through a channel for further processing.
+//! the `notify` crate. It sets up file system watchers that send events
+//! through a channel for further processing: a recursive one for a whole
+//! tree ([`setup_file_watcher`]), or a [`DirWatcher`] that watches only
+//! selected directories of the tree.
 //!
 //! # Event Filtering
 //!
@@ -10,15 +12,189 @@
 //! - Create events (new files)
 //! - Modify events (file changes)
 //! - Remove events (file deletions)
+//! - Rename events (a file or directory moved within the watched tree)
 //!
-//! Other events like metadata changes or directory operations are ignored.
+//! Other events like access notifications are ignored.
+//!
+//! # Rename Pairing
+//!
+//! Backends report a rename as two halves, the old path ("from") and the
+//! new path ("to"), sometimes followed by a combined event holding both.
+//! The halves are paired into a single [`FsEvent::Rename`] by their
+//! tracker cookie, or by order on backends that don't provide one. A
+//! "from" half that stays unpaired for [`RENAME_PAIR_TIMEOUT`] was moved
+//! out of the watched tree and is reported as [`FsEvent::Remove`]; an
+//! unpaired "to" half was moved in and is reported as [`FsEvent::Create`].
+//!
+//! Unpaired halves are expired by one [`PairingTimer`] thread per watcher.
+//! Stopping the timer reports a still pending "from" half right away, so
+//! no event is lost when a watcher is torn down.
 
-use notify::{Config, Event, EventKind, RecommendedWatcher, Watcher};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::{
+    collections::BTreeSet,
+    fs,
     path::{Path, PathBuf},
-    sync::mpsc::Sender,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
+/// How long the old path of a rename waits for its new path.
+pub const RENAME_PAIR_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A file system change forwarded by the watcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    /// A file or directory was created.
+    Create(PathBuf),
+
+    /// A file's content or metadata changed.
+    Modify(PathBuf),
+
+    /// A file or directory was removed.
+    Remove(PathBuf),
+
+    /// A file or directory was moved from `from` to `to`.
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+impl FsEvent {
+    /// Returns the path the event is keyed by: the new path for renames.
+    pub fn path(&self) -> &Path {
+        match self {
+            FsEvent::Create(path) | FsEvent::Modify(path) | FsEvent::Remove(path) => path,
+            FsEvent::Rename { to, .. } => to,
+        }
+    }
+
+    /// Combines a pending event with a newer one for the same path.
+    ///
+    /// A rename is only replaced by another rename, since dropping it would
+    /// lose track of the old path; the handler re-reads the new path
+    /// anyway. Otherwise the newer event wins.
+    pub fn merge(self, next: FsEvent) -> FsEvent {
+        match (self, next) {
+            (FsEvent::Rename { .. }, next @ FsEvent::Rename { .. }) => next,
+            (rename @ FsEvent::Rename { .. }, _) => rename,
+            (_, next) => next,
+        }
+    }
+}
+
+/// The old half of a rename waiting for its new half.
+struct PendingFrom {
+    path: PathBuf,
+    tracker: Option<usize>,
+    /// When the old path is given up on and reported as removed.
+    deadline: Instant,
+}
+
+/// Decides which events are forwarded; see [`setup_filtered_watcher`].
+type EventFilter = Box<dyn Fn(&FsEvent) -> bool + Send>;
+
+/// Pairs the two halves of rename events into [`FsEvent::Rename`].
+struct RenamePairer<T> {
+    tx: Sender<T>,
+    filter: EventFilter,
+    pending: Option<PendingFrom>,
+    /// Tracker of the last pair, so the combined event that follows the
+    /// halves on some backends is not reported twice.
+    last_paired: Option<usize>,
+}
+
+impl<T: From<FsEvent>> RenamePairer<T> {
+    fn send(&self, event: FsEvent) {
+        if (self.filter)(&event) {
+            let _ = self.tx.send(event.into());
+        }
+    }
+
+    /// Reports a pending old path as removed.
+    fn flush(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.send(FsEvent::Remove(pending.path));
+        }
+    }
+
+    /// Stores an old path until its new path arrives or it expires.
+    fn from(&mut self, path: PathBuf, tracker: Option<usize>) {
+        self.flush();
+        self.pending = Some(PendingFrom {
+            path,
+            tracker,
+            deadline: Instant::now() + RENAME_PAIR_TIMEOUT,
+        });
+    }
+
+    fn to(&mut self, path: PathBuf, tracker: Option<usize>) {
+        let matches = self
+            .pending
+            .as_ref()
+            .is_some_and(|p| p.tracker.is_none() || p.tracker == tracker);
+
+        if matches {
+            let from = self.pending.take().unwrap().path;
+            self.last_paired = tracker;
+            self.send(FsEvent::Rename { from, to: path });
+        } else {
+            self.flush();
+            self.send(FsEvent::Create(path));
+        }
+    }
+
+    fn both(&mut self, from: PathBuf, to: PathBuf, tracker: Option<usize>) {
+        if tracker.is_some() && tracker == self.last_paired {
+            return;
+        }
+        if self.pending.as_ref().is_some_and(|p| p.path == from) {
+            self.pending = None;
+        }
+        self.send(FsEvent::Rename { from, to });
+    }
+
+    /// Reports a pending old path as removed once its deadline passed.
+    ///
+    /// # Returns
+    /// How long to wait before checking again
+    fn expire(&mut self, now: Instant) -> Duration {
+        match &self.pending {
+            Some(pending) if pending.deadline > now => pending.deadline - now,
+            Some(_) => {
+                self.flush();
+                RENAME_PAIR_TIMEOUT
+            }
+            None => RENAME_PAIR_TIMEOUT,
+        }
+    }
+}
+
+/// The thread expiring unpaired rename halves of a watcher.
+///
+/// The thread also stops on its own once its watcher is dropped, but only
+/// after the watcher's event loop has wound down; [`PairingTimer::stop`]
+/// stops it right away.
+pub struct PairingTimer {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl PairingTimer {
+    /// Asks the timer to report a still unpaired old path as
+    /// [`FsEvent::Remove`] and exit.
+    ///
+    /// # Returns
+    /// The timer's thread, to be joined to wait for the final event
+    pub fn stop(self) -> JoinHandle<()> {
+        let _ = self.stop.send(());
+        self.thread
+    }
+}
+
 /// Sets up a file system watcher for the given path.
 ///
 /// Creates a recursive file system watcher that monitors the specified
@@ -27,52 +203,277 @@ use std::{
 ///
 /// # Arguments
 /// * `watch_path` - The directory path to monitor
-/// * `raw_event_tx` - Channel for sending file system events
+/// * `event_tx` - Channel for sending file system events, as any type
+///   convertible from [`FsEvent`]
 ///
 /// # Returns
 /// A `RecommendedWatcher` instance that can be used to control monitoring
 ///
 /// # Behavior
 /// - Monitors the directory recursively (all subdirectories)
-/// - Only forwards create, modify, and remove events
-/// - Ignores metadata changes and other non-essential events
-/// - Sends events as `(EventKind, PathBuf)` tuples
+/// - Only forwards create, modify, remove, and rename events
+/// - Pairs the two halves of a rename into one [`FsEvent::Rename`]
+/// - Ignores access notifications and other non-essential events
 ///
 /// # Errors
 /// Returns `notify::Error` if the watcher cannot be initialized
 ///
 /// # Examples
 /// ```rust,no_run
-/// use notify::EventKind;
 /// use std::sync::mpsc::channel;
-/// use ora_core::watcher::event::setup_file_watcher;
+/// use ora_core::watcher::event::{FsEvent, setup_file_watcher};
 /// use std::path::Path;
 ///
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-/// let (tx, rx) = channel();
+/// let (tx, rx) = channel::<FsEvent>();
 /// let watcher = setup_file_watcher(Path::new("/path/to/watch"), tx)?;
 /// # Ok(())
 /// # }
 /// ```
-pub fn setup_file_watcher(
+pub fn setup_file_watcher<T: From<FsEvent> + Send + 'static>(
     watch_path: &Path,
-    raw_event_tx: Sender<(EventKind, PathBuf)>,
+    event_tx: Sender<T>,
 ) -> Result<RecommendedWatcher, notify::Error> {
+    setup_filtered_watcher(watch_path, event_tx, |_| true)
+}
+
+/// Sets up a file system watcher like [`setup_file_watcher`] that only
+/// forwards events accepted by `filter`.
+///
+/// The filter sees each event after rename pairing, so a rename is judged
+/// as a whole rather than by its halves. It runs on the watcher's thread
+/// and should return quickly.
+///
+/// # Arguments
+/// * `watch_path` - The directory path to monitor
+/// * `event_tx` - Channel for sending file system events
+/// * `filter` - Returns `true` for events to forward
+///
+/// # Errors
+/// Returns `notify::Error` if the watcher cannot be initialized
+pub fn setup_filtered_watcher<T, F>(
+    watch_path: &Path,
+    event_tx: Sender<T>,
+    filter: F,
+) -> Result<RecommendedWatcher, notify::Error>
+where
+    T: From<FsEvent> + Send + 'static,
+    F: Fn(&FsEvent) -> bool + Send + 'static,
+{
+    let (mut watcher, _timer) = pairing_watcher(event_tx, filter)?;
+    watcher.watch(watch_path, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// Creates a watcher that watches nothing yet and forwards the events
+/// accepted by `filter` after pairing renames, along with the timer that
+/// expires unpaired halves.
+fn pairing_watcher<T, F>(
+    event_tx: Sender<T>,
+    filter: F,
+) -> Result<(RecommendedWatcher, PairingTimer), notify::Error>
+where
+    T: From<FsEvent> + Send + 'static,
+    F: Fn(&FsEvent) -> bool + Send + 'static,
+{
+    let pairer = Arc::new(Mutex::new(RenamePairer {
+        tx: event_tx,
+        filter: Box::new(filter),
+        pending: None,
+        last_paired: None,
+    }));
+
+    // A single timer expires unpaired old paths. It stops when asked to
+    // or when the event handler, which holds a sender, is dropped with the
+    // watcher, and reports any pending old path on the way out.
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let timer_pairer = Arc::clone(&pairer);
+    let thread = thread::spawn(move || {
+        let mut wait = RENAME_PAIR_TIMEOUT;
+        while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(wait) {
+            wait = timer_pairer.lock().unwrap().expire(Instant::now());
+        }
+        timer_pairer.lock().unwrap().flush();
+    });
+    let timer = PairingTimer {
+        stop: stop_tx.clone(),
+        thread,
+    };
+
     let event_handler = move |res: Result<Event, notify::Error>| {
-        if let Ok(event) = res {
-            for path in event.paths {
-                match event.kind {
-                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
-                        let _ = raw_event_tx.send((event.kind, path));
-                    }
-                    _ => {}
+        let _ = &stop_tx;
+        let Ok(event) = res else {
+            return;
+        };
+        let tracker = event.tracker();
+        let mut paths = event.paths.into_iter();
+        let mut state = pairer.lock().unwrap();
+        state.expire(Instant::now());
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                if let Some(path) = paths.next() {
+                    state.from(path, tracker);
                 }
             }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                if let Some(path) = paths.next() {
+                    state.to(path, tracker);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    state.both(from, to, tracker);
+                }
+            }
+            EventKind::Modify(_) => {
+                for path in paths {
+                    state.send(FsEvent::Modify(path));
+                }
+            }
+            EventKind::Create(_) => {
+                for path in paths {
+                    state.send(FsEvent::Create(path));
+                }
+            }
+            EventKind::Remove(_) => {
+                for path in paths {
+                    state.send(FsEvent::Remove(path));
+                }
+            }
+            _ => {}
         }
     };
 
-    let mut watcher = RecommendedWatcher::new(event_handler, Config::default())?;
-    watcher.watch(watch_path, notify::RecursiveMode::Recursive)?;
+    let watcher = RecommendedWatcher::new(event_handler, Config::default())?;
+    Ok((watcher, timer))
+}
 
-    Ok(watcher)
+/// A watcher of selected directories of a tree.
+///
+/// Each directory is watched on its own, non-recursively, so directories
+/// left out (hidden or ignored ones, such as `node_modules/`) cost no
+/// watches and produce no events at all. The set of watched directories
+/// is updated with [`DirWatcher::sync`] and [`DirWatcher::unwatch_tree`]
+/// as directories are created, moved and removed.
+pub struct DirWatcher {
+    watcher: RecommendedWatcher,
+    watched: BTreeSet<PathBuf>,
+    timer: Option<PairingTimer>,
+}
+
+impl DirWatcher {
+    /// Creates a watcher that watches no directory yet.
+    ///
+    /// Events are paired and filtered as by [`setup_filtered_watcher`].
+    ///
+    /// # Errors
+    /// Returns `notify::Error` if the watcher cannot be initialized
+    pub fn new<T, F>(event_tx: Sender<T>, filter: F) -> Result<Self, notify::Error>
+    where
+        T: From<FsEvent> + Send + 'static,
+        F: Fn(&FsEvent) -> bool + Send + 'static,
+    {
+        let (watcher, timer) = pairing_watcher(event_tx, filter)?;
+        Ok(DirWatcher {
+            watcher,
+            watched: BTreeSet::new(),
+            timer: Some(timer),
+        })
+    }
+
+    /// Takes the timer expiring unpaired rename halves, so its owner can
+    /// stop and join it after dropping the watcher.
+    ///
+    /// Returns `None` if the timer was already taken.
+    pub fn take_timer(&mut self) -> Option<PairingTimer> {
+        self.timer.take()
+    }
+
+    /// Watches `dir` and every directory below it accepted by `include`.
+    ///
+    /// `include` is asked about `dir` itself too. Directories it rejects
+    /// are not descended into, and watches of directories below `dir` that
+    /// are no longer accepted are removed.
+    ///
+    /// # Errors
+    /// Returns `notify::Error` if a watch cannot be added, for example
+    /// because the system limit on watches is reached. Directories that
+    /// vanish while they are scanned are skipped.
+    pub fn sync(
+        &mut self,
+        dir: &Path,
+        include: &dyn Fn(&Path) -> bool,
+    ) -> Result<(), notify::Error> {
+        let mut wanted = BTreeSet::new();
+        if include(dir) {
+            collect_dirs(dir, include, &mut wanted);
+        }
+
+        let stale: Vec<PathBuf> = self
+            .watched
+            .iter()
+            .filter(|path| path.starts_with(dir) && !wanted.contains(*path))
+            .cloned()
+            .collect();
+        for path in stale {
+            self.unwatch(&path);
+        }
+
+        for path in wanted {
+            if self.watched.contains(&path) {
+                continue;
+            }
+            match self.watcher.watch(&path, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.watched.insert(path);
+                }
+                Err(e) if is_not_found(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops watching `dir` and every directory below it.
+    pub fn unwatch_tree(&mut self, dir: &Path) {
+        let below: Vec<PathBuf> = self
+            .watched
+            .iter()
+            .filter(|path| path.starts_with(dir))
+            .cloned()
+            .collect();
+        for path in below {
+            self.unwatch(&path);
+        }
+    }
+
+    fn unwatch(&mut self, dir: &Path) {
+        // Fails if the directory is already gone, which ends its watch too.
+        let _ = self.watcher.unwatch(dir);
+        self.watched.remove(dir);
+    }
+}
+
+/// Adds `dir` and the directories below it accepted by `include` to `dirs`.
+fn collect_dirs(dir: &Path, include: &dyn Fn(&Path) -> bool, dirs: &mut BTreeSet<PathBuf>) {
+    dirs.insert(dir.to_path_buf());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|ft| ft.is_dir()) && include(&path) {
+            collect_dirs(&path, include, dirs);
+        }
+    }
+}
+
+/// Returns `true` if a watch failed because its path does not exist.
+fn is_not_found(error: &notify::Error) -> bool {
+    match &error.kind {
+        notify::ErrorKind::PathNotFound => true,
+        notify::ErrorKind::Io(e) => e.kind() == std::io::ErrorKind::NotFound,
+        _ => false,
+    }
 }
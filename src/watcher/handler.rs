@@ -6,20 +6,51 @@
 //!
 //! # Event Processing
 //!
-//! The handler processes three types of events:
+//! The handler processes four types of events:
 //! - **Create**: New files are added to the index
 //! - **Modify**: Existing files are updated in the index
 //! - **Remove**: Deleted files are removed from the index
+//! - **Rename**: Moved files keep their index entry under the new path
+//!
+//! After each change is applied to the index, a [`NoteEvent`] describing
+//! it is published to the handler's [`Subscribers`].
+//!
+//! # Echo Suppression
+//!
+//! Writes made through a [`ShelfManager`](crate::shelf::manager::ShelfManager)
+//! connected to the watcher, or saved with [`LocalNote::save_with`], are
+//! indexed and published synchronously. The file system events they cause
+//! afterwards (the atomic write's temporary file, its rename over the note,
+//! the modification) find the index already up to date and are skipped
+//! without publishing a second event. A plain [`LocalNote::save`] is not
+//! known to the watcher and is reported like any external change.
 //!
 //! # File Filtering
 //!
 //! Only Markdown files (`.md` extension) that are not hidden are processed.
-//! Hidden files (starting with `.`) and other file types are ignored.
+//! Hidden files and directories (starting with `.`) and other file types
+//! are ignored, as are paths matching the shelf's ignore rules (see
+//! [`crate::ignore`]). Moving a note to an ignored path removes it from the
+//! index; moving it out of one indexes it.
+//!
+//! A change to any `.oraignore` file reloads the rules and updates the
+//! index to match them.
+//!
+//! # History
+//!
+//! If the shelf keeps a revision history (see [`crate::history`]), every
+//! external creation or modification of a note is recorded as a revision,
+//! and renames move the note's revisions along with it.
 
 use crate::domain::LocalNote;
 use crate::error::OraError;
+use crate::history::RevisionSource;
+use crate::ignore::{in_hidden_dir, is_hidden, is_ignore_file};
+use crate::watcher::event::{DirWatcher, FsEvent};
 use crate::watcher::index::Index;
+use crate::watcher::subscription::{NoteEvent, Subscribers};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 /// Checks if a file path represents a processable Markdown file.
 ///
@@ -46,6 +77,13 @@ fn is_markdown_file(path: &Path) -> bool {
 pub struct FileIndexHandler {
     /// The search index to update when processing events.
     index: Index,
+
+    /// Receivers of the changes applied to the index.
+    subscribers: Subscribers,
+
+    /// The directories watched while the watcher runs, kept in line with
+    /// directory changes and the ignore rules.
+    watches: Arc<Mutex<Option<DirWatcher>>>,
 }
 
 impl FileIndexHandler {
@@ -57,7 +95,110 @@ impl FileIndexHandler {
     /// # Returns
     /// A new `FileIndexHandler` instance
     pub fn new(index: Index) -> Self {
-        Self { index }
+        Self {
+            index,
+            subscribers: Subscribers::new(),
+            watches: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the subscribers notified of every change to the index.
+    pub fn subscribers(&self) -> &Subscribers {
+        &self.subscribers
+    }
+
+    /// Returns the index kept up to date by this handler.
+    pub(crate) fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Starts maintaining the directories watched by `watcher`: the shelf
+    /// root and every visible, not ignored directory below it.
+    ///
+    /// # Errors
+    /// Returns `notify::Error` if a watch cannot be added
+    pub(crate) fn attach(&self, mut watcher: DirWatcher) -> Result<(), notify::Error> {
+        watcher.sync(self.index.root(), &|dir| self.is_watched_dir(dir))?;
+        *self.watches.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+
+    /// Drops the watcher given to [`FileIndexHandler::attach`], which stops
+    /// its events.
+    pub(crate) fn detach(&self) {
+        self.watches.lock().unwrap().take();
+    }
+
+    /// Applies a debounced file system event to the index.
+    ///
+    /// Dispatches to the matching `handle_*` method. If that fails, the
+    /// error is published as [`NoteEvent::IndexError`].
+    ///
+    /// # Arguments
+    /// * `event` - The event to apply
+    pub fn handle(&self, event: &FsEvent) {
+        let result = match event {
+            FsEvent::Rename { from, to } if is_ignore_file(from) || is_ignore_file(to) => {
+                self.reload_ignore_rules()
+            }
+            event if is_ignore_file(event.path()) => self.reload_ignore_rules(),
+            FsEvent::Create(path) => self.handle_create(path),
+            FsEvent::Modify(path) => self.handle_modify(path),
+            FsEvent::Remove(path) => self.handle_remove(path),
+            FsEvent::Rename { from, to } => self.handle_rename(from, to),
+        };
+
+        if let Err(e) = result {
+            self.subscribers.publish(NoteEvent::IndexError {
+                path: event.path().to_path_buf(),
+                message: e.to_string(),
+            });
+        }
+    }
+
+    /// Checks whether an event can affect the index.
+    ///
+    /// Used to drop events before they are debounced. Events for ignore
+    /// files are relevant; events for paths inside hidden directories or
+    /// matched by the ignore rules are not, except renames whose other
+    /// side is relevant.
+    ///
+    /// # Arguments
+    /// * `event` - The raw file system event
+    pub fn is_relevant(&self, event: &FsEvent) -> bool {
+        let visible = |path: &Path| {
+            is_ignore_file(path)
+                || !(in_hidden_dir(self.index.root(), path) || self.index.is_ignored(path))
+        };
+        match event {
+            FsEvent::Rename { from, to } => visible(from) || visible(to),
+            event => visible(event.path()),
+        }
+    }
+
+    /// Reloads the ignore rules after an `.oraignore` file changed.
+    ///
+    /// Directories that became ignored are no longer watched and those that
+    /// are no longer ignored are. Notes that became ignored are removed
+    /// from the index and notes that are no longer ignored are indexed,
+    /// publishing an event for each.
+    ///
+    /// # Errors
+    /// Returns `OraError` if reading the ignore files or indexing fails
+    pub fn reload_ignore_rules(&self) -> Result<(), OraError> {
+        self.index.reload_ignore_rules()?;
+        self.watch_tree(self.index.root())?;
+
+        let root = self.index.root().to_path_buf();
+        for path in self.index.paths_under(&root)? {
+            if self.index.is_ignored(&path) {
+                self.remove(&path)?;
+            }
+        }
+        for created in self.index.index_existing_files(&root)? {
+            self.publish_indexed(&created, true)?;
+        }
+        Ok(())
     }
 
     /// Handles file creation events.
@@ -71,14 +212,29 @@ impl FileIndexHandler {
     ///
     /// # Behavior
     /// - Only processes Markdown files (`.md` extension)
-    /// - Skips hidden files
+    /// - Skips hidden files and paths ignored by the shelf config
     /// - Checks if file is already indexed before adding
+    /// - Reports a note that took over the entry of a vanished file (see
+    ///   [`Index::index_note`]) as renamed rather than created
     /// - Logs errors for files that can't be opened
+    /// - Watches newly created directories and scans them, since files
+    ///   written into them before the watch is added produce no events
     ///
     /// # Errors
     /// Returns `OraError` if indexing operations fail
     pub fn handle_create(&self, path: &Path) -> Result<(), OraError> {
-        if !is_markdown_file(path) {
+        if path.is_dir() {
+            if self.is_excluded_dir(path) {
+                return Ok(());
+            }
+            self.watch_tree(path)?;
+            for created in self.index.index_existing_files(path)? {
+                self.publish_indexed(&created, true)?;
+            }
+            return Ok(());
+        }
+
+        if !self.is_note(path) {
             return Ok(());
         }
 
@@ -88,11 +244,18 @@ impl FileIndexHandler {
 
         match LocalNote::open(path) {
             Ok(note) => {
-                self.index.index_note(&note)?;
-            }
-            Err(e) => {
-                eprintln!("Failed to open note for indexing: {:?}, error: {}", path, e)
+                let moved_from = self.index.index_moved_note(&note)?;
+                self.index
+                    .record_revision(&note, RevisionSource::External)?;
+                match moved_from {
+                    Some(from) => self.publish_renamed(&from, path)?,
+                    None => self.publish_indexed(path, true)?,
+                }
             }
+            Err(e) => self.subscribers.publish(NoteEvent::IndexError {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            }),
         }
         Ok(())
     }
@@ -107,28 +270,29 @@ impl FileIndexHandler {
     ///
     /// # Behavior
     /// - Only processes Markdown files (`.md` extension)
-    /// - Skips hidden files
+    /// - Skips hidden files and paths ignored by the shelf config
     /// - Updates existing entries in the index
+    /// - Skips files whose indexed content is already up to date
     /// - Removes files from index if they can't be read
     ///
     /// # Errors
     /// Returns `OraError` if indexing operations fail
     pub fn handle_modify(&self, path: &Path) -> Result<(), OraError> {
-        if !is_markdown_file(path) {
+        if !self.is_note(path) {
             return Ok(());
         }
         match LocalNote::open(path) {
             Ok(note) => {
+                if self.index.is_current(&note)? {
+                    return Ok(());
+                }
+                let existed = self.index.exists(path)?;
                 self.index.index_note(&note)?;
+                self.index
+                    .record_revision(&note, RevisionSource::External)?;
+                self.publish_indexed(path, !existed)?;
             }
-            Err(_) => {
-                let deleted_note = LocalNote {
-                    title: String::new(),
-                    content: String::new(),
-                    path: path.to_path_buf(),
-                };
-                self.index.remove_note(&deleted_note)?;
-            }
+            Err(_) => self.remove(path)?,
         }
         Ok(())
     }
@@ -142,24 +306,212 @@ impl FileIndexHandler {
     /// * `path` - Path to the deleted file
     ///
     /// # Behavior
+    /// - Stops watching a removed directory and the directories below it
     /// - Only processes Markdown files (`.md` extension)
-    /// - Skips hidden files
+    /// - Skips hidden files and paths ignored by the shelf config
     /// - Removes entries from the search index
     ///
     /// # Errors
     /// Returns `OraError` if the removal operation fails
     pub fn handle_remove(&self, path: &Path) -> Result<(), OraError> {
-        if !is_markdown_file(path) {
+        self.unwatch_tree(path);
+        if !self.is_note(path) {
+            return Ok(());
+        }
+
+        self.remove(path)
+    }
+
+    /// Handles file and directory rename events.
+    ///
+    /// Moves the existing index entry to the new path instead of removing
+    /// and re-adding it, so the row id, stable ID, `created_at` and links
+    /// of the note are preserved.
+    ///
+    /// # Arguments
+    /// * `from` - The old path
+    /// * `to` - The new path
+    ///
+    /// # Behavior
+    /// - Renamed directories move every note below them, and the watches
+    ///   of the directories below them
+    /// - A Markdown file renamed to a non-Markdown or hidden name is removed
+    /// - A non-Markdown file renamed to a Markdown name (e.g. an editor's
+    ///   temporary file saved over a note) is indexed like a modification
+    /// - If the new path no longer exists, both paths are removed
+    ///
+    /// # Errors
+    /// Returns `OraError` if indexing operations fail
+    pub fn handle_rename(&self, from: &Path, to: &Path) -> Result<(), OraError> {
+        if to.is_dir() {
+            self.unwatch_tree(from);
+            self.watch_tree(to)?;
+            if self.is_excluded_dir(to) {
+                for path in self.index.paths_under(from)? {
+                    self.remove(&path)?;
+                }
+                return Ok(());
+            }
+            if let Some(history) = self.index.history() {
+                history.rename_dir(from, to)?;
+            }
+            for (old, new) in self.index.rename_dir(from, to)? {
+                if self.index.is_ignored(&new) {
+                    self.remove(&new)?;
+                } else {
+                    self.publish_renamed(&old, &new)?;
+                }
+            }
+            for created in self.index.index_existing_files(to)? {
+                self.publish_indexed(&created, true)?;
+            }
             return Ok(());
         }
 
-        let deleted_note = LocalNote {
-            title: String::new(),
-            content: String::new(),
-            path: path.to_path_buf(),
+        if !to.exists() {
+            self.handle_remove(from)?;
+            return self.handle_remove(to);
+        }
+
+        match (self.is_note(from), self.is_note(to)) {
+            (true, true) => match LocalNote::open(to) {
+                Ok(note) => {
+                    if !self.index.exists(from)? && self.index.is_current(&note)? {
+                        return Ok(());
+                    }
+                    let existed = self.index.exists(to)?;
+                    let renamed = self.index.rename_note(from, &note)?;
+                    if renamed && let Some(history) = self.index.history() {
+                        history.rename(from, to)?;
+                    }
+                    self.index
+                        .record_revision(&note, RevisionSource::External)?;
+                    if renamed {
+                        self.publish_renamed(from, to)?;
+                    } else {
+                        self.publish_indexed(to, !existed)?;
+                    }
+                }
+                Err(e) => {
+                    self.subscribers.publish(NoteEvent::IndexError {
+                        path: to.to_path_buf(),
+                        message: e.to_string(),
+                    });
+                    self.handle_remove(from)?;
+                }
+            },
+            (true, false) => self.handle_remove(from)?,
+            (false, true) => self.handle_modify(to)?,
+            (false, false) => {}
+        }
+        Ok(())
+    }
+
+    /// Applies a save made through the library, so the file system events
+    /// it causes are skipped as echoes.
+    ///
+    /// The note was read from `from` and has just been written to its
+    /// current path, which differs from `from` if it was saved under a new
+    /// title. The change is indexed, recorded in the history and published
+    /// as a single event.
+    ///
+    /// # Errors
+    /// Returns `OraError` if indexing operations fail
+    pub(crate) fn apply_save(&self, from: &Path, note: &LocalNote) -> Result<(), OraError> {
+        if !self.is_note(&note.path) {
+            return self.remove(from);
+        }
+
+        if from != note.path && self.index.rename_note(from, note)? {
+            if let Some(history) = self.index.history() {
+                history.rename(from, &note.path)?;
+            }
+            self.index.record_revision(note, RevisionSource::Library)?;
+            return self.publish_renamed(from, &note.path);
+        }
+
+        let existed = self.index.exists(&note.path)?;
+        self.index.index_note(note)?;
+        self.index.record_revision(note, RevisionSource::Library)?;
+        self.publish_indexed(&note.path, !existed)
+    }
+
+    /// Checks if the directory `dir` is watched: the shelf root, or a
+    /// directory whose notes are tracked.
+    fn is_watched_dir(&self, dir: &Path) -> bool {
+        dir == self.index.root() || !self.is_excluded_dir(dir)
+    }
+
+    /// Updates the watches of `dir` and the directories below it, if the
+    /// watcher is running.
+    fn watch_tree(&self, dir: &Path) -> Result<(), OraError> {
+        if let Some(watcher) = self.watches.lock().unwrap().as_mut() {
+            watcher.sync(dir, &|dir| self.is_watched_dir(dir))?;
+        }
+        Ok(())
+    }
+
+    /// Stops watching `dir` and the directories below it.
+    fn unwatch_tree(&self, dir: &Path) {
+        if let Some(watcher) = self.watches.lock().unwrap().as_mut() {
+            watcher.unwatch_tree(dir);
+        }
+    }
+
+    /// Checks if `path` is a Markdown file the index should track: not
+    /// hidden, not inside a hidden directory and not ignored.
+    fn is_note(&self, path: &Path) -> bool {
+        is_markdown_file(path)
+            && !in_hidden_dir(self.index.root(), path)
+            && !self.index.is_ignored(path)
+    }
+
+    /// Checks if the directory `path` is hidden, inside a hidden directory
+    /// (such as the trash) or ignored, so its notes are not tracked.
+    fn is_excluded_dir(&self, path: &Path) -> bool {
+        is_hidden(path) || in_hidden_dir(self.index.root(), path) || self.index.is_ignored(path)
+    }
+
+    /// Removes the note at `path` from the index and publishes its removal.
+    fn remove(&self, path: &Path) -> Result<(), OraError> {
+        let Some(indexed) = self.index.get_by_path(path)? else {
+            return Ok(());
         };
 
-        self.index.remove_note(&deleted_note)?;
+        let deleted_note = LocalNote::at_path(path);
+
+        if self.index.remove_note(&deleted_note)? {
+            self.subscribers.publish(NoteEvent::NoteRemoved {
+                path: path.to_path_buf(),
+                id: indexed.id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Publishes that the note at `path` was created or modified.
+    fn publish_indexed(&self, path: &Path, created: bool) -> Result<(), OraError> {
+        if let Some(indexed) = self.index.get_by_path(path)? {
+            let path = path.to_path_buf();
+            let id = indexed.id;
+            self.subscribers.publish(if created {
+                NoteEvent::NoteCreated { path, id }
+            } else {
+                NoteEvent::NoteModified { path, id }
+            });
+        }
+        Ok(())
+    }
+
+    /// Publishes that the note at `from` was moved to `to`.
+    fn publish_renamed(&self, from: &Path, to: &Path) -> Result<(), OraError> {
+        if let Some(indexed) = self.index.get_by_path(to)? {
+            self.subscribers.publish(NoteEvent::NoteRenamed {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                id: indexed.id,
+            });
+        }
         Ok(())
     }
 
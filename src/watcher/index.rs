@@ -6,9 +6,86 @@
 //!
 //! # Database Schema
 //!
-//! The index creates two main tables:
-//! - `notes` - Stores note metadata and content
-//! - `contents` - FTS5 virtual table for full-text search
+//! The index creates the following tables:
+//! - `notes` - Stores the note title, body, raw front matter and path
+//! - `note_metadata` - One row per front matter value, keyed by note
+//! - `note_tags` - One row per normalized tag, keyed by note
+//! - `links` - One row per wikilink or Markdown link to another note
+//! - `note_keys` - The names a wikilink can use for a note, keyed by note
+//! - `contents` - FTS5 virtual table for full-text search over title and body
+//! - `trigrams` - Optional FTS5 virtual table over title and body with the
+//!   trigram tokenizer, for substring and fuzzy search
+//!
+//! Front matter is kept out of the `contents` table so that header keys
+//! and values are not matched as prose.
+//!
+//! # Note Identity
+//!
+//! Every note has a stable ID in the `uid` column. If the note's front
+//! matter has an `id:` key, that value is used; otherwise the index assigns
+//! a random UUID and keeps it for as long as the row exists. The ID survives
+//! content edits and renames performed through [`Index::rename_note`]. A
+//! front matter ID additionally survives renames done outside the library,
+//! since the new file carries the same `id:` as the vanished one.
+//!
+//! # Links
+//!
+//! Links are stored as written and resolved when queried: wikilinks match
+//! notes by title (case-insensitive) or shelf-relative path, Markdown links
+//! by their resolved file path. A link to a note that does not exist yet is
+//! reported as unresolved and starts resolving once the note is indexed.
+//!
+//! To resolve links with indexed lookups, each link stores a normalized
+//! `target_key` and each note its keys in `note_keys`: the lower-cased
+//! title, and every trailing part of its shelf-relative path with two or
+//! more components, joined with `/` on every platform (`dir/Note`,
+//! `sub/dir/Note`). A wikilink resolves to the notes with a key equal to
+//! its `target_key`.
+//!
+//! # Reconciliation
+//!
+//! Each row stores the file's modification time, size and content hash as
+//! of its last indexing. [`Index::new`] compares them against the files on
+//! disk and re-indexes changed notes, indexes new ones and drops rows for
+//! notes deleted while nothing was watching; see [`Index::reconcile`].
+//! A new file with exactly the content of a vanished one takes over its
+//! row, so a note renamed while nothing was watching keeps its stable ID.
+//!
+//! # Configuration
+//!
+//! [`Index::new`] reads the shelf's `.shelf.toml` (see
+//! [`crate::shelf::config`]). Paths matching its `ignore` patterns or an
+//! `.oraignore` file (see [`crate::ignore`]) are not indexed, and rows for
+//! them are dropped on reconciliation. After editing an ignore file, call
+//! [`Index::reload_ignore_rules`]; the watcher does so automatically. The `contents`
+//! table uses the configured tokenizer; when the setting changes, the table
+//! is rebuilt from the stored notes on the next open.
+//!
+//! Setting `trigram_index` adds the `trigrams` table next to `contents`,
+//! filled from the stored notes on the next open and dropped again when
+//! the setting is turned off. It is not part of the versioned schema.
+//!
+//! # History
+//!
+//! If the shelf config enables `history`, the index opens the shelf's
+//! [`History`] store, available through [`Index::history`]. Notes that
+//! reconciliation finds new or changed are recorded as external revisions.
+//!
+//! # Schema Versioning
+//!
+//! The schema version is stored in SQLite's `PRAGMA user_version`. Opening
+//! an index applies every migration newer than the stored version, in
+//! order, each in its own transaction. Databases from before versioning
+//! report version 0 and are brought up to date the same way; columns that
+//! already exist are left alone.
+//!
+//! If a migration of an older schema fails, the schema is dropped and
+//! rebuilt from the Markdown files on disk; a file that is not an SQLite
+//! database or is corrupt is deleted with its journal files and recreated.
+//! The index is a cache of the shelf, so only index-assigned stable IDs are
+//! lost. A database written by a newer version of the library is left alone
+//! and opening it fails with [`OraError::SchemaTooNew`], as does any other
+//! error, such as the database being locked by another process.
 //!
 //! # Triggers
 //!
@@ -17,17 +94,29 @@
 //! - `notes_ad` - Removes deleted notes from search index  
 //! - `notes_au` - Updates modified notes in search index
 //!
+//! The `trigrams` table has its own `notes_trigram_ai`, `notes_trigram_ad`
+//! and `notes_trigram_au` triggers while it exists.
+//!
 //! # Thread Safety
 //!
 //! The index uses an `Arc<Mutex<Connection>>` to provide thread-safe access
 //! to the SQLite database, allowing concurrent read and write operations.
 
-use crate::domain::LocalNote;
+use crate::domain::{LocalNote, content_hash, dir_prefix};
 use crate::error::OraError;
-use rusqlite::{Connection, params};
+use crate::front_matter::FrontMatter;
+use crate::history::{History, RevisionSource};
+use crate::ignore::{IgnoreRules, markdown_files};
+use crate::links::{LinkKind, extract_links, note_keys, wiki_target_key};
+use crate::shelf::config::{ShelfConfig, Tokenizer};
+use crate::tags::extract_tags;
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Row, Transaction, params};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::UNIX_EPOCH;
+use uuid::Uuid;
 
 /// Thread-safe SQLite index for note search functionality.
 ///
@@ -50,6 +139,19 @@ use std::sync::{Arc, Mutex};
 pub struct Index {
     /// Shared SQLite connection wrapped in a mutex for thread safety.
     pub conn: Arc<Mutex<Connection>>,
+
+    /// Root directory of the shelf this index belongs to.
+    root: PathBuf,
+
+    /// Settings from the shelf's `.shelf.toml`.
+    config: ShelfConfig,
+
+    /// Paths left out of the index, from the config and `.oraignore` files.
+    /// Shared between clones so a reload applies to all of them.
+    ignore: Arc<RwLock<IgnoreRules>>,
+
+    /// Revision store, if the shelf config enables history.
+    history: Option<History>,
 }
 
 /// A note that has been indexed for search.
@@ -61,25 +163,140 @@ pub struct IndexedNote {
     /// The title of the note (extracted from filename).
     pub title: String,
 
-    /// The full content of the note.
+    /// The body of the note, without its front matter header.
     pub content: String,
 
+    /// The parsed front matter header of the note.
+    pub front_matter: FrontMatter,
+
     /// The file path where the note is stored.
     pub path: PathBuf,
+
+    /// The stable ID of the note, from its front matter `id:` or assigned
+    /// by the index.
+    pub id: String,
+}
+
+/// The identity of a note's index entry, carried over when the note moves
+/// to another shelf; see [`Index::import_note`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteOrigin {
+    /// The stable ID of the note.
+    pub id: String,
+
+    /// When the note was first indexed, as stored in the database.
+    pub created_at: String,
+
+    /// When the note's entry last changed, as stored in the database.
+    pub updated_at: String,
+}
+
+/// A link from one indexed note to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteLink {
+    /// Path of the note containing the link.
+    pub source: PathBuf,
+
+    /// Whether the link is a wikilink or a Markdown link.
+    pub kind: LinkKind,
+
+    /// The link target as written, without fragment or alias.
+    pub target: String,
+
+    /// The `#heading` part of the link, if any.
+    pub fragment: Option<String>,
+
+    /// The display text of the link, if any.
+    pub alias: Option<String>,
+
+    /// Path of the note the link points to, or `None` if it is dangling.
+    pub resolved: Option<PathBuf>,
+}
+
+/// SQL query for the ids of the notes that link `l` points at.
+const LINK_TARGETS: &str = "SELECT t.id FROM notes t
+    WHERE l.kind = 'markdown' AND t.path = l.target_path
+    UNION ALL
+    SELECT k.note_id FROM note_keys k
+    WHERE l.kind = 'wiki' AND k.key = l.target_key";
+
+/// SQL condition that is true when link `l` points at the note whose id
+/// is the SQL expression `note`.
+fn links_to(note: &str) -> String {
+    format!(
+        "((l.kind = 'markdown' AND l.target_path = (SELECT path FROM notes WHERE id = {note}))
+          OR (l.kind = 'wiki' AND l.target_key IN (SELECT key FROM note_keys WHERE note_id = {note})))"
+    )
+}
+
+/// The mtime, size and content hash stored for a note, if known.
+type StoredStamp = (Option<i64>, Option<i64>, Option<String>);
+
+/// What [`Index::reconcile`] changed to match the files on disk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    /// Notes that were not indexed before.
+    pub added: Vec<PathBuf>,
+
+    /// Notes whose content changed since they were last indexed.
+    pub updated: Vec<PathBuf>,
+
+    /// Rows dropped because their file no longer exists.
+    pub removed: Vec<PathBuf>,
+
+    /// Number of notes that were already up to date.
+    pub unchanged: usize,
+
+    /// `true` if the database could not be migrated and was rebuilt from
+    /// the files on disk. Stable IDs not stored in front matter are lost.
+    pub rebuilt: bool,
+}
+
+impl ReconcileReport {
+    /// Returns `true` if the index was already in sync with the disk.
+    pub fn is_clean(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Columns of note `n` read by [`IndexedNote::from_row`], in order.
+pub(crate) const NOTE_COLUMNS: &str = "n.title, n.content, n.front_matter, n.path, n.uid";
+
+impl IndexedNote {
+    /// Builds an `IndexedNote` from a row starting with [`NOTE_COLUMNS`].
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(IndexedNote {
+            title: row.get(0)?,
+            content: row.get(1)?,
+            front_matter: FrontMatter::parse(&row.get::<_, String>(2)?),
+            path: PathBuf::from(row.get::<_, String>(3)?),
+            id: row.get(4)?,
+        })
+    }
 }
 
 impl Index {
     /// Creates a new search index for the given shelf path.
     ///
-    /// Initializes the SQLite database, creates the necessary tables and
-    /// triggers, and indexes any existing notes in the shelf directory.
+    /// Initializes the SQLite database, creates or migrates the necessary
+    /// tables and triggers, and reconciles the index with the notes in the
+    /// shelf directory.
     ///
     /// # Database Setup
     ///
     /// Creates the following schema:
-    /// - `notes` table with id, title, content, path, and timestamps
+    /// - `notes` table with id, title, body, front matter, path, stable ID,
+    ///   file fingerprint (mtime, size, hash), and timestamps
+    /// - `note_metadata` table with one row per front matter value
+    /// - `note_tags` table with one row per tag
+    /// - `links` table with one row per link to another note
     /// - `contents` FTS5 virtual table for full-text search
-    /// - Triggers to keep FTS5 table synchronized
+    /// - `trigrams` FTS5 virtual table, if the shelf config enables
+    ///   `trigram_index`
+    /// - Triggers to keep the FTS5 tables synchronized
+    ///
+    /// Databases created by older versions are migrated to the current
+    /// [`SCHEMA_VERSION`]; see the module documentation.
     ///
     /// # Arguments
     /// * `shelf_path` - Path to the shelf directory containing notes
@@ -94,62 +311,171 @@ impl Index {
     /// - Creates `.shelf.db` file in the shelf directory
     /// - Scans and indexes all existing `.md` files recursively
     pub fn new(shelf_path: &Path) -> Result<Self, OraError> {
+        Self::new_with_report(shelf_path).map(|(index, _)| index)
+    }
+
+    /// Creates a new search index like [`Index::new`], also returning what
+    /// the startup reconciliation changed.
+    ///
+    /// # Arguments
+    /// * `shelf_path` - Path to the shelf directory containing notes
+    ///
+    /// # Returns
+    /// The index and the [`ReconcileReport`] of its startup scan
+    ///
+    /// # Errors
+    /// Returns `OraError` if database creation, initialization or the scan fails
+    pub fn new_with_report(shelf_path: &Path) -> Result<(Self, ReconcileReport), OraError> {
+        let config = ShelfConfig::load(shelf_path)?;
         let db_path = shelf_path.join(".shelf.db");
-        let conn = Connection::open(&db_path)?;
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS notes (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                title TEXT NOT NULL,
-                content TEXT NOT NULL DEFAULT '',
-                path TEXT UNIQUE NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+        let (conn, rebuilt) = open_database(&db_path)?;
+        set_tokenizer(&conn, config.tokenizer)?;
+        set_trigram_index(&conn, config.trigram_index)?;
 
-        conn.execute(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS contents USING fts5(title, content, content='notes', content_rowid='id')",
-            [],
-        )?;
+        let index = Index {
+            conn: Arc::new(Mutex::new(conn)),
+            root: shelf_path.to_path_buf(),
+            ignore: Arc::new(RwLock::new(IgnoreRules::load(shelf_path, &config.ignore)?)),
+            history: config
+                .history
+                .then(|| History::open(shelf_path, config.history_limit))
+                .transpose()?,
+            config,
+        };
 
-        conn.execute(
-            "CREATE TRIGGER IF NOT EXISTS notes_ai AFTER INSERT ON notes BEGIN
-             INSERT INTO contents(rowid, title, content) VALUES (new.id, new.title, new.content);
-            END",
-            [],
-        )?;
+        let mut report = index.reconcile()?;
+        report.rebuilt = rebuilt;
 
-        conn.execute(
-            "CREATE TRIGGER IF NOT EXISTS notes_ad AFTER DELETE ON notes BEGIN
-             INSERT INTO contents(contents, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
-            END",
-            [],
-        )?;
+        Ok((index, report))
+    }
 
-        conn.execute(
-            "CREATE TRIGGER IF NOT EXISTS notes_au AFTER UPDATE ON notes BEGIN
-             INSERT INTO contents(contents, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
-             INSERT INTO contents(rowid, title, content) VALUES (new.id, new.title, new.content);
-            END",
-            [],
-        )?;
+    /// Brings the index in line with the Markdown files on disk.
+    ///
+    /// Compares each file's modification time and size with the values
+    /// stored when it was last indexed. Files that differ are hashed, and
+    /// re-indexed only if their content actually changed; otherwise just
+    /// the stored fingerprint is refreshed.
+    ///
+    /// # Behavior
+    /// - Indexes files that have no row yet
+    /// - Re-indexes files whose content changed since they were indexed
+    /// - Deletes rows whose file no longer exists or is now ignored by
+    ///   the shelf config
+    /// - Keeps stable IDs of notes renamed outside the library, matched by
+    ///   their front matter `id:` or, without one, by identical content.
+    ///   A new file only takes over the entry of a vanished one if no other
+    ///   new file or vanished entry has the same content, so separate notes
+    ///   with the same text never share an ID
+    ///
+    /// # Returns
+    /// A [`ReconcileReport`] listing what was fixed
+    ///
+    /// # Errors
+    /// Returns `OraError` if scanning the shelf or a database operation fails
+    pub fn reconcile(&self) -> Result<ReconcileReport, OraError> {
+        let mut report = ReconcileReport::default();
 
-        let index = Index {
-            conn: Arc::new(Mutex::new(conn)),
+        let stored: HashMap<String, StoredStamp> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT path, mtime, size, hash FROM notes")?;
+            stmt.query_map([], |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?)))
+            })?
+            .collect::<Result<_, _>>()?
         };
 
-        index.index_existing_files(shelf_path)?;
+        let mut new_notes = Vec::new();
+        for path in markdown_files(&self.root, &self.root, &self.ignore_rules())? {
+            let Some((mtime, size)) = file_stamp(&path) else {
+                continue;
+            };
 
-        return Ok(index);
+            match stored.get(&path.display().to_string()) {
+                None => {
+                    if let Ok(note) = LocalNote::open(&path) {
+                        new_notes.push(note);
+                    }
+                }
+                Some((stored_mtime, stored_size, _))
+                    if *stored_mtime == Some(mtime) && *stored_size == Some(size) =>
+                {
+                    report.unchanged += 1;
+                }
+                Some((_, _, stored_hash)) => {
+                    let Ok(note) = LocalNote::open(&path) else {
+                        continue;
+                    };
+                    if stored_hash.as_deref() == Some(&content_hash(note.content.as_bytes())) {
+                        let conn = self.conn.lock().unwrap();
+                        conn.execute(
+                            "UPDATE notes SET mtime = ?, size = ? WHERE path = ?",
+                            params![mtime, size, path.display().to_string()],
+                        )?;
+                        report.unchanged += 1;
+                    } else {
+                        self.index_note(&note)?;
+                        self.record_revision(&note, RevisionSource::External)?;
+                        report.updated.push(path);
+                    }
+                }
+            }
+        }
+
+        // New files are matched with vanished entries by content only when
+        // the match is unambiguous on both sides.
+        let mut vanished: HashMap<&str, usize> = HashMap::new();
+        for (path, (_, _, hash)) in &stored {
+            if let Some(hash) = hash
+                && !Path::new(path).is_file()
+            {
+                *vanished.entry(hash).or_default() += 1;
+            }
+        }
+        let hashes: Vec<String> = new_notes
+            .iter()
+            .map(|note| content_hash(note.content.as_bytes()))
+            .collect();
+        let mut created: HashMap<&str, usize> = HashMap::new();
+        for (note, hash) in new_notes.iter().zip(&hashes) {
+            if note.id().is_none() {
+                *created.entry(hash).or_default() += 1;
+            }
+        }
+        for (note, hash) in new_notes.into_iter().zip(&hashes) {
+            let adopt =
+                created.get(hash.as_str()) == Some(&1) && vanished.get(hash.as_str()) == Some(&1);
+            self.store_note(&note, None, None, adopt)?;
+            self.record_revision(&note, RevisionSource::External)?;
+            report.added.push(note.path);
+        }
+
+        // Collected after indexing, so rows moved to a new path by their
+        // front matter ID or content are not reported as missing.
+        let indexed: Vec<String> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT path FROM notes")?;
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?
+        };
+        for path in indexed.into_iter().map(PathBuf::from) {
+            if !path.is_file() || self.is_ignored(&path) {
+                let conn = self.conn.lock().unwrap();
+                conn.execute(
+                    "DELETE FROM notes WHERE path = ?",
+                    params![path.display().to_string()],
+                )?;
+                report.removed.push(path);
+            }
+        }
+
+        Ok(report)
     }
 
     /// Recursively indexes all existing Markdown files in the shelf.
     ///
     /// Scans the shelf directory and all subdirectories for `.md` files,
     /// indexing any that haven't been indexed yet. Hidden files (starting
-    /// with `.`) are ignored.
+    /// with `.`) and paths matched by the shelf's ignore rules are skipped.
     ///
     /// # Arguments
     /// * `shelf_path` - Root path of the shelf to scan
@@ -157,56 +483,294 @@ impl Index {
     /// # Behavior
     /// - Recursively walks through all subdirectories
     /// - Only processes files with `.md` extension
-    /// - Skips hidden files and directories
+    /// - Skips hidden files and directories and ignored paths
     /// - Avoids re-indexing files that already exist in the database
     ///
+    /// # Returns
+    /// The paths of the notes that were newly indexed
+    ///
     /// # Errors
     /// Returns `OraError` if directory scanning or file indexing fails
-    pub fn index_existing_files(&self, shelf_path: &Path) -> Result<(), OraError> {
-        for entry in fs::read_dir(shelf_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                self.index_existing_files(&path)?;
-            } else if let Some(ext) = path.extension() {
-                if ext == "md" && !path.file_name().unwrap().to_str().unwrap().starts_with('.') {
-                    // Check if file is already indexed to avoid duplicates
-                    if !self.exists(&path)? {
-                        if let Ok(note) = LocalNote::open(&path) {
-                            self.index_note(&note)?;
-                        }
-                    }
-                }
+    pub fn index_existing_files(&self, shelf_path: &Path) -> Result<Vec<PathBuf>, OraError> {
+        let mut indexed = Vec::new();
+        for path in markdown_files(&self.root, shelf_path, &self.ignore_rules())? {
+            // Check if file is already indexed to avoid duplicates
+            if !self.exists(&path)?
+                && let Ok(note) = LocalNote::open(&path)
+            {
+                self.index_note(&note)?;
+                indexed.push(path);
             }
         }
-        Ok(())
+        Ok(indexed)
     }
 
     /// Adds or updates a note in the search index.
     ///
-    /// Uses `INSERT OR REPLACE` to either create a new entry or update
-    /// an existing one. The note is identified by its file path, so
-    /// moving a file to a new path will create a separate entry.
+    /// Inserts a new entry or updates the existing one in place. The note
+    /// is identified by its file path, so moving a file to a new path will
+    /// create a separate entry; use [`Index::rename_note`] to move one.
+    ///
+    /// The front matter header is split off the content: its raw text is
+    /// stored in the `front_matter` column and each value gets a row in
+    /// `note_metadata`, while only the body is full-text indexed. Tags from
+    /// the header and inline `#hashtags` are stored in `note_tags`, and
+    /// wikilinks and Markdown links to other notes in `links`.
     ///
     /// # Arguments
     /// * `note` - The note to index
     ///
     /// # Behavior
     /// - Updates the `updated_at` timestamp automatically
+    /// - Keeps the row id, stable ID and `created_at` of existing entries
+    /// - Uses the front matter `id:` as stable ID when present. If another
+    ///   entry holds that ID and its file no longer exists, that entry is
+    ///   moved to this path (the note was renamed outside the library); if
+    ///   its file still exists (the note was copied), this note gets a
+    ///   generated ID instead. Revisions in the shelf history move along
+    ///   with a taken-over entry
     /// - Triggers FTS5 index update through database triggers
     /// - Thread-safe through mutex locking
     ///
     /// # Errors
     /// Returns `OraError` if the database operation fails
     pub fn index_note(&self, note: &LocalNote) -> Result<(), OraError> {
+        self.store_note(note, None, None, false).map(drop)
+    }
+
+    /// Indexes a note the watcher saw being created, and reports whether it
+    /// took over the entry of a note that vanished from disk.
+    ///
+    /// Besides the front matter `id:` handled by [`Index::index_note`], a
+    /// note without one that is not indexed yet takes over the entry of a
+    /// vanished file with exactly the same content, so a move the watcher
+    /// saw as a separate remove and create keeps its ID. Only a single
+    /// vanished entry with that content qualifies; entries of files deleted
+    /// while no watcher ran are pruned at startup by [`Index::reconcile`].
+    ///
+    /// # Returns
+    /// The old path of the entry that was moved to the note's path, if any
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub(crate) fn index_moved_note(&self, note: &LocalNote) -> Result<Option<PathBuf>, OraError> {
+        self.store_note(note, None, None, true)
+    }
+
+    /// Indexes a note moved in from another shelf, keeping the stable ID
+    /// and timestamps it had there.
+    ///
+    /// Any entry already indexed at the note's path is replaced. A front
+    /// matter `id:` takes precedence over `origin.id`, and if another entry
+    /// of this index already holds `origin.id`, a new ID is generated
+    /// instead.
+    ///
+    /// # Arguments
+    /// * `note` - The note to index
+    /// * `origin` - The note's entry in its previous index, from
+    ///   [`Index::origin`]
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn import_note(&self, note: &LocalNote, origin: &NoteOrigin) -> Result<(), OraError> {
+        self.remove_note(note)?;
+        let taken = self.get_by_id(&origin.id)?.is_some();
+        self.store_note(note, (!taken).then_some(&origin.id), Some(origin), false)
+            .map(drop)
+    }
+
+    /// Returns the stable ID and timestamps of the entry at `path`, to
+    /// carry them over with [`Index::import_note`].
+    ///
+    /// # Returns
+    /// `None` if no note is indexed at `path`
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn origin(&self, path: &Path) -> Result<Option<NoteOrigin>, OraError> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO notes (title, content, path, updated_at)
-             VALUES (?, ?, ?, CURRENT_TIMESTAMP)",
-            params![&note.title, &note.content, note.path.display().to_string()],
+        let origin = conn
+            .query_row(
+                "SELECT uid, created_at, updated_at FROM notes WHERE path = ?",
+                params![path.display().to_string()],
+                |row| {
+                    Ok(NoteOrigin {
+                        id: row.get(0)?,
+                        created_at: row.get(1)?,
+                        updated_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(origin)
+    }
+
+    /// Inserts or updates the entry for `note`. New entries without a front
+    /// matter ID get `new_uid`, or a random UUID if it is `None`, and the
+    /// timestamps of `origin` if given.
+    ///
+    /// With `adopt`, a note without a front matter ID that is not indexed
+    /// yet takes over the entry of the only vanished file with the same
+    /// content, as described for [`Index::index_moved_note`].
+    ///
+    /// Returns the old path of the entry that was moved to the note's path
+    /// because its file vanished.
+    fn store_note(
+        &self,
+        note: &LocalNote,
+        new_uid: Option<&str>,
+        origin: Option<&NoteOrigin>,
+        adopt: bool,
+    ) -> Result<Option<PathBuf>, OraError> {
+        let (front_matter, body) = FrontMatter::split(&note.content);
+        let path = note.path.display().to_string();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut uid = note.id();
+        let mut moved_from = None;
+        if let Some(wanted) = &uid {
+            let holder: Option<(i64, String)> = tx
+                .query_row(
+                    "SELECT id, path FROM notes WHERE uid = ?",
+                    params![wanted],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            if let Some((row_id, holder_path)) = holder
+                && holder_path != path
+            {
+                if Path::new(&holder_path).exists() {
+                    uid = None;
+                } else {
+                    tx.execute("DELETE FROM notes WHERE path = ?", params![path])?;
+                    tx.execute(
+                        "UPDATE notes SET path = ? WHERE id = ?",
+                        params![path, row_id],
+                    )?;
+                    moved_from = Some(PathBuf::from(holder_path));
+                }
+            }
+        } else if adopt && new_uid.is_none() && !note.content.trim().is_empty() {
+            let indexed: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM notes WHERE path = ?)",
+                params![path],
+                |row| row.get(0),
+            )?;
+            if !indexed {
+                let candidates: Vec<(i64, String)> = {
+                    let mut stmt = tx.prepare("SELECT id, path FROM notes WHERE hash = ?")?;
+                    stmt.query_map(params![content_hash(note.content.as_bytes())], |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                    .collect::<Result<_, _>>()?
+                };
+                let mut vanished = candidates
+                    .into_iter()
+                    .filter(|(_, old_path)| !Path::new(old_path).exists());
+                if let (Some((row_id, old_path)), None) = (vanished.next(), vanished.next()) {
+                    tx.execute(
+                        "UPDATE notes SET path = ? WHERE id = ?",
+                        params![path, row_id],
+                    )?;
+                    moved_from = Some(PathBuf::from(old_path));
+                }
+            }
+        }
+
+        let (mtime, size) = file_stamp(&note.path).unzip();
+        let id: i64 = tx.query_row(
+            "INSERT INTO notes (title, content, front_matter, path, uid, mtime, size, hash, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, COALESCE(?5, ?6), ?7, ?8, ?9,
+                     COALESCE(?10, CURRENT_TIMESTAMP), COALESCE(?11, CURRENT_TIMESTAMP))
+             ON CONFLICT(path) DO UPDATE SET
+                title = excluded.title,
+                content = excluded.content,
+                front_matter = excluded.front_matter,
+                uid = COALESCE(?5, notes.uid),
+                mtime = excluded.mtime,
+                size = excluded.size,
+                hash = excluded.hash,
+                updated_at = CURRENT_TIMESTAMP
+             RETURNING id",
+            params![
+                &note.title,
+                body,
+                front_matter.render(),
+                path,
+                uid,
+                new_uid.map_or_else(|| Uuid::new_v4().to_string(), str::to_string),
+                mtime,
+                size,
+                content_hash(note.content.as_bytes()),
+                origin.map(|o| &o.created_at),
+                origin.map(|o| &o.updated_at)
+            ],
+            |row| row.get(0),
         )?;
-        Ok(())
+
+        tx.execute("DELETE FROM note_metadata WHERE note_id = ?", params![id])?;
+        for (key, value) in front_matter.iter() {
+            for item in value.values() {
+                tx.execute(
+                    "INSERT INTO note_metadata (note_id, key, value) VALUES (?, ?, ?)",
+                    params![id, key, item],
+                )?;
+            }
+        }
+
+        tx.execute("DELETE FROM note_tags WHERE note_id = ?", params![id])?;
+        for tag in extract_tags(&note.content) {
+            tx.execute(
+                "INSERT INTO note_tags (note_id, tag) VALUES (?, ?)",
+                params![id, tag],
+            )?;
+        }
+
+        tx.execute("DELETE FROM note_keys WHERE note_id = ?", params![id])?;
+        for key in note_keys(&note.title, &note.path, &self.root) {
+            tx.execute(
+                "INSERT INTO note_keys (note_id, key) VALUES (?, ?)",
+                params![id, key],
+            )?;
+        }
+
+        tx.execute("DELETE FROM links WHERE source_id = ?", params![id])?;
+        for link in extract_links(&note.content) {
+            let target = match link.kind {
+                LinkKind::Wiki => link.target.strip_suffix(".md").unwrap_or(&link.target),
+                LinkKind::Markdown => &link.target,
+            };
+            let target_path = link
+                .resolve_path(&note.path, &self.root)
+                .map(|p| p.display().to_string());
+            let target_key = (link.kind == LinkKind::Wiki).then(|| wiki_target_key(target));
+            tx.execute(
+                "INSERT INTO links (source_id, kind, target, target_path, target_key, fragment, alias)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    id,
+                    link.kind.as_str(),
+                    target,
+                    target_path,
+                    target_key,
+                    link.fragment,
+                    link.alias
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        drop(conn);
+
+        if let Some(from) = &moved_from
+            && let Some(history) = self.history()
+        {
+            history.rename(from, &note.path)?;
+        }
+        Ok(moved_from)
     }
 
     /// Removes a note from the search index.
@@ -256,6 +820,39 @@ impl Index {
         Ok(count > 0)
     }
 
+    /// Checks if the index already holds exactly this version of a note.
+    ///
+    /// Compares the title, front matter and body stored for the note's path
+    /// with the note. Used to recognize file system events caused by writes
+    /// that were already indexed.
+    ///
+    /// # Arguments
+    /// * `note` - The note as it is on disk
+    ///
+    /// # Returns
+    /// `true` if an entry exists at the note's path with identical content
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn is_current(&self, note: &LocalNote) -> Result<bool, OraError> {
+        let (front_matter, body) = FrontMatter::split(&note.content);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT COUNT(*) FROM notes
+             WHERE path = ? AND title = ? AND content = ? AND front_matter = ?",
+        )?;
+        let count: i64 = stmt.query_row(
+            params![
+                note.path.display().to_string(),
+                &note.title,
+                body,
+                front_matter.render()
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
     /// Retrieves a note from the index by its file path.
     ///
     /// Queries the database for a note with the exact file path
@@ -271,15 +868,11 @@ impl Index {
     /// Returns `OraError` if the database query fails
     pub fn get_by_path(&self, path: &Path) -> Result<Option<IndexedNote>, OraError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT title, content, path FROM notes WHERE path = ?")?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {NOTE_COLUMNS} FROM notes n WHERE n.path = ?"
+        ))?;
 
-        let result = stmt.query_row(params![path.display().to_string()], |row| {
-            Ok(IndexedNote {
-                title: row.get(0)?,
-                content: row.get(1)?,
-                path: PathBuf::from(row.get::<_, String>(2)?),
-            })
-        });
+        let result = stmt.query_row(params![path.display().to_string()], IndexedNote::from_row);
 
         match result {
             Ok(note) => Ok(Some(note)),
@@ -287,4 +880,808 @@ impl Index {
             Err(e) => Err(OraError::Other(e.to_string())),
         }
     }
+
+    /// Retrieves a note from the index by its stable ID.
+    ///
+    /// # Arguments
+    /// * `id` - The note's front matter `id:` or index-assigned ID
+    ///
+    /// # Returns
+    /// `Some(IndexedNote)` if a note has that ID, `None` otherwise
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn get_by_id(&self, id: &str) -> Result<Option<IndexedNote>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {NOTE_COLUMNS} FROM notes n WHERE n.uid = ?"
+        ))?;
+
+        match stmt.query_row(params![id], IndexedNote::from_row) {
+            Ok(note) => Ok(Some(note)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Moves an indexed note to a new path and re-indexes its content.
+    ///
+    /// Unlike removing and re-adding the note, this keeps the row id, the
+    /// stable ID, `created_at`, and links pointing from the note.
+    ///
+    /// # Arguments
+    /// * `old_path` - The path the note was indexed under
+    /// * `note` - The note at its new path
+    ///
+    /// # Returns
+    /// `true` if an entry existed at `old_path`, `false` if the note was
+    /// indexed as new
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn rename_note(&self, old_path: &Path, note: &LocalNote) -> Result<bool, OraError> {
+        let moved = {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let new_path = note.path.display().to_string();
+            let old_path = old_path.display().to_string();
+
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM notes WHERE path = ?)",
+                params![old_path],
+                |row| row.get(0),
+            )?;
+            if exists && old_path != new_path {
+                tx.execute("DELETE FROM notes WHERE path = ?", params![new_path])?;
+                tx.execute(
+                    "UPDATE notes SET path = ?, title = ? WHERE path = ?",
+                    params![new_path, &note.title, old_path],
+                )?;
+            }
+            tx.commit()?;
+            exists
+        };
+
+        self.index_note(note)?;
+        Ok(moved)
+    }
+
+    /// Moves all indexed notes under `old_dir` to the same relative paths
+    /// under `new_dir`, then re-indexes them from disk.
+    ///
+    /// Like [`Index::rename_note`], this keeps row ids, stable IDs and
+    /// `created_at`. Notes that can no longer be read are removed.
+    ///
+    /// # Arguments
+    /// * `old_dir` - The directory the notes were indexed under
+    /// * `new_dir` - The directory they now live in
+    ///
+    /// # Returns
+    /// The old and new path of every note that was moved
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database operation fails
+    pub fn rename_dir(
+        &self,
+        old_dir: &Path,
+        new_dir: &Path,
+    ) -> Result<Vec<(PathBuf, PathBuf)>, OraError> {
+        let moved: Vec<(PathBuf, PathBuf)> = {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let prefix = dir_prefix(old_dir);
+
+            let old_paths: Vec<String> = {
+                let mut stmt =
+                    tx.prepare("SELECT path FROM notes WHERE substr(path, 1, length(?1)) = ?1")?;
+                stmt.query_map(params![prefix], |row| row.get(0))?
+                    .collect::<Result<_, _>>()?
+            };
+
+            let mut moved = Vec::new();
+            for old_path in old_paths {
+                // Stored paths are display strings, so strip the prefix as
+                // text: `old_dir` may not round-trip through one.
+                let Some(relative) = old_path.strip_prefix(prefix.as_str()) else {
+                    continue;
+                };
+                let new_path = new_dir.join(relative);
+                let new_str = new_path.display().to_string();
+
+                tx.execute("DELETE FROM notes WHERE path = ?", params![new_str])?;
+                tx.execute(
+                    "UPDATE notes SET path = ? WHERE path = ?",
+                    params![new_str, old_path],
+                )?;
+                moved.push((PathBuf::from(old_path), new_path));
+            }
+            tx.commit()?;
+            moved
+        };
+
+        for (_, path) in &moved {
+            match LocalNote::open(path) {
+                Ok(note) => self.index_note(&note)?,
+                Err(_) => {
+                    self.remove_note(&LocalNote::at_path(path))?;
+                }
+            }
+        }
+
+        Ok(moved)
+    }
+
+    /// Lists the paths of all indexed notes below `dir`, at any depth.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn paths_under(&self, dir: &Path) -> Result<Vec<PathBuf>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let prefix = dir_prefix(dir);
+        let mut stmt = conn.prepare(
+            "SELECT path FROM notes WHERE substr(path, 1, length(?1)) = ?1 ORDER BY path",
+        )?;
+        let paths = stmt
+            .query_map(params![prefix], |row| row.get::<_, String>(0))?
+            .map(|path| path.map(PathBuf::from))
+            .collect::<Result<_, _>>()?;
+        Ok(paths)
+    }
+
+    /// Finds all notes whose front matter has `key` set to `value`.
+    ///
+    /// List values match if any of their items equals `value`.
+    ///
+    /// # Arguments
+    /// * `key` - The front matter key, e.g. `aliases`
+    /// * `value` - The value to look for
+    ///
+    /// # Returns
+    /// Matching notes ordered by title
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn find_by_metadata(&self, key: &str, value: &str) -> Result<Vec<IndexedNote>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT {NOTE_COLUMNS}
+             FROM notes n
+             JOIN note_metadata m ON m.note_id = n.id
+             WHERE m.key = ? AND m.value = ?
+             ORDER BY n.title"
+        ))?;
+
+        let rows = stmt.query_map(params![key, value], IndexedNote::from_row)?;
+
+        let mut notes = Vec::new();
+        for row in rows {
+            notes.push(row?);
+        }
+        Ok(notes)
+    }
+
+    /// Returns the root directory of the shelf this index belongs to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the shelf configuration the index was opened with.
+    pub fn config(&self) -> &ShelfConfig {
+        &self.config
+    }
+
+    /// Checks whether `path` is excluded from the index by the shelf's
+    /// ignore patterns or `.oraignore` files.
+    ///
+    /// Hidden files are not covered here; they are never indexed.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.ignore.read().unwrap().is_ignored_in(&self.root, path)
+    }
+
+    /// Returns the revision store of the shelf, or `None` if the shelf
+    /// config does not enable history.
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// Records a revision of `note` if the shelf keeps a history.
+    ///
+    /// # Errors
+    /// Returns `OraError` if the history database operation fails
+    pub fn record_revision(
+        &self,
+        note: &LocalNote,
+        source: RevisionSource,
+    ) -> Result<(), OraError> {
+        if let Some(history) = &self.history {
+            history.record(&note.path, &note.content, source)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a snapshot of the ignore rules currently in effect.
+    pub fn ignore_rules(&self) -> IgnoreRules {
+        self.ignore.read().unwrap().clone()
+    }
+
+    /// Re-reads the `.oraignore` files of the shelf.
+    ///
+    /// Only the rules are replaced; call [`Index::reconcile`] afterwards to
+    /// drop rows of newly ignored notes and index newly visible ones.
+    ///
+    /// # Errors
+    /// Returns `OraError` if a directory or ignore file cannot be read
+    pub fn reload_ignore_rules(&self) -> Result<(), OraError> {
+        let rules = IgnoreRules::load(&self.root, &self.config.ignore)?;
+        *self.ignore.write().unwrap() = rules;
+        Ok(())
+    }
+
+    /// Lists the links going out of the note at `path`.
+    ///
+    /// # Arguments
+    /// * `path` - The file path of the linking note
+    ///
+    /// # Returns
+    /// The note's links in document order, each with its resolved target
+    /// (or `None` if the target does not exist)
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn outgoing_links(&self, path: &Path) -> Result<Vec<NoteLink>, OraError> {
+        self.query_links("s.path = ?1", params![path.display().to_string()])
+    }
+
+    /// Lists the links from other notes that point at the note at `path`.
+    ///
+    /// Answers "what links here". Links from the note to itself are not
+    /// included.
+    ///
+    /// # Arguments
+    /// * `path` - The file path of the linked note
+    ///
+    /// # Returns
+    /// Incoming links ordered by source path
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn backlinks(&self, path: &Path) -> Result<Vec<NoteLink>, OraError> {
+        let condition = format!(
+            "s.path != ?1 AND {}",
+            links_to("(SELECT id FROM notes WHERE path = ?1)")
+        );
+        self.query_links(&condition, params![path.display().to_string()])
+    }
+
+    /// Lists all links in the shelf whose target does not exist.
+    ///
+    /// # Returns
+    /// Dangling links ordered by source path
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn unresolved_links(&self) -> Result<Vec<NoteLink>, OraError> {
+        let condition = format!("NOT EXISTS ({LINK_TARGETS})");
+        self.query_links(&condition, [])
+    }
+
+    /// Lists notes that no other note links to.
+    ///
+    /// # Returns
+    /// Orphan notes ordered by title
+    ///
+    /// # Errors
+    /// Returns `OraError` if the database query fails
+    pub fn orphans(&self) -> Result<Vec<IndexedNote>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE NOT EXISTS (
+                SELECT 1 FROM links l WHERE l.source_id != n.id AND {}
+             )
+             ORDER BY n.title",
+            links_to("n.id")
+        ))?;
+        let rows = stmt.query_map([], IndexedNote::from_row)?;
+
+        let mut notes = Vec::new();
+        for row in rows {
+            notes.push(row?);
+        }
+        Ok(notes)
+    }
+
+    /// Runs a link query with `condition` applied to links `l` and their
+    /// source notes `s`.
+    fn query_links(
+        &self,
+        condition: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<NoteLink>, OraError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT s.path, l.kind, l.target, l.fragment, l.alias,
+                (SELECT path FROM notes WHERE id IN ({LINK_TARGETS}) ORDER BY path LIMIT 1)
+             FROM links l
+             JOIN notes s ON s.id = l.source_id
+             WHERE {condition}
+             ORDER BY s.path, l.rowid"
+        ))?;
+
+        let rows = stmt.query_map(params, |row| {
+            let kind: String = row.get(1)?;
+            Ok(NoteLink {
+                source: PathBuf::from(row.get::<_, String>(0)?),
+                kind: LinkKind::from_name(&kind).unwrap_or(LinkKind::Wiki),
+                target: row.get(2)?,
+                fragment: row.get(3)?,
+                alias: row.get(4)?,
+                resolved: row.get::<_, Option<String>>(5)?.map(PathBuf::from),
+            })
+        })?;
+
+        let mut links = Vec::new();
+        for row in rows {
+            links.push(row?);
+        }
+        Ok(links)
+    }
+}
+
+/// Returns a file's modification time (nanoseconds since the epoch) and size.
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((mtime.as_nanos() as i64, metadata.len() as i64))
+}
+
+/// Current version of the index schema, stored in `PRAGMA user_version`.
+pub const SCHEMA_VERSION: i32 = MIGRATIONS.len() as i32;
+
+/// A step from one schema version to the next.
+struct Migration {
+    /// What the migration changes, for error messages.
+    description: &'static str,
+    apply: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// All migrations in order; migration `i` brings the schema to version `i + 1`.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "create notes and full-text tables",
+        apply: create_base_schema,
+    },
+    Migration {
+        description: "split front matter into its own column",
+        apply: add_front_matter,
+    },
+    Migration {
+        description: "add note tags",
+        apply: add_note_tags,
+    },
+    Migration {
+        description: "add links between notes",
+        apply: add_links,
+    },
+    Migration {
+        description: "add stable note IDs",
+        apply: add_stable_ids,
+    },
+    Migration {
+        description: "add file fingerprints for reconciliation",
+        apply: add_fingerprints,
+    },
+    Migration {
+        description: "add link target keys",
+        apply: add_link_keys,
+    },
+    Migration {
+        description: "index content hashes",
+        apply: add_hash_index,
+    },
+];
+
+/// Opens the database at `db_path` and brings its schema up to date.
+///
+/// Rebuilds the schema if a migration of an older version fails, and
+/// recreates the file if it is not a database or is corrupt. Any other
+/// error, such as a database locked by another process, is returned.
+///
+/// # Returns
+/// The connection and whether the database had to be rebuilt
+///
+/// # Errors
+/// - [`OraError::SchemaTooNew`] if a newer version of the library wrote
+///   the database
+/// - [`OraError`] if the database cannot be opened or migrated
+fn open_database(db_path: &Path) -> Result<(Connection, bool), OraError> {
+    let mut conn = Connection::open(db_path)?;
+    let error = match migrate(&mut conn) {
+        Ok(()) => return Ok((conn, false)),
+        Err(MigrateError::Failed(_)) => match drop_schema(&conn) {
+            Ok(()) => match migrate(&mut conn) {
+                Ok(()) => return Ok((conn, true)),
+                Err(error) => error,
+            },
+            Err(error) => error.into(),
+        },
+        Err(error) => error,
+    };
+    if !matches!(error, MigrateError::Corrupt(_)) {
+        return Err(error.into());
+    }
+
+    drop(conn);
+    remove_database(db_path)?;
+    let mut conn = Connection::open(db_path)?;
+    migrate(&mut conn)?;
+    Ok((conn, true))
+}
+
+/// Why [`migrate`] failed, which decides how [`open_database`] recovers.
+enum MigrateError {
+    /// The file is not an SQLite database or is damaged.
+    Corrupt(rusqlite::Error),
+
+    /// A migration step failed on an older schema.
+    Failed(OraError),
+
+    /// Any other error, which is not recovered from.
+    Fatal(OraError),
+}
+
+impl From<rusqlite::Error> for MigrateError {
+    fn from(error: rusqlite::Error) -> Self {
+        match error.sqlite_error_code() {
+            Some(ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt) => {
+                MigrateError::Corrupt(error)
+            }
+            _ => MigrateError::Fatal(error.into()),
+        }
+    }
+}
+
+impl From<MigrateError> for OraError {
+    fn from(error: MigrateError) -> Self {
+        match error {
+            MigrateError::Corrupt(error) => error.into(),
+            MigrateError::Failed(error) | MigrateError::Fatal(error) => error,
+        }
+    }
+}
+
+/// Deletes the database at `db_path` along with its journal files.
+fn remove_database(db_path: &Path) -> Result<(), OraError> {
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Recreates the full-text table if it was built with another tokenizer.
+///
+/// The table is refilled from the `notes` table, so switching tokenizers
+/// needs no rescan of the shelf.
+fn set_tokenizer(conn: &Connection, tokenizer: Tokenizer) -> Result<(), OraError> {
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE name = 'contents'",
+        [],
+        |row| row.get(0),
+    )?;
+    let current = match sql.split_once("tokenize=") {
+        Some((_, option)) => option.trim_end_matches(')').trim_matches(['\'', '"']),
+        None => Tokenizer::default().fts5_option(),
+    };
+    if current == tokenizer.fts5_option() {
+        return Ok(());
+    }
+
+    conn.execute_batch(&format!(
+        "BEGIN;
+         DROP TABLE contents;
+         CREATE VIRTUAL TABLE contents USING fts5(title, content, content='notes', content_rowid='id', tokenize='{}');
+         INSERT INTO contents(contents) VALUES('rebuild');
+         COMMIT;",
+        tokenizer.fts5_option()
+    ))?;
+    Ok(())
+}
+
+/// Creates or drops the trigram table and its triggers.
+///
+/// A new table is filled from the `notes` table, like [`set_tokenizer`].
+fn set_trigram_index(conn: &Connection, enabled: bool) -> Result<(), OraError> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'trigrams')",
+        [],
+        |row| row.get(0),
+    )?;
+    if exists == enabled {
+        return Ok(());
+    }
+
+    if !enabled {
+        conn.execute_batch(
+            "BEGIN;
+             DROP TRIGGER IF EXISTS notes_trigram_ai;
+             DROP TRIGGER IF EXISTS notes_trigram_ad;
+             DROP TRIGGER IF EXISTS notes_trigram_au;
+             DROP TABLE trigrams;
+             COMMIT;",
+        )?;
+        return Ok(());
+    }
+
+    conn.execute_batch(
+        "BEGIN;
+         CREATE VIRTUAL TABLE trigrams USING fts5(title, content, content='notes', content_rowid='id', tokenize='trigram');
+
+         CREATE TRIGGER notes_trigram_ai AFTER INSERT ON notes BEGIN
+          INSERT INTO trigrams(rowid, title, content) VALUES (new.id, new.title, new.content);
+         END;
+
+         CREATE TRIGGER notes_trigram_ad AFTER DELETE ON notes BEGIN
+          INSERT INTO trigrams(trigrams, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
+         END;
+
+         CREATE TRIGGER notes_trigram_au AFTER UPDATE ON notes BEGIN
+          INSERT INTO trigrams(trigrams, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
+          INSERT INTO trigrams(rowid, title, content) VALUES (new.id, new.title, new.content);
+         END;
+
+         INSERT INTO trigrams(trigrams) VALUES('rebuild');
+         COMMIT;",
+    )?;
+    Ok(())
+}
+
+/// Applies all migrations newer than the database's schema version.
+///
+/// # Errors
+/// - [`MigrateError::Fatal`] with [`OraError::SchemaTooNew`] if the
+///   database was written by a newer version of the library
+/// - [`MigrateError::Failed`] if a migration fails
+/// - [`MigrateError::Corrupt`] if the file is not a usable database
+fn migrate(conn: &mut Connection) -> Result<(), MigrateError> {
+    conn.execute_batch("PRAGMA foreign_keys = ON")?;
+
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(MigrateError::Fatal(OraError::SchemaTooNew {
+            found: version,
+            supported: SCHEMA_VERSION,
+        }));
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx).map_err(|e| match e.sqlite_error_code() {
+            Some(
+                ErrorCode::NotADatabase
+                | ErrorCode::DatabaseCorrupt
+                | ErrorCode::DatabaseBusy
+                | ErrorCode::DatabaseLocked,
+            ) => MigrateError::from(e),
+            _ => MigrateError::Failed(OraError::Other(format!(
+                "index migration to version {} ({}) failed: {e}",
+                i + 1,
+                migration.description
+            ))),
+        })?;
+        tx.pragma_update(None, "user_version", i as i32 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Drops every table, index and trigger and resets the schema version.
+fn drop_schema(conn: &Connection) -> rusqlite::Result<()> {
+    let objects: Vec<(String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT type, name FROM sqlite_master
+             WHERE name NOT LIKE 'sqlite_%' AND type IN ('trigger', 'table')
+             ORDER BY type = 'table', sql NOT LIKE 'CREATE VIRTUAL%'",
+        )?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?
+    };
+
+    conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+    for (kind, name) in objects {
+        let kind = if kind == "trigger" {
+            "TRIGGER"
+        } else {
+            "TABLE"
+        };
+        conn.execute_batch(&format!("DROP {kind} IF EXISTS \"{name}\""))?;
+    }
+    conn.pragma_update(None, "user_version", 0)
+}
+
+/// Returns `true` if `table` has a column named `column`.
+fn has_column(tx: &Transaction, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({table})"))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in columns {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Adds a column unless a pre-versioning database already has it.
+fn add_column(
+    tx: &Transaction,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    if !has_column(tx, table, column)? {
+        tx.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))?;
+    }
+    Ok(())
+}
+
+/// Version 1: the original notes table, FTS5 table and sync triggers.
+fn create_base_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL DEFAULT '',
+            path TEXT UNIQUE NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS contents USING fts5(title, content, content='notes', content_rowid='id');
+
+        CREATE TRIGGER IF NOT EXISTS notes_ai AFTER INSERT ON notes BEGIN
+         INSERT INTO contents(rowid, title, content) VALUES (new.id, new.title, new.content);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS notes_ad AFTER DELETE ON notes BEGIN
+         INSERT INTO contents(contents, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS notes_au AFTER UPDATE ON notes BEGIN
+         INSERT INTO contents(contents, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
+         INSERT INTO contents(rowid, title, content) VALUES (new.id, new.title, new.content);
+        END;",
+    )
+}
+
+/// Version 2: raw front matter per note and one row per front matter value.
+fn add_front_matter(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column(tx, "notes", "front_matter", "TEXT NOT NULL DEFAULT ''")?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS note_metadata (
+            note_id INTEGER NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS note_metadata_key ON note_metadata(key, value);",
+    )
+}
+
+/// Version 3: one row per normalized tag.
+fn add_note_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS note_tags (
+            note_id INTEGER NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (note_id, tag)
+        );
+
+        CREATE INDEX IF NOT EXISTS note_tags_tag ON note_tags(tag);",
+    )
+}
+
+/// Version 4: one row per link to another note.
+fn add_links(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS links (
+            source_id INTEGER NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            target_path TEXT,
+            fragment TEXT,
+            alias TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS links_source ON links(source_id);",
+    )
+}
+
+/// Version 5: a stable ID for every note, generated for existing rows.
+fn add_stable_ids(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column(tx, "notes", "uid", "TEXT")?;
+
+    let missing: Vec<i64> = {
+        let mut stmt = tx.prepare("SELECT id FROM notes WHERE uid IS NULL")?;
+        stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?
+    };
+    for id in missing {
+        tx.execute(
+            "UPDATE notes SET uid = ? WHERE id = ?",
+            params![Uuid::new_v4().to_string(), id],
+        )?;
+    }
+
+    tx.execute_batch("CREATE UNIQUE INDEX IF NOT EXISTS notes_uid ON notes(uid)")
+}
+
+/// Version 6: file fingerprints. Existing rows get none, so the next
+/// reconciliation re-indexes them with the current parsing rules.
+fn add_fingerprints(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column(tx, "notes", "mtime", "INTEGER")?;
+    add_column(tx, "notes", "size", "INTEGER")?;
+    add_column(tx, "notes", "hash", "TEXT")
+}
+
+/// Version 7: keys for resolving wikilinks with indexed lookups, filled in
+/// for existing rows. The shelf root is the directory of the database.
+fn add_link_keys(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column(tx, "links", "target_key", "TEXT")?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS note_keys (
+            note_id INTEGER NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+            key TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS note_keys_key ON note_keys(key);
+        CREATE INDEX IF NOT EXISTS note_keys_note ON note_keys(note_id);
+        CREATE INDEX IF NOT EXISTS links_target_key ON links(target_key);
+        CREATE INDEX IF NOT EXISTS links_target_path ON links(target_path);",
+    )?;
+
+    let root = tx
+        .path()
+        .and_then(|db| Path::new(db).parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let notes: Vec<(i64, String, String)> = {
+        let mut stmt = tx.prepare("SELECT id, title, path FROM notes")?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?
+    };
+    for (id, title, path) in notes {
+        tx.execute("DELETE FROM note_keys WHERE note_id = ?", params![id])?;
+        for key in note_keys(&title, Path::new(&path), &root) {
+            tx.execute(
+                "INSERT INTO note_keys (note_id, key) VALUES (?, ?)",
+                params![id, key],
+            )?;
+        }
+    }
+
+    let links: Vec<(i64, String)> = {
+        let mut stmt = tx.prepare("SELECT rowid, target FROM links WHERE kind = 'wiki'")?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?
+    };
+    for (rowid, target) in links {
+        tx.execute(
+            "UPDATE links SET target_key = ? WHERE rowid = ?",
+            params![wiki_target_key(&target), rowid],
+        )?;
+    }
+    Ok(())
+}
+
+/// Version 8: looks up notes by content hash, to find the entry of a note
+/// renamed outside the library.
+fn add_hash_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS notes_hash ON notes(hash);")
 }
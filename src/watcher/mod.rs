@@ -3,3 +3,4 @@ pub mod event;
 pub mod handler;
 pub mod index;
 pub mod service;
+pub mod subscription;
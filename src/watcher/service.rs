@@ -11,25 +11,51 @@
 //! - **Debouncer**: Prevents rapid successive changes from causing excessive updates
 //! - **Handler**: Processes debounced events and updates the SQLite index
 //!
+//! # Subscriptions
+//!
+//! Applications can be notified of changes instead of polling the index:
+//! [`WatcherService::subscribe`] and [`WatcherService::on_event`] register
+//! subscribers that receive a [`NoteEvent`] after each index update.
+//!
+//! # Configuration
+//!
+//! The service reads the shelf's `.shelf.toml` when it is created: the
+//! index uses the configured tokenizer (see [`crate::shelf::config`]).
+//!
+//! Only the shelf root and its visible, not ignored directories are
+//! watched, each on its own (see [`DirWatcher`]), so ignored trees such as
+//! `node_modules/` cost no watches. Events for hidden or ignored files in
+//! watched directories are dropped as they arrive, before debouncing.
+//! Editing an `.oraignore` file reloads the rules and brings the watched
+//! directories and the index in line with them; see [`crate::ignore`].
+//!
 //! # Thread Safety
 //!
 //! The service is designed to be thread-safe and can be safely used in
 //! concurrent applications. All shared state is properly synchronized.
 
 use std::{
-    path::PathBuf,
+    path::Path,
     sync::mpsc::{Sender, channel},
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use notify::{EventKind, RecommendedWatcher};
-
 use crate::{
     error::OraError,
-    watcher::{debounce::Debouncer, event::setup_file_watcher, handler::FileIndexHandler, index},
+    shelf::config::ShelfConfig,
+    watcher::{
+        debounce::{Debouncer, DebouncerMessage, ShutdownMode},
+        event::{DirWatcher, FsEvent, PairingTimer},
+        handler::FileIndexHandler,
+        index,
+        subscription::{NoteEvent, Subscribers},
+    },
 };
 
+/// How long [`WatcherService::shutdown`] waits for each background thread.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// A service that monitors file system changes and maintains an up-to-date search index.
 ///
 /// The `WatcherService` provides real-time monitoring of a directory tree,
@@ -41,14 +67,18 @@ use crate::{
 /// 1. **Creation**: Use [`WatcherService::create`] to initialize the service
 /// 2. **Start**: Call [`WatcherService::run`] to begin monitoring
 /// 3. **Shutdown**: Use [`WatcherService::shutdown`] to stop gracefully
+/// 4. **Restart**: [`WatcherService::run`] may be called again after shutdown
+///
+/// Dropping a running service shuts it down as if by [`WatcherService::shutdown`].
 ///
 /// # Thread Management
 ///
-/// The service spawns two background threads:
+/// The service spawns three background threads:
+/// - A timer thread that expires unpaired rename halves (see [`PairingTimer`])
 /// - A debouncer thread that processes raw file system events
 /// - A handler thread that updates the search index
 ///
-/// Both threads are properly joined during shutdown to ensure clean termination.
+/// All threads are properly joined during shutdown to ensure clean termination.
 pub struct WatcherService {
     /// Handles file system events and updates the search index.
     handler: FileIndexHandler,
@@ -59,17 +89,14 @@ pub struct WatcherService {
     /// Handle to the handler thread.
     handler_thread: Option<JoinHandle<()>>,
 
-    /// Channel for signaling shutdown to background threads.
-    shutdown_tx: Option<Sender<()>>,
+    /// Timer expiring unpaired rename halves of the running watcher.
+    rename_timer: Option<PairingTimer>,
+
+    /// Channel for signaling shutdown to the debouncer thread.
+    shutdown_tx: Option<Sender<DebouncerMessage>>,
 
     /// Debounce duration for file system events.
     duration: Duration,
-
-    /// Path being monitored for changes.
-    watch_path: PathBuf,
-
-    /// The underlying file system watcher.
-    watcher: Option<RecommendedWatcher>,
 }
 
 impl WatcherService {
@@ -85,9 +112,13 @@ impl WatcherService {
     /// # Returns
     /// A new `WatcherService` instance ready to be started
     ///
-    /// # Panics
-    /// Panics if the search index cannot be created. This typically indicates
-    /// permission issues or an invalid path.
+    /// # Errors
+    /// - [`OraError::Shelf`] if the shelf's `.shelf.toml` is invalid
+    /// - [`OraError::SchemaTooNew`] if the index was written by a newer
+    ///   version of the library
+    /// - [`OraError`] if the index cannot be created or the existing notes
+    ///   cannot be indexed, typically because of permission issues or an
+    ///   invalid path
     ///
     /// # Examples
     /// ```rust,no_run
@@ -101,19 +132,17 @@ impl WatcherService {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn create(shelf_path: &PathBuf, debounce_duration: Duration) -> Result<Self, OraError> {
-        let index = index::Index::new(shelf_path)
-            .expect("failed to create index, check provided path or permissions");
+    pub fn create(shelf_path: &Path, debounce_duration: Duration) -> Result<Self, OraError> {
+        let index = index::Index::new(shelf_path)?;
         let handler = FileIndexHandler::new(index);
 
         Ok(WatcherService {
             handler,
             debouncer_thread: None,
             handler_thread: None,
+            rename_timer: None,
             shutdown_tx: None,
             duration: debounce_duration,
-            watch_path: shelf_path.to_path_buf(),
-            watcher: None,
         })
     }
 
@@ -136,7 +165,8 @@ impl WatcherService {
     /// # Errors
     ///
     /// Returns an error if the file system watcher cannot be initialized.
-    /// This can happen due to permission issues or invalid paths.
+    /// This can happen due to permission issues or invalid paths. Also
+    /// returns [`OraError::Other`] if the service is already running.
     ///
     /// # Examples
     /// ```rust,no_run
@@ -158,11 +188,21 @@ impl WatcherService {
     /// # }
     /// ```
     pub fn run(&mut self) -> Result<(), OraError> {
-        let (raw_tx, raw_rx) = channel::<(EventKind, PathBuf)>();
-        let (debounced_tx, debounced_rx) = channel::<(EventKind, PathBuf)>();
+        if self.is_running() {
+            return Err(OraError::Other(
+                "watcher service is already running".to_string(),
+            ));
+        }
 
-        let watcher = setup_file_watcher(&self.watch_path, raw_tx)?;
-        self.watcher = Some(watcher);
+        let (raw_tx, raw_rx) = channel::<DebouncerMessage>();
+        let (debounced_tx, debounced_rx) = channel::<FsEvent>();
+
+        // Drop events for hidden and ignored paths before they are debounced.
+        let filter = self.handler.clone();
+        let mut watcher = DirWatcher::new(raw_tx.clone(), move |event| filter.is_relevant(event))?;
+        self.rename_timer = watcher.take_timer();
+        self.handler.attach(watcher)?;
+        self.shutdown_tx = Some(raw_tx);
 
         let mut debouncer = Debouncer::new(debounced_tx, self.duration);
 
@@ -173,25 +213,8 @@ impl WatcherService {
         let handler = self.handler.clone();
 
         let handler_thread = thread::spawn(move || {
-            while let Ok((event_kind, path)) = debounced_rx.recv() {
-                match event_kind {
-                    EventKind::Create(_) => {
-                        if let Err(e) = handler.handle_create(&path) {
-                            eprintln!("Handler create error: {}", e);
-                        }
-                    }
-                    EventKind::Modify(_) => {
-                        if let Err(e) = handler.handle_modify(&path) {
-                            eprintln!("Handler modify error: {}", e);
-                        }
-                    }
-                    EventKind::Remove(_) => {
-                        if let Err(e) = handler.handle_remove(&path) {
-                            eprintln!("Handler remove error: {}", e);
-                        }
-                    }
-                    _ => {}
-                }
+            while let Ok(event) = debounced_rx.recv() {
+                handler.handle(&event);
             }
         });
 
@@ -201,24 +224,88 @@ impl WatcherService {
         Ok(())
     }
 
-    /// Shuts down the watcher service gracefully.
+    /// Subscribes to note changes applied by the watcher.
     ///
-    /// This method stops the file system watcher and waits for all background
-    /// threads to complete their work. It ensures clean shutdown without
-    /// losing any pending file system events.
+    /// Returns a channel that receives a [`NoteEvent`] after every change
+    /// the watcher applies to the index. Any number of subscriptions may be
+    /// active; each receives every event. Subscriptions survive
+    /// [`WatcherService::shutdown`] and a later restart. Dropping the
+    /// receiver unsubscribes.
     ///
-    /// # Shutdown Process
+    /// # Examples
+    /// ```rust,no_run
+    /// use ora_core::watcher::service::WatcherService;
+    /// use ora_core::watcher::subscription::NoteEvent;
+    /// use std::time::Duration;
+    /// use std::path::PathBuf;
     ///
-    /// 1. Stops the file system watcher (prevents new events)
-    /// 2. Closes the shutdown channel (signals threads to exit)
-    /// 3. Waits for debouncer thread to finish
-    /// 4. Waits for handler thread to finish
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut watcher = WatcherService::create(
+    ///     &PathBuf::from("/path/to/notes"),
+    ///     Duration::from_millis(100)
+    /// )?;
+    /// let events = watcher.subscribe();
+    /// watcher.run()?;
     ///
-    /// # Blocking Behavior
+    /// for event in events {
+    ///     if let NoteEvent::NoteModified { path, .. } = event {
+    ///         println!("{} changed", path.display());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<NoteEvent> {
+        self.handler.subscribers().subscribe()
+    }
+
+    /// Registers a callback invoked with every [`NoteEvent`].
     ///
-    /// This method blocks until all background threads have terminated.
-    /// The shutdown typically completes quickly, but may take longer if
-    /// there are many pending file system events.
+    /// The callback runs on the watcher's handler thread, after the index
+    /// has been updated, and should return quickly.
+    pub fn on_event<F>(&self, callback: F)
+    where
+        F: Fn(&NoteEvent) + Send + Sync + 'static,
+    {
+        self.handler.subscribers().on_event(callback);
+    }
+
+    /// Returns the subscribers notified by this service.
+    pub fn subscribers(&self) -> &Subscribers {
+        self.handler.subscribers()
+    }
+
+    /// Returns the shelf configuration the service was created with.
+    pub fn config(&self) -> &ShelfConfig {
+        self.handler.index().config()
+    }
+
+    /// Returns the index maintained by this service.
+    pub(crate) fn index(&self) -> &index::Index {
+        self.handler.index()
+    }
+
+    /// Returns the handler applying changes to the index.
+    pub(crate) fn handler(&self) -> &FileIndexHandler {
+        &self.handler
+    }
+
+    /// Returns `true` if the service has been started and not shut down.
+    pub fn is_running(&self) -> bool {
+        self.debouncer_thread.is_some() || self.handler_thread.is_some()
+    }
+
+    /// Shuts down the watcher service gracefully.
+    ///
+    /// Pending debounced events are flushed to the index before the
+    /// background threads exit. Equivalent to
+    /// [`WatcherService::shutdown_with`] with [`ShutdownMode::Flush`] and
+    /// [`DEFAULT_SHUTDOWN_TIMEOUT`].
+    ///
+    /// Calling this on a service that is not running does nothing.
+    ///
+    /// # Errors
+    /// Returns [`OraError::Other`] if a background thread does not stop in time
     ///
     /// # Examples
     /// ```rust,no_run
@@ -236,22 +323,52 @@ impl WatcherService {
     ///
     /// // Later, when shutting down:
     /// watcher.shutdown()?;
+    ///
+    /// // The service can be started again.
+    /// watcher.run()?;
     /// # Ok(())
     /// # }
     /// ```
     pub fn shutdown(&mut self) -> Result<(), OraError> {
-        self.watcher.take();
-        self.shutdown_tx.take();
+        self.shutdown_with(ShutdownMode::Flush, DEFAULT_SHUTDOWN_TIMEOUT)
+    }
 
-        if let Some(handle) = self.debouncer_thread.take() {
-            let _ = handle.join();
-        }
+    /// Shuts down the watcher service, choosing what happens to pending events.
+    ///
+    /// # Shutdown Process
+    ///
+    /// 1. Stops the file system watcher (prevents new events)
+    /// 2. Stops the rename timer, which reports a still unpaired rename
+    ///    half as a removal, and waits for its thread to finish
+    /// 3. Sends a shutdown message to the debouncer, which flushes or
+    ///    discards its pending events according to `mode` and exits
+    /// 4. Waits for the debouncer thread to finish
+    /// 5. Waits for the handler thread to finish indexing the events it
+    ///    already received
+    ///
+    /// # Arguments
+    /// * `mode` - Whether to flush or discard pending debounced events
+    /// * `timeout` - How long to wait for each background thread
+    ///
+    /// # Errors
+    /// Returns [`OraError::Other`] if a background thread does not stop
+    /// within `timeout`. That thread is detached; the service is still
+    /// considered stopped and may be restarted.
+    pub fn shutdown_with(&mut self, mode: ShutdownMode, timeout: Duration) -> Result<(), OraError> {
+        self.handler.detach();
+        let timer = join_with_timeout(
+            self.rename_timer.take().map(PairingTimer::stop),
+            "rename timer",
+            timeout,
+        );
 
-        if let Some(handle) = self.handler_thread.take() {
-            let _ = handle.join();
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.send(DebouncerMessage::Shutdown(mode));
         }
 
-        Ok(())
+        let debouncer = join_with_timeout(self.debouncer_thread.take(), "debouncer", timeout);
+        let handler = join_with_timeout(self.handler_thread.take(), "handler", timeout);
+        timer.and(debouncer).and(handler)
     }
 
     /// Gets access to the underlying search index.
@@ -274,3 +391,36 @@ impl WatcherService {
         self.handler.get_index()
     }
 }
+
+impl Drop for WatcherService {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// Waits for a background thread to finish, giving up after `timeout`.
+///
+/// # Errors
+/// Returns [`OraError::Other`] if the thread is still running after `timeout`
+fn join_with_timeout(
+    handle: Option<JoinHandle<()>>,
+    name: &str,
+    timeout: Duration,
+) -> Result<(), OraError> {
+    let Some(handle) = handle else {
+        return Ok(());
+    };
+
+    let deadline = Instant::now() + timeout;
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return Err(OraError::Other(format!(
+                "watcher {name} thread did not stop within {timeout:?}"
+            )));
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let _ = handle.join();
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//! Note change notifications for watcher subscribers.
+//!
+//! The [`WatcherService`](crate::watcher::service::WatcherService) reports
+//! every change it applies to the index as a [`NoteEvent`]. Events are
+//! published only after the index has been updated, so a subscriber that
+//! queries the index in response sees the new state.
+//!
+//! # Delivery
+//!
+//! Any number of subscribers may be registered, either as channels
+//! ([`Subscribers::subscribe`]) or as callbacks ([`Subscribers::on_event`]).
+//! Every subscriber receives every event, in the order the changes were
+//! applied. Channel subscribers whose receiver has been dropped are removed
+//! automatically. Callbacks run on the watcher's handler thread and should
+//! return quickly.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, Sender, channel},
+    },
+};
+
+/// A change to a note, published after the index has been updated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteEvent {
+    /// A note was added to the index.
+    NoteCreated { path: PathBuf, id: String },
+
+    /// The content of an indexed note changed.
+    NoteModified { path: PathBuf, id: String },
+
+    /// A note was moved; its index entry and stable ID were kept.
+    NoteRenamed {
+        from: PathBuf,
+        to: PathBuf,
+        id: String,
+    },
+
+    /// A note was removed from the index.
+    NoteRemoved { path: PathBuf, id: String },
+
+    /// A change at `path` could not be applied to the index.
+    IndexError { path: PathBuf, message: String },
+}
+
+impl NoteEvent {
+    /// Returns the path the event refers to: the new path for renames.
+    pub fn path(&self) -> &Path {
+        match self {
+            NoteEvent::NoteCreated { path, .. }
+            | NoteEvent::NoteModified { path, .. }
+            | NoteEvent::NoteRemoved { path, .. }
+            | NoteEvent::IndexError { path, .. } => path,
+            NoteEvent::NoteRenamed { to, .. } => to,
+        }
+    }
+}
+
+/// A callback registered with [`Subscribers::on_event`].
+type Callback = Arc<dyn Fn(&NoteEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct Registry {
+    channels: Vec<Sender<NoteEvent>>,
+    callbacks: Vec<Callback>,
+}
+
+/// A shared list of [`NoteEvent`] subscribers.
+///
+/// Cloning a `Subscribers` yields a handle to the same list, so subscribers
+/// added through any clone receive events published through any other.
+#[derive(Clone, Default)]
+pub struct Subscribers {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Subscribers {
+    /// Creates an empty subscriber list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new channel subscriber.
+    ///
+    /// # Returns
+    /// The receiving end of a channel that gets every future event
+    pub fn subscribe(&self) -> Receiver<NoteEvent> {
+        let (tx, rx) = channel();
+        self.registry.lock().unwrap().channels.push(tx);
+        rx
+    }
+
+    /// Registers a callback that is invoked with every future event.
+    ///
+    /// The callback runs without the subscriber list locked, so it may
+    /// register further subscribers or publish events itself.
+    pub fn on_event<F>(&self, callback: F)
+    where
+        F: Fn(&NoteEvent) + Send + Sync + 'static,
+    {
+        self.registry
+            .lock()
+            .unwrap()
+            .callbacks
+            .push(Arc::new(callback));
+    }
+
+    /// Returns the number of registered subscribers.
+    pub fn len(&self) -> usize {
+        let registry = self.registry.lock().unwrap();
+        registry.channels.len() + registry.callbacks.len()
+    }
+
+    /// Returns `true` if no subscriber is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Delivers `event` to every subscriber.
+    ///
+    /// Channel subscribers whose receiver was dropped are removed.
+    pub fn publish(&self, event: NoteEvent) {
+        let callbacks = {
+            let mut registry = self.registry.lock().unwrap();
+            registry
+                .channels
+                .retain(|tx| tx.send(event.clone()).is_ok());
+            registry.callbacks.clone()
+        };
+        for callback in callbacks {
+            callback(&event);
+        }
+    }
+}
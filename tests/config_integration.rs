@@ -0,0 +1,119 @@
+use ora_core::shelf::config::{CONFIG_FILE, FilenameStyle, ShelfConfig, Tokenizer};
+use ora_core::shelf::library::Library;
+use ora_core::shelf::storage::ShelfError;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn rendered_config_parses_back() {
+    let config = ShelfConfig {
+        ignore: vec!["drafts/".into(), "*.tmp.md".into()],
+        template: Some("# {{title}}\n\n\"quoted\"\ttab\\".into()),
+        filename_style: FilenameStyle::Slug,
+        tokenizer: Tokenizer::Trigram,
+        trigram_index: true,
+        history: true,
+        history_limit: Some(20),
+    };
+
+    assert_eq!(ShelfConfig::parse(&config.render()).unwrap(), config);
+    assert_eq!(
+        ShelfConfig::parse(&ShelfConfig::default().render()).unwrap(),
+        ShelfConfig::default()
+    );
+}
+
+#[test]
+fn hand_written_config_is_parsed() {
+    let text = r#"
+# comment
+ignore = [
+    "archive/**",  # old notes
+    'raw\path',
+]
+template = """
+# {{title}}
+Created from the template.
+"""
+filename_style = "slug"   # trailing comment
+unknown_key = true
+"tokenizer" = "porter"
+trigram_index = true
+history = true
+history_limit = 0
+"#;
+
+    let config = ShelfConfig::parse(text).unwrap();
+    assert_eq!(config.ignore, vec!["archive/**", "raw\\path"]);
+    assert_eq!(
+        config.render_template("Plan").as_deref(),
+        Some("# Plan\nCreated from the template.\n")
+    );
+    assert_eq!(config.filename_style, FilenameStyle::Slug);
+    assert_eq!(config.tokenizer, Tokenizer::Porter);
+    assert!(config.trigram_index);
+    assert!(config.history);
+    assert_eq!(config.history_limit, None);
+}
+
+#[test]
+fn invalid_config_reports_line() {
+    let cases = [
+        ("ignore = \"drafts\"", 1),
+        ("\n\ntokenizer = \"snowball\"", 3),
+        ("template = \"open", 1),
+        ("[section]\nkey = 1", 1),
+        ("filename_style = \"slug\"\nfilename_style = \"title\"", 2),
+        ("ignore = [\"a\"\n\"b\"]", 2),
+        ("history = \"yes\"", 1),
+        ("\nhistory_limit = -1", 2),
+    ];
+
+    for (text, expected) in cases {
+        match ShelfConfig::parse(text) {
+            Err(ShelfError::InvalidConfig { line, .. }) => assert_eq!(line, expected, "{text}"),
+            other => panic!("expected an error for {text:?}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn filename_styles_derive_stems() {
+    assert_eq!(FilenameStyle::Title.file_stem("  My Note "), "My Note");
+    assert_eq!(FilenameStyle::Title.file_stem(""), "Untitled");
+    assert_eq!(
+        FilenameStyle::Slug.file_stem("Ünïcode & Rust: 2024!"),
+        "ünïcode-rust-2024"
+    );
+    assert_eq!(FilenameStyle::Slug.file_stem("!!!"), "untitled");
+}
+
+#[test]
+fn shelves_write_and_load_their_config() {
+    let tmpdir = TempDir::new().unwrap();
+    let library = Library::new(tmpdir.path());
+
+    let shelf = library.create_shelf("configured").unwrap();
+    assert_eq!(shelf.config, ShelfConfig::default());
+    assert!(shelf.root.join(CONFIG_FILE).is_file());
+
+    let config = ShelfConfig {
+        tokenizer: Tokenizer::Porter,
+        ..ShelfConfig::default()
+    };
+    config.save(&shelf.root).unwrap();
+    assert_eq!(library.open_shelf("configured").unwrap().config, config);
+
+    // Shelves without a config file use the defaults.
+    fs::create_dir(tmpdir.path().join("bare")).unwrap();
+    assert_eq!(
+        library.open_shelf("bare").unwrap().config,
+        ShelfConfig::default()
+    );
+
+    fs::write(shelf.root.join(CONFIG_FILE), "tokenizer = 1").unwrap();
+    assert!(matches!(
+        library.open_shelf("configured"),
+        Err(ShelfError::InvalidConfig { line: 1, .. })
+    ));
+}
@@ -0,0 +1,85 @@
+use ora_core::watcher::debounce::Debouncer;
+use ora_core::watcher::event::FsEvent;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn modify(path: &str) -> FsEvent {
+    FsEvent::Modify(PathBuf::from(path))
+}
+
+#[test]
+fn events_for_the_same_path_are_coalesced() {
+    let (tx, rx) = channel();
+    let mut debouncer = Debouncer::new(tx, Duration::from_millis(100));
+    let start = Instant::now();
+
+    debouncer.push(modify("a.md"), start);
+    debouncer.push(modify("b.md"), start + Duration::from_millis(10));
+    debouncer.push(
+        FsEvent::Remove("a.md".into()),
+        start + Duration::from_millis(50),
+    );
+    assert_eq!(debouncer.pending_len(), 2);
+
+    // b.md is due first, a.md was pushed back by its second event.
+    assert_eq!(debouncer.expire(start + Duration::from_millis(110)), 1);
+    assert_eq!(rx.try_recv().unwrap(), modify("b.md"));
+    assert!(rx.try_recv().is_err());
+
+    assert_eq!(debouncer.expire(start + Duration::from_millis(150)), 1);
+    assert_eq!(rx.try_recv().unwrap(), FsEvent::Remove("a.md".into()));
+    assert_eq!(debouncer.pending_len(), 0);
+    assert_eq!(debouncer.next_deadline(), None);
+}
+
+#[test]
+fn continuously_written_file_is_forwarded_after_max_wait() {
+    let (tx, rx) = channel();
+    let mut debouncer =
+        Debouncer::new(tx, Duration::from_millis(100)).with_max_wait(Duration::from_millis(300));
+    let start = Instant::now();
+
+    let mut now = start;
+    while now < start + Duration::from_millis(290) {
+        debouncer.push(modify("busy.md"), now);
+        assert_eq!(debouncer.expire(now), 0);
+        now += Duration::from_millis(50);
+    }
+
+    assert_eq!(
+        debouncer.next_deadline(),
+        Some(start + Duration::from_millis(300))
+    );
+    assert_eq!(debouncer.expire(start + Duration::from_millis(300)), 1);
+    assert_eq!(rx.try_recv().unwrap(), modify("busy.md"));
+}
+
+#[test]
+fn run_forwards_events_and_flushes_on_close() {
+    let (raw_tx, raw_rx) = channel();
+    let (out_tx, out_rx) = channel();
+    let mut debouncer = Debouncer::new(out_tx, Duration::from_millis(20));
+    let worker = thread::spawn(move || debouncer.run(raw_rx));
+
+    for i in 0..1000 {
+        raw_tx
+            .send(modify(&format!("note {}.md", i % 10)).into())
+            .unwrap();
+    }
+    let first = out_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(matches!(first, FsEvent::Modify(_)));
+
+    raw_tx.send(modify("last.md").into()).unwrap();
+    drop(raw_tx);
+    worker.join().unwrap();
+
+    let mut events = vec![first];
+    events.extend(out_rx.try_iter());
+    assert_eq!(events.last(), Some(&modify("last.md")));
+
+    let paths: HashSet<_> = events.iter().map(|e| e.path().to_path_buf()).collect();
+    assert_eq!(paths.len(), 11);
+}
@@ -1,4 +1,7 @@
 use ora_core::domain::{LocalNote, NoteError};
+use ora_core::front_matter::MetaValue;
+use std::fs;
+use std::time::{Duration, SystemTime};
 use tempfile::TempDir;
 
 #[test]
@@ -6,7 +9,7 @@ fn create_and_reload_note() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let note = LocalNote::create("Test Note".into(), "Hello, world".into(), dir)?;
+    let note = LocalNote::create("Test Note", "Hello, world", dir)?;
     assert!(note.path.exists());
 
     assert_eq!(note.content, "Hello, world");
@@ -23,9 +26,9 @@ fn update_content_and_save() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let note = LocalNote::create("Content Note".into(), "Original".into(), dir)?;
+    let note = LocalNote::create("Content Note", "Original", dir)?;
 
-    let updated = note.with_content("Updated".into());
+    let mut updated = note.with_content("Updated");
     updated.save()?;
 
     let reloaded = updated.reload()?;
@@ -39,7 +42,7 @@ fn save_as_renames_note() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let mut note = LocalNote::create("Title Note".into(), "data".into(), dir)?;
+    let mut note = LocalNote::create("Title Note", "data", dir)?;
     let old_path = note.path.clone();
 
     note.save_as("Renamed")?;
@@ -58,11 +61,13 @@ fn delete_removes_file() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let note = LocalNote::create("Delete Note".into(), "to be deleted".into(), dir)?;
+    let note = LocalNote::create("Delete Note", "to be deleted", dir)?;
     assert!(note.path.exists());
 
-    note.delete()?;
+    let trashed = note.delete()?;
     assert!(!note.path.exists());
+    assert_eq!(trashed.original_path, note.path);
+    assert!(trashed.path.exists());
 
     Ok(())
 }
@@ -72,7 +77,7 @@ fn special_characters_in_title() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let note = LocalNote::create("Note: Special Characters!".into(), "oops".into(), dir)?;
+    let note = LocalNote::create("Note: Special Characters!", "oops", dir)?;
     assert_eq!(note.title, "Note: Special Characters!");
     assert!(note.path.ends_with("Note: Special Characters!.md"));
 
@@ -84,7 +89,7 @@ fn empty_title_defaults_to_untitled() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let untitled = LocalNote::create("    ".into(), "empty".into(), dir)?;
+    let untitled = LocalNote::create("    ", "empty", dir)?;
     assert_eq!(untitled.title, "Untitled");
     assert!(untitled.path.ends_with("Untitled.md"));
 
@@ -96,17 +101,339 @@ fn duplicate_titles_get_number_suffix() -> Result<(), NoteError> {
     let tmpdir = TempDir::new().unwrap();
     let dir = tmpdir.path();
 
-    let note1 = LocalNote::create("Same Title".into(), "first".into(), dir)?;
+    let note1 = LocalNote::create("Same Title", "first", dir)?;
     assert_eq!(note1.title, "Same Title");
     assert!(note1.path.ends_with("Same Title.md"));
 
-    let note2 = LocalNote::create("Same Title".into(), "second".into(), dir)?;
+    let note2 = LocalNote::create("Same Title", "second", dir)?;
     assert_eq!(note2.title, "Same Title");
     assert!(note2.path.ends_with("Same Title 1.md"));
 
-    let note3 = LocalNote::create("Same Title".into(), "third".into(), dir)?;
+    let note3 = LocalNote::create("Same Title", "third", dir)?;
     assert_eq!(note3.title, "Same Title");
     assert!(note3.path.ends_with("Same Title 2.md"));
 
     Ok(())
 }
+
+const FRONT_MATTER_NOTE: &str = "---\n\
+title: Ora  # display title\n\
+tags: [rust, \"notes, misc\"]\n\
+aliases:\n  - Ora Core\n  - ora\n\
+created: 2024-01-01\n\
+---\n\
+# Heading\n\nBody text.\n";
+
+#[test]
+fn front_matter_is_parsed_into_typed_values() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new().unwrap();
+    let note = LocalNote::create("Meta", FRONT_MATTER_NOTE, tmpdir.path())?;
+
+    let front_matter = note.front_matter();
+    assert_eq!(
+        front_matter.get("title"),
+        Some(&MetaValue::Text("Ora".into()))
+    );
+    assert_eq!(
+        front_matter.get("tags"),
+        Some(&MetaValue::List(vec!["rust".into(), "notes, misc".into()]))
+    );
+    assert_eq!(
+        front_matter.get("aliases"),
+        Some(&MetaValue::List(vec!["Ora Core".into(), "ora".into()]))
+    );
+    assert_eq!(
+        front_matter.get("created").and_then(MetaValue::as_text),
+        Some("2024-01-01")
+    );
+    assert_eq!(note.body(), "# Heading\n\nBody text.\n");
+
+    Ok(())
+}
+
+#[test]
+fn editing_body_keeps_front_matter_bytes() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new().unwrap();
+    let note = LocalNote::create("Body Edit", FRONT_MATTER_NOTE, tmpdir.path())?;
+
+    let mut edited = note.with_body("New body.\n");
+    edited.save()?;
+
+    let reloaded = edited.reload()?;
+    let header_len = FRONT_MATTER_NOTE.len() - note.body().len();
+    assert_eq!(
+        reloaded.content,
+        format!("{}New body.\n", &FRONT_MATTER_NOTE[..header_len])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn editing_one_key_only_rewrites_that_key() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new().unwrap();
+    let note = LocalNote::create("Key Edit", FRONT_MATTER_NOTE, tmpdir.path())?;
+
+    let edited = note.with_meta("created", "2025-02-02");
+    assert_eq!(
+        edited.content,
+        FRONT_MATTER_NOTE.replace("created: 2024-01-01", "created: 2025-02-02")
+    );
+
+    let unchanged = note.with_front_matter(&note.front_matter());
+    assert_eq!(unchanged.content, FRONT_MATTER_NOTE);
+
+    let block_list = note.with_meta("aliases", vec!["Ora".to_string()]);
+    assert!(block_list.content.contains("aliases:\n  - Ora\ncreated"));
+
+    Ok(())
+}
+
+#[test]
+fn setting_meta_on_plain_note_adds_header() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new().unwrap();
+    let note = LocalNote::create("Plain", "Just text", tmpdir.path())?;
+
+    assert!(!note.front_matter().is_present());
+    assert_eq!(note.body(), "Just text");
+
+    let tagged = note.with_meta("status", "draft");
+    assert_eq!(tagged.content, "---\nstatus: draft\n---\nJust text");
+    assert_eq!(tagged.body(), "Just text");
+
+    Ok(())
+}
+
+#[test]
+fn with_stable_id_adds_id_once() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Identity", "Body", tmpdir.path())?;
+    assert_eq!(note.id(), None);
+
+    let with_id = note.with_stable_id();
+    let id = with_id.id().expect("id should be set");
+    assert!(with_id.content.ends_with("---\nBody"));
+    assert_eq!(with_id.with_stable_id().id(), Some(id));
+
+    Ok(())
+}
+
+#[test]
+fn save_detects_external_edit() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Shared", "Original", tmpdir.path())?;
+    let mut edited = note.with_content("Mine");
+    assert!(!edited.is_stale()?);
+
+    fs::write(&note.path, "Edited in Vim")?;
+    assert!(edited.is_stale()?);
+
+    match edited.save() {
+        Err(NoteError::Conflict(conflict)) => {
+            assert_eq!(conflict.path, note.path);
+            assert_eq!(conflict.ours, "Mine");
+            assert_eq!(conflict.theirs.as_deref(), Some("Edited in Vim"));
+        }
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+    assert_eq!(fs::read_to_string(&note.path)?, "Edited in Vim");
+
+    // Resolving on top of the disk version saves normally.
+    let mut merged = edited.reload()?.with_content("Edited in Vim\nMine");
+    merged.save()?;
+    assert_eq!(fs::read_to_string(&note.path)?, "Edited in Vim\nMine");
+
+    Ok(())
+}
+
+#[test]
+fn save_force_overwrites_and_refreshes_fingerprint() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Forced", "Original", tmpdir.path())?;
+    let mut edited = note.with_content("Mine");
+
+    fs::write(&note.path, "Theirs")?;
+    edited.save_force()?;
+    assert_eq!(fs::read_to_string(&note.path)?, "Mine");
+
+    // The note now matches the file it wrote, so later saves succeed.
+    assert!(!edited.is_stale()?);
+    edited.content = "Mine, again".to_string();
+    edited.save()?;
+    assert_eq!(fs::read_to_string(&note.path)?, "Mine, again");
+
+    Ok(())
+}
+
+#[test]
+fn save_as_detects_deleted_file() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let mut note = LocalNote::create("Gone", "Body", tmpdir.path())?;
+    fs::remove_file(&note.path)?;
+
+    match note.save_as("Renamed") {
+        Err(NoteError::Conflict(conflict)) => assert_eq!(conflict.theirs, None),
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+    assert!(!tmpdir.path().join("Renamed.md").exists());
+
+    note.save_as_force("Renamed")?;
+    assert!(note.path.ends_with("Renamed.md"));
+    assert_eq!(fs::read_to_string(&note.path)?, "Body");
+
+    Ok(())
+}
+
+#[test]
+fn touching_a_file_is_not_a_conflict() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Touched", "Original", tmpdir.path())?;
+    let mut edited = note.with_content("Updated");
+
+    fs::File::options()
+        .write(true)
+        .open(&note.path)?
+        .set_modified(SystemTime::now() + Duration::from_secs(3600))?;
+    assert!(!edited.is_stale()?);
+
+    edited.save()?;
+    assert_eq!(fs::read_to_string(&note.path)?, "Updated");
+
+    Ok(())
+}
+
+#[test]
+fn quoted_list_items_round_trip() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Escapes", "Body", tmpdir.path())?;
+    let items = vec![
+        r"C:\\server\share, old".to_string(),
+        r#"say "hi", then \" leave"#.to_string(),
+        r"ends in, backslash\".to_string(),
+    ];
+
+    let edited = note.with_meta("paths", items.clone());
+    assert_eq!(
+        edited.front_matter().get("paths"),
+        Some(&MetaValue::List(items))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn multi_line_values_round_trip() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Lines", "Body", tmpdir.path())?;
+
+    let edited = note
+        .with_meta("title", "line one\nline two: evil")
+        .with_meta(
+            "notes",
+            vec!["first\r\nsecond".to_string(), "plain".to_string()],
+        );
+    assert!(
+        edited
+            .content
+            .starts_with("---\ntitle: \"line one\\nline two: evil\"\n")
+    );
+
+    let front_matter = edited.front_matter();
+    assert_eq!(
+        front_matter.get("title"),
+        Some(&MetaValue::Text("line one\nline two: evil".into()))
+    );
+    assert_eq!(
+        front_matter.get("notes"),
+        Some(&MetaValue::List(vec![
+            "first\r\nsecond".into(),
+            "plain".into()
+        ]))
+    );
+    assert_eq!(front_matter.get("line two"), None);
+
+    Ok(())
+}
+
+#[test]
+fn apostrophes_inside_words_are_not_quotes() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let content = "---\ntitle: Don't panic # comment\ntags: [don't, rust]\n---\nBody";
+    let note = LocalNote::create("Apostrophes", content, tmpdir.path())?;
+
+    let front_matter = note.front_matter();
+    assert_eq!(
+        front_matter.get("title"),
+        Some(&MetaValue::Text("Don't panic".into()))
+    );
+    assert_eq!(
+        front_matter.get("tags"),
+        Some(&MetaValue::List(vec!["don't".into(), "rust".into()]))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn block_scalars_and_nested_maps_round_trip() -> Result<(), NoteError> {
+    let tmpdir = TempDir::new()?;
+    let content = "---\n\
+        summary: |\n  First line\n    indented\n\n  last\n\
+        folded: >-\n  one\n  two\n\n  three\n\
+        kept: |+\n  text\n\n\
+        meta:\n  author: Ann\n  tags: [a, b]\n\
+        inline: {a: 1}\n\
+        title: Ora\n\
+        ---\nBody";
+    let note = LocalNote::create("Blocks", content, tmpdir.path())?;
+
+    let front_matter = note.front_matter();
+    assert_eq!(
+        front_matter.get("summary"),
+        Some(&MetaValue::Text("First line\n  indented\n\nlast\n".into()))
+    );
+    assert_eq!(
+        front_matter.get("folded"),
+        Some(&MetaValue::Text("one two\nthree".into()))
+    );
+    assert_eq!(
+        front_matter.get("kept"),
+        Some(&MetaValue::Text("text\n\n".into()))
+    );
+    assert_eq!(
+        front_matter.get("meta"),
+        Some(&MetaValue::Raw("  author: Ann\n  tags: [a, b]".into()))
+    );
+    assert_eq!(
+        front_matter.get("inline"),
+        Some(&MetaValue::Raw("{a: 1}".into()))
+    );
+    assert_eq!(
+        front_matter.get("title"),
+        Some(&MetaValue::Text("Ora".into()))
+    );
+    assert_eq!(front_matter.get("author"), None);
+
+    // Parsing and rendering keeps the header byte-for-byte.
+    assert_eq!(note.with_front_matter(&front_matter).content, content);
+    let edited = note.with_meta("title", "Ora Core");
+    assert_eq!(
+        edited.content,
+        content.replace("title: Ora\n", "title: Ora Core\n")
+    );
+
+    // Rewritten values read back the same.
+    let mut rewritten = front_matter.clone();
+    for key in ["summary", "folded", "kept", "meta", "inline"] {
+        let value = match front_matter.get(key).unwrap() {
+            MetaValue::Text(text) => MetaValue::Text(format!("{text}!")),
+            MetaValue::Raw(raw) => MetaValue::Raw(raw.replace("Ann", "Bo").replace('1', "2")),
+            MetaValue::List(_) => unreachable!(),
+        };
+        rewritten.set(key, value.clone());
+        let reparsed = note.with_front_matter(&rewritten).front_matter();
+        assert_eq!(reparsed.get(key), Some(&value));
+    }
+
+    Ok(())
+}
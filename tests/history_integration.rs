@@ -0,0 +1,300 @@
+use ora_core::domain::LocalNote;
+use ora_core::error::OraError;
+use ora_core::history::{DiffLine, History, RevisionSource, diff_lines};
+use ora_core::shelf::config::ShelfConfig;
+use ora_core::shelf::manager::{RenameOptions, ShelfManager, TransferMode, TransferOptions};
+use ora_core::shelf::storage::{Shelf, ShelfError};
+use ora_core::watcher::index::Index;
+use std::fs;
+use tempfile::TempDir;
+
+fn history_shelf(tmpdir: &TempDir, limit: Option<usize>) -> Shelf {
+    let config = ShelfConfig {
+        history: true,
+        history_limit: limit,
+        ..ShelfConfig::default()
+    };
+    config.save(tmpdir.path()).unwrap();
+    Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "history".to_string(),
+        config,
+    }
+}
+
+#[test]
+fn diff_lines_finds_minimal_changes() {
+    let diff = diff_lines("one\ntwo\nthree\nfour\n", "zero\none\nthree\nfour\nfive\n");
+    assert_eq!(
+        diff,
+        vec![
+            DiffLine::Added("zero".into()),
+            DiffLine::Unchanged("one".into()),
+            DiffLine::Removed("two".into()),
+            DiffLine::Unchanged("three".into()),
+            DiffLine::Unchanged("four".into()),
+            DiffLine::Added("five".into()),
+        ]
+    );
+
+    let rendered: Vec<String> = diff.iter().map(ToString::to_string).collect();
+    assert_eq!(rendered[0], "+zero");
+    assert_eq!(rendered[2], "-two");
+    assert!(
+        diff_lines("same\n", "same\n")
+            .iter()
+            .all(|line| matches!(line, DiffLine::Unchanged(_)))
+    );
+    assert_eq!(diff_lines("", "new"), vec![DiffLine::Added("new".into())]);
+}
+
+#[test]
+fn diff_lines_handles_large_notes() {
+    let old: Vec<String> = (0..20_000).map(|i| format!("line {i}")).collect();
+    let mut new = old.clone();
+    new[5] = "changed".to_string();
+    new.remove(10_000);
+    new.insert(15_000, "inserted".to_string());
+    new.push("appended".to_string());
+
+    let diff = diff_lines(&old.join("\n"), &new.join("\n"));
+    let changes: Vec<&DiffLine> = diff
+        .iter()
+        .filter(|line| !matches!(line, DiffLine::Unchanged(_)))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            &DiffLine::Removed("line 5".into()),
+            &DiffLine::Added("changed".into()),
+            &DiffLine::Removed("line 10000".into()),
+            &DiffLine::Added("inserted".into()),
+            &DiffLine::Added("appended".into()),
+        ]
+    );
+    assert_eq!(diff.len(), 20_003);
+}
+
+#[test]
+fn history_store_records_dedupes_limits_and_follows_renames() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let history = History::open(tmpdir.path(), Some(2))?;
+    let note = tmpdir.path().join("Note.md");
+
+    let first = history
+        .record(&note, "v1", RevisionSource::Library)?
+        .unwrap();
+    assert!(
+        history
+            .record(&note, "v1", RevisionSource::External)?
+            .is_none()
+    );
+    history.record(&note, "v2", RevisionSource::External)?;
+    let third = history
+        .record(&note, "v3", RevisionSource::Library)?
+        .unwrap();
+
+    let revisions = history.revisions(&note)?;
+    let contents: Vec<_> = revisions.iter().map(|r| r.content.as_str()).collect();
+    assert_eq!(contents, vec!["v3", "v2"]);
+    assert_eq!(revisions[1].source, RevisionSource::External);
+    assert!(history.get(first.id)?.is_none());
+    assert_eq!(history.get(third.id)?.unwrap(), third);
+
+    let moved = tmpdir.path().join("dir/Note.md");
+    history.rename(&note, &moved)?;
+    assert!(history.revisions(&note)?.is_empty());
+    assert_eq!(history.revisions(&moved)?.len(), 2);
+
+    let renamed_dir = tmpdir.path().join("other/Note.md");
+    history.rename_dir(&tmpdir.path().join("dir"), &tmpdir.path().join("other"))?;
+    assert_eq!(history.revisions(&renamed_dir)?.len(), 2);
+    assert_eq!(history.forget(&renamed_dir)?, 2);
+    Ok(())
+}
+
+#[test]
+fn manager_records_diffs_and_restores_revisions() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = history_shelf(&tmpdir, None);
+    let manager = ShelfManager::with_index(&shelf, Index::new(&shelf.root)?);
+
+    manager.create_note("Plan", "step one\nstep two\n")?;
+    manager.update_note("Plan", None, Some("step one\nstep 2\nstep three\n"))?;
+    manager.rename_note("Plan", "Roadmap", &RenameOptions::default())?;
+
+    let revisions = manager.revisions("Roadmap")?;
+    assert_eq!(revisions.len(), 2);
+    assert!(manager.revisions("Plan")?.is_empty());
+    let (newest, oldest) = (revisions[0].id, revisions[1].id);
+    assert!(
+        revisions
+            .iter()
+            .all(|r| r.source == RevisionSource::Library)
+    );
+
+    assert_eq!(
+        manager.diff_revisions(oldest, newest)?,
+        vec![
+            DiffLine::Unchanged("step one".into()),
+            DiffLine::Removed("step two".into()),
+            DiffLine::Added("step 2".into()),
+            DiffLine::Added("step three".into()),
+        ]
+    );
+
+    let restored = manager.restore_revision("Roadmap", oldest)?;
+    assert_eq!(restored.content, "step one\nstep two\n");
+    let revisions = manager.revisions("Roadmap")?;
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[0].content, "step one\nstep two\n");
+
+    manager.create_note("Other", "other")?;
+    assert!(matches!(
+        manager.restore_revision("Other", oldest),
+        Err(OraError::Note(_))
+    ));
+
+    // Changes made while nothing was watching are picked up on open.
+    fs::write(shelf.root.join("Roadmap.md"), "edited elsewhere\n")?;
+    let reopened = ShelfManager::with_index(&shelf, Index::new(&shelf.root)?);
+    let latest = &reopened.revisions("Roadmap")?[0];
+    assert_eq!(latest.content, "edited elsewhere\n");
+    assert_eq!(latest.source, RevisionSource::External);
+    Ok(())
+}
+
+#[test]
+fn history_is_opt_in() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "plain".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::new(&shelf);
+    manager.create_note("Plan", "content")?;
+
+    assert!(manager.history()?.is_none());
+    assert!(matches!(
+        manager.revisions("Plan"),
+        Err(OraError::Shelf(ShelfError::HistoryDisabled(_)))
+    ));
+    assert!(!tmpdir.path().join(".history.db").exists());
+
+    // Without an index, the manager opens the history itself.
+    let tracked = TempDir::new()?;
+    let shelf = history_shelf(&tracked, Some(1));
+    let manager = ShelfManager::new(&shelf);
+    manager.create_note("Plan", "v1")?;
+    manager.update_note("Plan", None, Some("v2"))?;
+    let revisions = manager.revisions("Plan")?;
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].content, "v2");
+    Ok(())
+}
+
+#[test]
+fn local_notes_record_revisions_explicitly() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = history_shelf(&tmpdir, None);
+    fs::create_dir(shelf.root.join("folder"))?;
+    let mut note = LocalNote::create("Plan", "v1", &shelf.root.join("folder"))?;
+    let history = History::open(&shelf.root, None)?;
+
+    // Saving alone records nothing.
+    note = note.with_content("v2");
+    note.save()?;
+    assert!(history.revisions(&note.path)?.is_empty());
+
+    let from = note.path.clone();
+    assert!(note.record_revision(&history, &from)?.is_some());
+    assert!(note.record_revision(&history, &from)?.is_none());
+    note = note.with_content("v3");
+    note.save_as("Roadmap")?;
+    note.record_revision(&history, &from)?;
+
+    let revisions = history.revisions(&note.path)?;
+    let contents: Vec<&str> = revisions.iter().map(|r| r.content.as_str()).collect();
+    assert_eq!(contents, vec!["v3", "v2"]);
+    assert!(
+        revisions
+            .iter()
+            .all(|r| r.source == RevisionSource::Library)
+    );
+    assert!(history.revisions(&from)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn transfers_carry_revisions_to_the_target_shelf() -> Result<(), OraError> {
+    let source_dir = TempDir::new()?;
+    let target_dir = TempDir::new()?;
+    let source_shelf = history_shelf(&source_dir, None);
+    let target_shelf = history_shelf(&target_dir, None);
+    let source = ShelfManager::new(&source_shelf);
+    let target = ShelfManager::new(&target_shelf);
+
+    source.create_note("Plan", "v1")?;
+    source.update_note("Plan", None, Some("v2"))?;
+    source.transfer_note("Plan", &target, "", &TransferOptions::default())?;
+
+    let contents = |manager: &ShelfManager, title: &str| -> Result<Vec<String>, OraError> {
+        Ok(manager
+            .revisions(title)?
+            .into_iter()
+            .map(|r| r.content)
+            .collect())
+    };
+    assert_eq!(contents(&target, "Plan")?, vec!["v2", "v1"]);
+    assert!(
+        source
+            .history()?
+            .unwrap()
+            .revisions(&source_dir.path().join("Plan.md"))?
+            .is_empty()
+    );
+
+    // A copy leaves the source history alone and records the copied
+    // content in the target.
+    target.update_note("Plan", None, Some("v3"))?;
+    let copy = TransferOptions {
+        mode: TransferMode::Copy,
+        ..TransferOptions::default()
+    };
+    target.transfer_note("Plan", &source, "archive", &copy)?;
+    assert_eq!(contents(&source, "archive/Plan")?, vec!["v3", "v2", "v1"]);
+    assert_eq!(contents(&target, "Plan")?, vec!["v3", "v2", "v1"]);
+
+    // A target with history records the transfer even if the source has none.
+    let plain_dir = TempDir::new()?;
+    let plain_shelf = Shelf {
+        root: plain_dir.path().to_path_buf(),
+        name: "plain".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let plain = ShelfManager::new(&plain_shelf);
+    plain.create_note("Idea", "only version")?;
+    plain.transfer_note("Idea", &target, "", &TransferOptions::default())?;
+    let revisions = target.revisions("Idea")?;
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].source, RevisionSource::Library);
+    Ok(())
+}
+
+#[test]
+fn history_failures_after_a_rename_are_reported() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = history_shelf(&tmpdir, None);
+    LocalNote::create("Plan", "v1", tmpdir.path())?;
+    // A directory in place of the database makes opening the history fail.
+    fs::create_dir(tmpdir.path().join(".history.db"))?;
+    let manager = ShelfManager::new(&shelf);
+
+    let report = manager.rename_note("Plan", "Roadmap", &RenameOptions::default())?;
+
+    assert_eq!(report.sync_errors.len(), 1);
+    assert!(!tmpdir.path().join("Plan.md").exists());
+    assert_eq!(fs::read_to_string(tmpdir.path().join("Roadmap.md"))?, "v1");
+    Ok(())
+}
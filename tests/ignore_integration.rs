@@ -0,0 +1,117 @@
+use ora_core::ignore::{IGNORE_FILE, IgnoreRules};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn rules(patterns: &[&str]) -> IgnoreRules {
+    let mut rules = IgnoreRules::new();
+    rules.add_patterns(Path::new(""), patterns);
+    rules
+}
+
+fn ignored(rules: &IgnoreRules, path: &str) -> bool {
+    rules.is_ignored(Path::new(path), false)
+}
+
+#[test]
+fn unanchored_patterns_match_at_any_depth() {
+    let rules = rules(&["*.tmp.md", "scratch.md"]);
+
+    assert!(ignored(&rules, "a.tmp.md"));
+    assert!(ignored(&rules, "deep/down/b.tmp.md"));
+    assert!(ignored(&rules, "notes/scratch.md"));
+    assert!(!ignored(&rules, "a.md"));
+    assert!(!ignored(&rules, "scratch.md.bak"));
+}
+
+#[test]
+fn anchored_and_directory_patterns() {
+    let rules = rules(&[
+        "/inbox.md",
+        "drafts/",
+        "archive/**",
+        "a/**/z.md",
+        "journal/202?-*.md",
+    ]);
+
+    assert!(ignored(&rules, "inbox.md"));
+    assert!(!ignored(&rules, "sub/inbox.md"));
+
+    assert!(ignored(&rules, "drafts/idea.md"));
+    assert!(ignored(&rules, "projects/drafts/idea.md"));
+    assert!(rules.is_ignored(Path::new("drafts"), true));
+    assert!(!ignored(&rules, "drafts"), "a file named drafts is kept");
+
+    assert!(ignored(&rules, "archive/2020/old.md"));
+    assert!(!rules.is_ignored(Path::new("archive"), true));
+
+    assert!(ignored(&rules, "a/z.md"));
+    assert!(ignored(&rules, "a/b/c/z.md"));
+    assert!(!ignored(&rules, "b/z.md"));
+
+    assert!(ignored(&rules, "journal/2024-01.md"));
+    assert!(!ignored(&rules, "journal/sub/2024-01.md"));
+}
+
+#[test]
+fn negation_and_character_classes() {
+    let rules = rules(&[
+        "# comment",
+        "",
+        "*.md",
+        "!keep.md",
+        "[0-9]*.txt",
+        "\\#hash.md",
+    ]);
+
+    assert!(ignored(&rules, "note.md"));
+    assert!(!ignored(&rules, "keep.md"));
+    assert!(ignored(&rules, "1st.txt"));
+    assert!(!ignored(&rules, "first.txt"));
+    assert!(ignored(&rules, "#hash.md"));
+
+    // Files inside an ignored directory cannot be re-included.
+    let rules = self::rules(&["private/", "!private/public.md"]);
+    assert!(ignored(&rules, "private/public.md"));
+}
+
+#[test]
+fn empty_rules_ignore_nothing() {
+    let rules = IgnoreRules::new();
+    assert!(rules.is_empty());
+    assert!(!ignored(&rules, "anything.md"));
+}
+
+#[test]
+fn ignore_files_are_loaded_per_directory() {
+    let tmpdir = TempDir::new().unwrap();
+    let root = tmpdir.path();
+    fs::create_dir_all(root.join("projects/web/node_modules")).unwrap();
+    fs::create_dir_all(root.join("archive")).unwrap();
+    fs::write(root.join(IGNORE_FILE), "archive/\n*.log.md\n").unwrap();
+    fs::write(
+        root.join("projects").join(IGNORE_FILE),
+        "node_modules/\n/local.md\n!keep.log.md\n",
+    )
+    .unwrap();
+    // Never read: its directory is ignored.
+    fs::write(root.join("archive").join(IGNORE_FILE), "!*").unwrap();
+
+    let rules = IgnoreRules::load(root, &["from-config.md"]).unwrap();
+
+    assert!(ignored(&rules, "from-config.md"));
+    assert!(ignored(&rules, "archive/old.md"));
+    assert!(ignored(&rules, "debug.log.md"));
+    assert!(ignored(&rules, "projects/web/node_modules/pkg/readme.md"));
+    assert!(ignored(&rules, "projects/local.md"));
+    assert!(
+        !ignored(&rules, "projects/web/local.md"),
+        "anchored to its dir"
+    );
+    assert!(!ignored(&rules, "projects/keep.log.md"), "deeper files win");
+    assert!(ignored(&rules, "keep.log.md"));
+    assert!(
+        !ignored(&rules, "node_modules/readme.md"),
+        "scoped to projects/"
+    );
+}
@@ -0,0 +1,408 @@
+use ora_core::domain::LocalNote;
+use ora_core::error::OraError;
+use ora_core::front_matter::MetaValue;
+use ora_core::search::Query;
+use ora_core::shelf::config::{ShelfConfig, Tokenizer};
+use ora_core::watcher::index::Index;
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+#[test]
+fn front_matter_is_stored_apart_from_body() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create(
+        "Indexed",
+        "---\naliases: [zephyr]\n---\nThe body mentions rust.\n",
+        tmpdir.path(),
+    )?;
+
+    let index = Index::new(tmpdir.path())?;
+    let indexed = index
+        .get_by_path(&note.path)?
+        .expect("note should be indexed");
+
+    assert_eq!(indexed.content, "The body mentions rust.\n");
+    assert_eq!(
+        indexed.front_matter.get("aliases"),
+        Some(&MetaValue::List(vec!["zephyr".into()]))
+    );
+
+    let query = Query::new(&index);
+    assert_eq!(query.search("rust")?.len(), 1);
+    assert!(
+        query.search("zephyr")?.is_empty(),
+        "front matter should not be full-text indexed"
+    );
+
+    let by_alias = index.find_by_metadata("aliases", "zephyr")?;
+    assert_eq!(by_alias.len(), 1);
+    assert_eq!(by_alias[0].path, note.path);
+
+    Ok(())
+}
+
+#[test]
+fn reindexing_replaces_metadata_rows() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let note = LocalNote::create("Status", "---\nstatus: draft\n---\nText", tmpdir.path())?;
+
+    let index = Index::new(tmpdir.path())?;
+    assert_eq!(index.find_by_metadata("status", "draft")?.len(), 1);
+
+    let mut published = note.with_meta("status", "published");
+    published.save()?;
+    index.index_note(&published)?;
+
+    assert!(index.find_by_metadata("status", "draft")?.is_empty());
+    assert_eq!(index.find_by_metadata("status", "published")?.len(), 1);
+
+    index.remove_note(&published)?;
+    assert!(index.find_by_metadata("status", "published")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn links_backlinks_unresolved_and_orphans() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    std::fs::create_dir(dir.join("sub"))?;
+
+    let hub = LocalNote::create(
+        "Hub",
+        "Links to [[Target]], [[target|case]], [[Missing]] and [file](sub/Deep.md).",
+        dir,
+    )?;
+    let target = LocalNote::create("Target", "Back to [[Hub]] and [[Target]] itself.", dir)?;
+    let deep = LocalNote::create("Deep", "No links here.", &dir.join("sub"))?;
+    let lonely = LocalNote::create("Lonely", "Nobody links to me.", dir)?;
+
+    let index = Index::new(dir)?;
+
+    let outgoing = index.outgoing_links(&hub.path)?;
+    assert_eq!(outgoing.len(), 4);
+    assert_eq!(outgoing[0].resolved.as_deref(), Some(target.path.as_path()));
+    assert_eq!(outgoing[1].resolved.as_deref(), Some(target.path.as_path()));
+    assert_eq!(outgoing[1].alias.as_deref(), Some("case"));
+    assert_eq!(outgoing[2].resolved, None);
+    assert_eq!(outgoing[3].resolved.as_deref(), Some(deep.path.as_path()));
+
+    let backlinks = index.backlinks(&target.path)?;
+    assert_eq!(backlinks.len(), 2);
+    assert!(backlinks.iter().all(|l| l.source == hub.path));
+    assert_eq!(index.backlinks(&deep.path)?.len(), 1);
+
+    let unresolved = index.unresolved_links()?;
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].target, "Missing");
+
+    let orphans: Vec<_> = index.orphans()?.into_iter().map(|n| n.path).collect();
+    assert_eq!(orphans, vec![lonely.path.clone()]);
+
+    // Creating the missing note resolves the dangling link.
+    let missing = LocalNote::create("Missing", "Now I exist.", dir)?;
+    index.index_note(&missing)?;
+    assert!(index.unresolved_links()?.is_empty());
+
+    // Removing a note drops its outgoing links.
+    index.remove_note(&hub)?;
+    assert!(index.backlinks(&target.path)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn wikilinks_resolve_by_shelf_relative_path() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    std::fs::create_dir_all(dir.join("projects").join("ora"))?;
+
+    let deep = LocalNote::create("Plan", "Deep.", &dir.join("projects").join("ora"))?;
+    let hub = LocalNote::create(
+        "Hub",
+        "[[ora/Plan]] [[projects/ora/Plan]] [[PLAN]] [[Plan.md]] [[other/Plan]] [[ORA/Plan]]",
+        dir,
+    )?;
+
+    let index = Index::new(dir)?;
+    let resolved: Vec<_> = index
+        .outgoing_links(&hub.path)?
+        .into_iter()
+        .map(|link| link.resolved)
+        .collect();
+    let found = Some(deep.path.clone());
+    assert_eq!(
+        resolved,
+        [
+            found.clone(),
+            found.clone(),
+            found.clone(),
+            found,
+            None,
+            None
+        ]
+    );
+    assert_eq!(index.backlinks(&deep.path)?.len(), 4);
+    assert_eq!(index.unresolved_links()?.len(), 2);
+    assert_eq!(
+        index
+            .orphans()?
+            .into_iter()
+            .map(|n| n.path)
+            .collect::<Vec<_>>(),
+        std::slice::from_ref(&hub.path)
+    );
+
+    // Keys written by an older version are filled in by the migration.
+    {
+        let conn = index.conn.lock().unwrap();
+        conn.execute_batch(
+            "UPDATE links SET target_key = NULL;
+             DROP TABLE note_keys;
+             PRAGMA user_version = 6;",
+        )?;
+    }
+    drop(index);
+    let index = Index::new(dir)?;
+    assert_eq!(index.backlinks(&deep.path)?.len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn stable_ids_survive_edits_and_renames() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let plain = LocalNote::create("Plain", "No header.", dir)?;
+    let tagged = LocalNote::create("Tagged", "---\nid: note-42\n---\nBody", dir)?;
+
+    let index = Index::new(dir)?;
+    let plain_id = index.get_by_path(&plain.path)?.unwrap().id;
+    assert!(!plain_id.is_empty());
+    assert_eq!(index.get_by_path(&tagged.path)?.unwrap().id, "note-42");
+
+    // Editing keeps the generated ID.
+    let mut edited = plain.with_content("Edited.");
+    edited.save()?;
+    index.index_note(&edited)?;
+    assert_eq!(index.get_by_path(&plain.path)?.unwrap().id, plain_id);
+
+    // Renaming through the index keeps it as well.
+    let mut renamed = edited.clone();
+    renamed.save_as("Renamed")?;
+    assert!(index.rename_note(&plain.path, &renamed)?);
+    let by_id = index.get_by_id(&plain_id)?.expect("id should resolve");
+    assert_eq!(by_id.path, renamed.path);
+    assert!(index.get_by_path(&plain.path)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn front_matter_id_survives_external_rename_but_not_copy() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let note = LocalNote::create("Original", "---\nid: abc\n---\nBody", dir)?;
+    let index = Index::new(dir)?;
+
+    // An editor renames the file: the old path vanishes, a new one appears.
+    let moved = dir.join("Moved.md");
+    std::fs::rename(&note.path, &moved)?;
+    index.index_note(&LocalNote::open(&moved)?)?;
+
+    assert_eq!(index.get_by_id("abc")?.unwrap().path, moved);
+    assert!(index.get_by_path(&note.path)?.is_none());
+
+    // A copy carries the same header but must not steal the ID.
+    let copy = dir.join("Copy.md");
+    std::fs::copy(&moved, &copy)?;
+    index.index_note(&LocalNote::open(&copy)?)?;
+
+    assert_eq!(index.get_by_id("abc")?.unwrap().path, moved);
+    assert_ne!(index.get_by_path(&copy)?.unwrap().id, "abc");
+
+    Ok(())
+}
+
+#[test]
+fn notes_renamed_while_closed_keep_their_id() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let note = LocalNote::create("Original", "No header here.", dir)?;
+    let index = Index::new(dir)?;
+    let id = index.get_by_path(&note.path)?.unwrap().id;
+    drop(index);
+
+    // Renamed by another program: same content at a new path.
+    let moved = dir.join("Moved.md");
+    fs::rename(&note.path, &moved)?;
+    let (index, report) = Index::new_with_report(dir)?;
+    assert_eq!(report.added, vec![moved.clone()]);
+    assert!(report.removed.is_empty());
+    assert_eq!(index.get_by_id(&id)?.unwrap().path, moved);
+
+    // A copy of a note that still exists gets its own ID.
+    let copy = dir.join("Copy.md");
+    fs::copy(&moved, &copy)?;
+    index.index_note(&LocalNote::open(&copy)?)?;
+    assert_eq!(index.get_by_id(&id)?.unwrap().path, moved);
+    assert_ne!(index.get_by_path(&copy)?.unwrap().id, id);
+
+    Ok(())
+}
+
+#[test]
+fn separate_notes_with_the_same_content_get_their_own_ids() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let body = "## Agenda\n\n## Notes\n";
+    let deleted = LocalNote::create("Monday", body, dir)?;
+    let index = Index::new(dir)?;
+    let deleted_id = index.get_by_path(&deleted.path)?.unwrap().id;
+
+    // Indexing a note directly never takes over a vanished entry.
+    fs::remove_file(&deleted.path)?;
+    let direct = LocalNote::create("Direct", body, dir)?;
+    index.index_note(&direct)?;
+    let direct_id = index.get_by_path(&direct.path)?.unwrap().id;
+    assert_ne!(direct_id, deleted_id);
+    assert!(index.get_by_id(&deleted_id)?.is_some());
+    fs::remove_file(&direct.path)?;
+    drop(index);
+
+    // Two new notes with the content of two vanished ones: no clear move.
+    let tuesday = LocalNote::create("Tuesday", body, dir)?;
+    let wednesday = LocalNote::create("Wednesday", body, dir)?;
+    let (index, report) = Index::new_with_report(dir)?;
+    assert_eq!(report.added.len(), 2);
+    assert_eq!(report.removed.len(), 2);
+    let tuesday_id = index.get_by_path(&tuesday.path)?.unwrap().id;
+    let wednesday_id = index.get_by_path(&wednesday.path)?.unwrap().id;
+    assert_ne!(tuesday_id, wednesday_id);
+    for id in [&tuesday_id, &wednesday_id] {
+        assert_ne!(id, &deleted_id);
+        assert_ne!(id, &direct_id);
+    }
+    drop(index);
+
+    // One vanished entry but two new notes with its content.
+    fs::remove_file(&tuesday.path)?;
+    let thursday = LocalNote::create("Thursday", body, dir)?;
+    let friday = LocalNote::create("Friday", body, dir)?;
+    let index = Index::new(dir)?;
+    assert!(index.get_by_id(&tuesday_id)?.is_none());
+    assert_ne!(
+        index.get_by_path(&thursday.path)?.unwrap().id,
+        index.get_by_path(&friday.path)?.unwrap().id
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reconcile_fixes_changes_made_while_closed() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let edited = LocalNote::create("Edited", "old text", dir)?;
+    let touched = LocalNote::create("Touched", "same text", dir)?;
+    let deleted = LocalNote::create("Deleted", "soon gone", dir)?;
+    LocalNote::create("Kept", "untouched", dir)?;
+
+    let (index, report) = Index::new_with_report(dir)?;
+    assert_eq!(report.added.len(), 4);
+    let edited_id = index.get_by_path(&edited.path)?.unwrap().id;
+    drop(index);
+
+    // Changes made while no index is open.
+    std::fs::write(&edited.path, "new and longer text")?;
+    std::fs::File::options()
+        .write(true)
+        .open(&touched.path)?
+        .set_modified(SystemTime::now() + Duration::from_secs(60))?;
+    deleted.delete()?;
+    let added = LocalNote::create("Added", "brand new", dir)?;
+
+    let (index, report) = Index::new_with_report(dir)?;
+    assert_eq!(report.added, vec![added.path.clone()]);
+    assert_eq!(report.updated, vec![edited.path.clone()]);
+    assert_eq!(report.removed, vec![deleted.path.clone()]);
+    assert_eq!(report.unchanged, 2);
+
+    let refreshed = index.get_by_path(&edited.path)?.unwrap();
+    assert_eq!(refreshed.content, "new and longer text");
+    assert_eq!(refreshed.id, edited_id);
+    assert!(Query::new(&index).search("soon")?.is_empty());
+
+    // A second pass finds nothing to do.
+    let report = index.reconcile()?;
+    assert!(report.is_clean());
+    assert_eq!(report.unchanged, 4);
+
+    Ok(())
+}
+
+#[test]
+fn index_follows_shelf_config() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    fs::create_dir(dir.join("drafts"))?;
+    LocalNote::create("Kept", "She runs every morning.", dir)?;
+    let draft = LocalNote::create("Draft", "Unfinished", &dir.join("drafts"))?;
+
+    let config = ShelfConfig {
+        ignore: vec!["drafts/".into()],
+        tokenizer: Tokenizer::Porter,
+        ..ShelfConfig::default()
+    };
+    config.save(dir)?;
+
+    let index = Index::new(dir)?;
+    assert!(index.is_ignored(&draft.path));
+    assert!(index.get_by_path(&draft.path)?.is_none());
+    assert_eq!(index.config(), &config);
+    assert_eq!(Query::new(&index).search("run")?.len(), 1, "porter stems");
+    drop(index);
+
+    // Switching back rebuilds the full-text table with the new tokenizer,
+    // and un-ignored notes are picked up.
+    ShelfConfig::default().save(dir)?;
+    let index = Index::new(dir)?;
+    assert!(Query::new(&index).search("run")?.is_empty());
+    assert_eq!(Query::new(&index).search("runs")?.len(), 1);
+    assert!(index.get_by_path(&draft.path)?.is_some());
+    drop(index);
+
+    // Ignoring an indexed note drops its row.
+    config.save(dir)?;
+    let (index, report) = Index::new_with_report(dir)?;
+    assert_eq!(report.removed, vec![draft.path.clone()]);
+    assert!(index.get_by_path(&draft.path)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn ignore_files_are_honored_and_reloaded() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    fs::create_dir_all(dir.join("node_modules/pkg"))?;
+    fs::create_dir_all(dir.join(".obsidian"))?;
+    let vendored = LocalNote::create("Readme", "vendored", &dir.join("node_modules/pkg"))?;
+    let hidden = LocalNote::create("Workspace", "settings", &dir.join(".obsidian"))?;
+    let note = LocalNote::create("Note", "mine", dir)?;
+    fs::write(dir.join(".oraignore"), "node_modules/\n")?;
+
+    let index = Index::new(dir)?;
+    assert!(index.get_by_path(&note.path)?.is_some());
+    assert!(index.get_by_path(&vendored.path)?.is_none());
+    assert!(index.get_by_path(&hidden.path)?.is_none());
+
+    fs::write(dir.join(".oraignore"), "Note.md\n")?;
+    index.reload_ignore_rules()?;
+    let report = index.reconcile()?;
+    assert_eq!(report.added, vec![vendored.path.clone()]);
+    assert_eq!(report.removed, vec![note.path.clone()]);
+
+    Ok(())
+}
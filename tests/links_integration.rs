@@ -0,0 +1,73 @@
+use ora_core::links::{LinkKind, extract_links, rebase_links};
+use std::path::Path;
+
+#[test]
+fn wikilinks_with_alias_fragment_and_embed() {
+    let content = "Intro [[Note Title]], [[Other|shown]] and ![[Diagram#Part 2]].";
+    let links = extract_links(content);
+
+    assert_eq!(links.len(), 3);
+    assert_eq!(links[0].target, "Note Title");
+    assert_eq!(&content[links[0].target_range.clone()], "Note Title");
+    assert_eq!(links[1].target, "Other");
+    assert_eq!(links[1].alias.as_deref(), Some("shown"));
+    assert_eq!(links[2].target, "Diagram");
+    assert_eq!(links[2].fragment.as_deref(), Some("Part 2"));
+    assert!(links[2].embed);
+}
+
+#[test]
+fn markdown_links_only_to_markdown_files() {
+    let content = "[a](other%20note.md#sec) [b](<sub dir/x.md>) [c](https://x.org/a.md) \
+                   [d](image.png) ![e](pic.md)";
+    let links = extract_links(content);
+
+    assert_eq!(links.len(), 2);
+    assert!(links.iter().all(|l| l.kind == LinkKind::Markdown));
+    assert_eq!(links[0].target, "other note.md");
+    assert_eq!(&content[links[0].target_range.clone()], "other%20note.md");
+    assert_eq!(links[0].fragment.as_deref(), Some("sec"));
+    assert_eq!(links[1].target, "sub dir/x.md");
+}
+
+#[test]
+fn links_in_code_and_front_matter_are_ignored() {
+    let content =
+        "---\nsource: \"[[Header]]\"\n---\n`[[Inline]]`\n```\n[[Fenced]]\n```\n[[Real]]\n";
+    let links = extract_links(content);
+
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].target, "Real");
+    assert_eq!(&content[links[0].target_range.clone()], "Real");
+}
+
+#[test]
+fn markdown_links_resolve_relative_to_source() {
+    let content = "[up](../Other.md) [root](/top/Note.md)";
+    let links = extract_links(content);
+    let source = Path::new("/shelf/folder/Note.md");
+    let root = Path::new("/shelf");
+
+    assert_eq!(
+        links[0].resolve_path(source, root).unwrap(),
+        Path::new("/shelf/Other.md")
+    );
+    assert_eq!(
+        links[1].resolve_path(source, root).unwrap(),
+        Path::new("/shelf/top/Note.md")
+    );
+}
+
+#[test]
+fn rebase_links_follows_moved_note() {
+    let (content, changed) = rebase_links(
+        "[a](sibling.md) [b](</abs/x.md>) [c](<sub dir/c.md#top>) [[Wiki]]",
+        Path::new("/shelf/notes/Note.md"),
+        Path::new("/shelf/archive/2024/Note.md"),
+    );
+    assert_eq!(
+        content,
+        "[a](../../notes/sibling.md) [b](</abs/x.md>) [c](<../../notes/sub dir/c.md#top>) [[Wiki]]"
+    );
+    assert_eq!(changed, 2);
+}
@@ -1,6 +1,14 @@
+use ora_core::domain::{LocalNote, NoteError};
 use ora_core::error::OraError;
-use ora_core::shelf::manager::ShelfManager;
+use ora_core::search::{SortBy, SortDirection};
+use ora_core::shelf::config::{FilenameStyle, ShelfConfig};
+use ora_core::shelf::manager::{
+    ConflictPolicy, RenameOptions, ShelfManager, TransferMode, TransferOptions,
+};
 use ora_core::shelf::storage::Shelf;
+use ora_core::watcher::index::Index;
+use std::fs;
+use std::time::{Duration, SystemTime};
 use tempfile::TempDir;
 
 #[test]
@@ -9,6 +17,7 @@ fn create_and_list_notes() -> Result<(), OraError> {
     let shelf = Shelf {
         root: tmpdir.path().to_path_buf(),
         name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
     };
 
     let manager = ShelfManager::new(&shelf);
@@ -34,6 +43,7 @@ fn get_note_should_return_specific_note() -> Result<(), OraError> {
     let shelf = Shelf {
         root: tmpdir.path().to_path_buf(),
         name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
     };
 
     let manager = ShelfManager::new(&shelf);
@@ -55,6 +65,7 @@ fn update_note_should_change_content_and_title() -> Result<(), OraError> {
     let shelf = Shelf {
         root: tmpdir.path().to_path_buf(),
         name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
     };
 
     let manager = ShelfManager::new(&shelf);
@@ -76,6 +87,7 @@ fn delete_note_should_remove_file() -> Result<(), OraError> {
     let shelf = Shelf {
         root: tmpdir.path().to_path_buf(),
         name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
     };
 
     let manager = ShelfManager::new(&shelf);
@@ -89,3 +101,631 @@ fn delete_note_should_remove_file() -> Result<(), OraError> {
 
     Ok(())
 }
+
+#[test]
+fn rename_note_rewrites_inbound_links() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
+    };
+    fs::create_dir(shelf.root.join("sub"))?;
+
+    let manager = ShelfManager::new(&shelf);
+    manager.create_note("Old Title", "I am [[Old Title]].")?;
+    let linker = manager.create_note(
+        "Linker",
+        "See [[old title|alias]], [[Old Title#Part]] and [md](Old%20Title.md).",
+    )?;
+    let nested = LocalNote::create(
+        "Nested",
+        "Up: [x](../Old%20Title.md)",
+        &shelf.root.join("sub"),
+    )?;
+    let untouched = manager.create_note("Untouched", "No links to [[Other]].")?;
+
+    let options = RenameOptions {
+        update_links: true,
+        dry_run: false,
+    };
+    let report = manager.rename_note("Old Title", "New Title", &options)?;
+
+    assert!(!report.old_path.exists());
+    assert!(report.new_path.ends_with("New Title.md"));
+    assert_eq!(report.rewrites.len(), 3);
+    assert!(!report.changed_files().contains(&untouched.path.as_path()));
+
+    assert_eq!(
+        fs::read_to_string(&linker.path)?,
+        "See [[New Title|alias]], [[New Title#Part]] and [md](New%20Title.md)."
+    );
+    assert_eq!(
+        fs::read_to_string(&nested.path)?,
+        "Up: [x](../New%20Title.md)"
+    );
+    assert_eq!(fs::read_to_string(&report.new_path)?, "I am [[New Title]].");
+    assert_eq!(
+        fs::read_to_string(&untouched.path)?,
+        "No links to [[Other]]."
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rename_note_dry_run_changes_nothing() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
+    };
+
+    let manager = ShelfManager::new(&shelf);
+    let target = manager.create_note("Target", "content")?;
+    let linker = manager.create_note("Linker", "[[Target]]")?;
+
+    let options = RenameOptions {
+        update_links: true,
+        dry_run: true,
+    };
+    let report = manager.rename_note("Target", "Renamed", &options)?;
+
+    assert!(report.dry_run);
+    assert_eq!(report.changed_files(), vec![linker.path.as_path()]);
+    assert_eq!(report.rewrites[0].new_content, "[[Renamed]]");
+    assert!(target.path.exists());
+    assert!(!report.new_path.exists());
+    assert_eq!(fs::read_to_string(&linker.path)?, "[[Target]]");
+
+    // Without link updates only the note itself is renamed.
+    let report = manager.rename_note("Target", "Renamed", &RenameOptions::default())?;
+    assert!(report.rewrites.is_empty());
+    assert!(report.new_path.exists());
+    assert_eq!(fs::read_to_string(&linker.path)?, "[[Target]]");
+
+    Ok(())
+}
+
+#[test]
+fn rename_note_skips_ambiguous_titles() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
+    };
+    fs::create_dir(shelf.root.join("a"))?;
+    fs::create_dir(shelf.root.join("b"))?;
+    let first = LocalNote::create("Plan", "First plan", &shelf.root.join("a"))?;
+    LocalNote::create("Plan", "Second plan", &shelf.root.join("b"))?;
+    let linker = LocalNote::create("Ref", "See [[Plan]] and [[b/Plan]].", &shelf.root)?;
+
+    let index = Index::new(&shelf.root)?;
+    let manager = ShelfManager::with_index(&shelf, index.clone());
+    let options = RenameOptions {
+        update_links: true,
+        dry_run: false,
+    };
+    let report = manager.rename_note("b/Plan", "Roadmap", &options)?;
+
+    // The bare title also names a/Plan, so only the path link is rewritten.
+    assert_eq!(
+        fs::read_to_string(&linker.path)?,
+        "See [[Plan]] and [[b/Roadmap]]."
+    );
+    assert_eq!(report.ambiguous.len(), 1);
+    assert_eq!(report.ambiguous[0].path, linker.path);
+    assert_eq!(report.ambiguous[0].target, "Plan");
+    assert_eq!(index.backlinks(&first.path)?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn rename_note_matches_titles_like_the_index() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let target = LocalNote::create("Über", "content", &shelf.root)?;
+    let linker = LocalNote::create("Ref", "See [[über]].", &shelf.root)?;
+
+    let index = Index::new(&shelf.root)?;
+    assert_eq!(index.backlinks(&target.path)?.len(), 1);
+    let manager = ShelfManager::with_index(&shelf, index.clone());
+    let options = RenameOptions {
+        update_links: true,
+        dry_run: false,
+    };
+    let report = manager.rename_note("Über", "Other", &options)?;
+
+    assert_eq!(report.rewrites.len(), 1);
+    assert_eq!(fs::read_to_string(&linker.path)?, "See [[Other]].");
+    assert_eq!(index.backlinks(&report.new_path)?.len(), 1);
+    assert!(index.unresolved_links()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn get_note_by_id_with_and_without_index() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "test_shelf".to_string(),
+        config: ShelfConfig::default(),
+    };
+
+    let plain = ShelfManager::new(&shelf);
+    plain.create_note("Tagged", "---\nid: fixed\n---\nBody")?;
+    assert_eq!(plain.get_note_by_id("fixed")?.title, "Tagged");
+    assert!(matches!(
+        plain.get_note_by_id("missing"),
+        Err(OraError::Note(NoteError::NotFound(_)))
+    ));
+
+    let manager = ShelfManager::with_index(&shelf, Index::new(&shelf.root)?);
+    let note = manager.create_note("Untagged", "content")?;
+    let id = manager
+        .index()
+        .unwrap()
+        .get_by_path(&note.path)?
+        .unwrap()
+        .id;
+
+    manager.update_note("Untagged", Some("Retitled"), None)?;
+    assert_eq!(manager.get_note_by_id(&id)?.title, "Retitled");
+
+    manager.rename_note("Retitled", "Final", &RenameOptions::default())?;
+    assert_eq!(manager.get_note_by_id(&id)?.title, "Final");
+    assert_eq!(manager.get_note_by_id("fixed")?.title, "Tagged");
+
+    Ok(())
+}
+
+#[test]
+fn manager_follows_shelf_config() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "configured".to_string(),
+        config: ShelfConfig {
+            ignore: vec!["*.tmp.md".into()],
+            template: Some("# {{title}}\n".into()),
+            filename_style: FilenameStyle::Slug,
+            ..ShelfConfig::default()
+        },
+    };
+    let manager = ShelfManager::new(&shelf);
+
+    let note = manager.create_note("Weekly Plan", "")?;
+    assert_eq!(note.path, tmpdir.path().join("weekly-plan.md"));
+    assert_eq!(note.content, "# Weekly Plan\n");
+
+    let explicit = manager.create_note("Explicit", "Own content")?;
+    assert_eq!(explicit.content, "Own content");
+
+    assert_eq!(manager.get_note("Weekly Plan")?.path, note.path);
+    let renamed = manager.update_note("weekly-plan", Some("Monthly Plan"), None)?;
+    assert_eq!(renamed.path, tmpdir.path().join("monthly-plan.md"));
+
+    fs::write(tmpdir.path().join("scratch.tmp.md"), "ignored")?;
+    let mut titles: Vec<_> = manager.list_notes()?.into_iter().map(|n| n.title).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["explicit", "monthly-plan"]);
+
+    Ok(())
+}
+
+#[test]
+fn list_notes_honors_ignore_files() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "ignoring".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::new(&shelf);
+    manager.create_note("Visible", "shown")?;
+    manager.create_note("Scratch", "hidden")?;
+
+    fs::write(tmpdir.path().join(".oraignore"), "Scratch.md\n")?;
+    let titles: Vec<_> = manager.list_notes()?.into_iter().map(|n| n.title).collect();
+    assert_eq!(titles, vec!["Visible"]);
+
+    Ok(())
+}
+
+#[test]
+fn notes_are_addressed_by_relative_path() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "folders".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::new(&shelf);
+
+    let plan = manager.create_note("projects/2024/Plan", "plan")?;
+    assert_eq!(plan.path, tmpdir.path().join("projects/2024/Plan.md"));
+    assert_eq!(plan.title, "Plan");
+    manager.create_note("Root", "root")?;
+    manager.create_note("projects/Overview", "overview")?;
+
+    assert_eq!(manager.get_note("projects/2024/Plan")?.content, "plan");
+    assert_eq!(manager.get_note("projects/2024/Plan.md")?.path, plan.path);
+    for invalid in ["../outside", ".secret", "projects/..", ""] {
+        assert!(
+            matches!(
+                manager.get_note(invalid),
+                Err(OraError::Note(NoteError::InvalidPath))
+            ),
+            "{invalid:?}"
+        );
+    }
+    for invalid in [".hidden/Note", ".hidden", "projects/.draft"] {
+        assert!(
+            matches!(
+                manager.create_note(invalid, "x"),
+                Err(OraError::Note(NoteError::InvalidPath))
+            ),
+            "{invalid:?}"
+        );
+    }
+    assert!(!tmpdir.path().join(".hidden.md").exists());
+    assert!(matches!(
+        manager.update_note("Root", Some(".hidden"), None),
+        Err(OraError::Note(NoteError::InvalidPath))
+    ));
+    assert!(matches!(
+        manager.rename_note("Root", ".hidden", &Default::default()),
+        Err(OraError::Note(NoteError::InvalidPath))
+    ));
+
+    let titles = |notes: Vec<LocalNote>| notes.into_iter().map(|n| n.title).collect::<Vec<_>>();
+    assert_eq!(titles(manager.list_notes()?), vec!["Root"]);
+    assert_eq!(
+        titles(manager.list_notes_in("projects", false)?),
+        vec!["Overview"]
+    );
+    assert_eq!(
+        titles(manager.list_notes_in("", true)?),
+        vec!["Root", "Plan", "Overview"]
+    );
+
+    let updated = manager.update_note("projects/2024/Plan", Some("Roadmap"), None)?;
+    assert_eq!(updated.path, tmpdir.path().join("projects/2024/Roadmap.md"));
+    manager.delete_note("projects/2024/Roadmap")?;
+    assert!(!updated.path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn folders_can_be_created_listed_renamed_and_deleted() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "folders".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::with_index(&shelf, Index::new(&shelf.root)?);
+    let index = manager.index().unwrap().clone();
+
+    manager.create_folder("archive/2023")?;
+    manager.create_folder("drafts")?;
+    fs::write(tmpdir.path().join(".oraignore"), "drafts/\n")?;
+    let note = manager.create_note("work/Ideas", "ideas")?;
+    let id = index.get_by_path(&note.path)?.unwrap().id;
+
+    let names = |folders: Vec<std::path::PathBuf>| {
+        folders
+            .iter()
+            .map(|f| f.display().to_string())
+            .collect::<Vec<_>>()
+    };
+    index.reload_ignore_rules()?;
+    assert_eq!(
+        names(manager.list_folders("", false)?),
+        vec!["archive", "work"]
+    );
+    assert_eq!(
+        names(manager.list_folders("", true)?),
+        vec!["archive", "archive/2023", "work"]
+    );
+
+    let moved = manager.rename_folder("work", "archive/work")?;
+    assert_eq!(moved, tmpdir.path().join("archive/work"));
+    let renamed = index.get_by_id(&id)?.unwrap();
+    assert_eq!(renamed.path, tmpdir.path().join("archive/work/Ideas.md"));
+    assert!(matches!(
+        manager.rename_folder("archive", "archive/inner"),
+        Err(OraError::Note(NoteError::InvalidPath))
+    ));
+
+    assert!(manager.delete_folder("archive", false).is_err());
+    manager.delete_folder("archive", true)?;
+    assert!(!tmpdir.path().join("archive").exists());
+    assert!(index.get_by_id(&id)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn move_note_keeps_id_and_links() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "moving".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::with_index(&shelf, Index::new(&shelf.root)?);
+    manager.create_folder("archive")?;
+    manager.create_note("archive/Plan", "older plan")?;
+    let plan = manager.create_note("Plan", "See [guide](guides/Guide.md).")?;
+    manager.create_note("guides/Guide", "guide")?;
+    manager.create_note("Index", "[plan](Plan.md) and [[Plan]]")?;
+    let id = manager
+        .index()
+        .unwrap()
+        .get_by_path(&plan.path)?
+        .unwrap()
+        .id;
+
+    let options = RenameOptions {
+        update_links: true,
+        ..RenameOptions::default()
+    };
+    let report = manager.move_note("Plan", "archive", &options)?;
+    assert_eq!(report.new_path, tmpdir.path().join("archive/Plan 1.md"));
+    assert!(!plan.path.exists());
+
+    let moved = manager.get_note("archive/Plan 1")?;
+    assert_eq!(moved.content, "See [guide](../guides/Guide.md).");
+    // archive/Plan shares the title, so the wikilink is only reported.
+    assert_eq!(
+        manager.get_note("Index")?.content,
+        "[plan](archive/Plan%201.md) and [[Plan]]"
+    );
+    assert_eq!(report.ambiguous.len(), 1);
+    assert_eq!(manager.get_note_by_id(&id)?.path, moved.path);
+
+    assert!(matches!(
+        manager.move_note("archive/Plan 1", "archive", &options),
+        Err(OraError::NoChanges)
+    ));
+    assert!(matches!(
+        manager.move_note("archive/Plan 1", "missing", &options),
+        Err(OraError::Note(NoteError::NotFound(_)))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn notes_move_and_copy_between_shelves() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = |name: &str| -> Result<Shelf, OraError> {
+        let root = tmpdir.path().join(name);
+        fs::create_dir_all(&root)?;
+        Ok(Shelf {
+            root,
+            name: name.to_string(),
+            config: ShelfConfig::default(),
+        })
+    };
+    let (work, home) = (shelf("work")?, shelf("home")?);
+    let source = ShelfManager::with_index(&work, Index::new(&work.root)?);
+    let target = ShelfManager::with_index(&home, Index::new(&home.root)?);
+
+    let note = source.create_note("Plan", "the plan")?;
+    let id = source.index().unwrap().get_by_path(&note.path)?.unwrap().id;
+    {
+        let conn = source.index().unwrap().conn.lock().unwrap();
+        conn.execute(
+            "UPDATE notes SET created_at = '2020-01-01 00:00:00', updated_at = '2021-06-01 12:00:00'",
+            [],
+        )?;
+    }
+    let modified = fs::metadata(&note.path)?.modified()?;
+    target.create_note("inbox/Plan", "another plan")?;
+
+    let report = source.transfer_note("Plan", &target, "inbox", &TransferOptions::default())?;
+    let moved = home.root.join("inbox/Plan 1.md");
+    assert_eq!(report.transferred, vec![(note.path.clone(), moved.clone())]);
+    assert!(!note.path.exists());
+    assert_eq!(fs::metadata(&moved)?.modified()?, modified);
+    assert!(source.index().unwrap().get_by_path(&note.path)?.is_none());
+    assert_eq!(target.index().unwrap().get_by_id(&id)?.unwrap().path, moved);
+    let origin = target.index().unwrap().origin(&moved)?.unwrap();
+    assert_eq!(
+        (
+            origin.id,
+            origin.created_at.as_str(),
+            origin.updated_at.as_str()
+        ),
+        (id.clone(), "2020-01-01 00:00:00", "2021-06-01 12:00:00")
+    );
+
+    let copy = TransferOptions {
+        mode: TransferMode::Copy,
+        on_conflict: ConflictPolicy::Skip,
+    };
+    let report = target.transfer_note("inbox/Plan", &source, "", &copy)?;
+    assert_eq!(report.transferred.len(), 1);
+    assert_eq!(source.get_note("Plan")?.content, "another plan");
+    assert!(home.root.join("inbox/Plan.md").exists());
+
+    let report = target.transfer_note("inbox/Plan", &source, "", &copy)?;
+    assert_eq!(report.skipped, vec![home.root.join("inbox/Plan.md")]);
+
+    let fail = TransferOptions {
+        on_conflict: ConflictPolicy::Fail,
+        ..TransferOptions::default()
+    };
+    assert!(matches!(
+        target.transfer_note("inbox/Plan", &source, "", &fail),
+        Err(OraError::Io(e)) if e.kind() == std::io::ErrorKind::AlreadyExists
+    ));
+
+    let overwrite = TransferOptions {
+        on_conflict: ConflictPolicy::Overwrite,
+        ..TransferOptions::default()
+    };
+    let copied = source.update_note("Plan", None, Some("replacement"))?;
+    let copied_id = source
+        .index()
+        .unwrap()
+        .get_by_path(&copied.path)?
+        .unwrap()
+        .id;
+    source.transfer_note("Plan", &target, "inbox", &overwrite)?;
+    let replaced = target.get_note("inbox/Plan")?;
+    assert_eq!(replaced.content, "replacement");
+    let indexed = target
+        .index()
+        .unwrap()
+        .get_by_path(&replaced.path)?
+        .unwrap();
+    assert_eq!(indexed.id, copied_id);
+    assert!(source.list_notes()?.is_empty());
+
+    // Transferring a note onto itself changes nothing, whatever the policy.
+    let inbox = target.list_notes_in("inbox", false)?.len();
+    for on_conflict in [ConflictPolicy::Rename, ConflictPolicy::Overwrite] {
+        for mode in [TransferMode::Move, TransferMode::Copy] {
+            let options = TransferOptions { mode, on_conflict };
+            assert!(matches!(
+                target.transfer_note("inbox/Plan", &target, "inbox", &options),
+                Err(OraError::NoChanges)
+            ));
+        }
+    }
+    assert_eq!(target.list_notes_in("inbox", false)?.len(), inbox);
+
+    Ok(())
+}
+
+#[test]
+fn folders_transfer_between_shelves() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = |name: &str| -> Result<Shelf, OraError> {
+        let root = tmpdir.path().join(name);
+        fs::create_dir_all(&root)?;
+        Ok(Shelf {
+            root,
+            name: name.to_string(),
+            config: ShelfConfig::default(),
+        })
+    };
+    let (work, home) = (shelf("work")?, shelf("home")?);
+    let source = ShelfManager::with_index(&work, Index::new(&work.root)?);
+    let target = ShelfManager::with_index(&home, Index::new(&home.root)?);
+
+    source.create_note("projects/Alpha", "alpha")?;
+    source.create_note("projects/old/Beta", "beta")?;
+    fs::write(work.root.join("projects/diagram.png"), "png")?;
+    target.create_note("archive/projects/Alpha", "existing")?;
+
+    let copy = TransferOptions {
+        mode: TransferMode::Copy,
+        ..TransferOptions::default()
+    };
+    let report = source.transfer_folder("projects", &target, "archive", &copy)?;
+    assert_eq!(report.transferred.len(), 2);
+    assert!(work.root.join("projects/Alpha.md").exists());
+    assert_eq!(
+        target.get_note("archive/projects/Alpha 1")?.content,
+        "alpha"
+    );
+    assert_eq!(
+        target.list_notes_in("archive", true)?.len(),
+        target
+            .index()
+            .unwrap()
+            .paths_under(&home.root.join("archive"))?
+            .len()
+    );
+
+    let report = source.transfer_folder("projects", &target, "", &TransferOptions::default())?;
+    assert_eq!(report.transferred.len(), 2);
+    assert_eq!(target.get_note("projects/old/Beta")?.content, "beta");
+    assert!(!work.root.join("projects/old").exists());
+    assert!(work.root.join("projects/diagram.png").exists());
+    assert!(
+        source
+            .index()
+            .unwrap()
+            .paths_under(&work.root.join("projects"))?
+            .is_empty()
+    );
+
+    assert!(matches!(
+        source.transfer_folder("", &target, "", &copy),
+        Err(OraError::Note(NoteError::InvalidPath))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn notes_can_be_listed_in_order() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "sorted".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::new(&shelf);
+
+    let now = SystemTime::now();
+    for (title, content, age) in [
+        ("beta", "a longer body here", 30),
+        ("Alpha", "short", 10),
+        ("gamma", "mid body", 20),
+    ] {
+        let note = manager.create_note(title, content)?;
+        fs::File::options()
+            .write(true)
+            .open(&note.path)?
+            .set_modified(now - Duration::from_secs(age * 60))?;
+    }
+
+    let titles = |sort_by, direction| -> Result<Vec<String>, OraError> {
+        Ok(manager
+            .list_notes_sorted("", false, sort_by, direction)?
+            .into_iter()
+            .map(|note| note.title)
+            .collect())
+    };
+    use SortDirection::{Ascending, Descending};
+
+    assert_eq!(
+        titles(SortBy::Title, Ascending)?,
+        ["Alpha", "beta", "gamma"]
+    );
+    assert_eq!(
+        titles(SortBy::Path, Descending)?,
+        ["gamma", "beta", "Alpha"]
+    );
+    assert_eq!(titles(SortBy::Size, Ascending)?, ["Alpha", "gamma", "beta"]);
+    assert_eq!(
+        titles(SortBy::Updated, Ascending)?,
+        ["beta", "gamma", "Alpha"]
+    );
+    assert_eq!(
+        titles(
+            SortBy::RelevanceWithRecency {
+                half_life: Duration::from_secs(3600)
+            },
+            Ascending
+        )?,
+        ["Alpha", "gamma", "beta"]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use ora_core::error::OraError;
+use ora_core::search::Query;
+use ora_core::watcher::index::{Index, SCHEMA_VERSION};
+use ora_core::watcher::service::WatcherService;
+use rusqlite::{Connection, params};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Creates a `.shelf.db` the way the first released version did: no
+/// schema version, whole file content (front matter included) in `content`.
+fn create_unversioned_db(dir: &Path, notes: &[(&str, &str)]) -> rusqlite::Result<()> {
+    let conn = Connection::open(dir.join(".shelf.db"))?;
+    conn.execute_batch(
+        "CREATE TABLE notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL DEFAULT '',
+            path TEXT UNIQUE NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE VIRTUAL TABLE contents USING fts5(title, content, content='notes', content_rowid='id');
+        CREATE TRIGGER notes_ai AFTER INSERT ON notes BEGIN
+         INSERT INTO contents(rowid, title, content) VALUES (new.id, new.title, new.content);
+        END;
+        CREATE TRIGGER notes_ad AFTER DELETE ON notes BEGIN
+         INSERT INTO contents(contents, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
+        END;
+        CREATE TRIGGER notes_au AFTER UPDATE ON notes BEGIN
+         INSERT INTO contents(contents, rowid, title, content) VALUES('delete', old.id, old.title, old.content);
+         INSERT INTO contents(rowid, title, content) VALUES (new.id, new.title, new.content);
+        END;",
+    )?;
+
+    for (title, content) in notes {
+        let path = dir.join(format!("{title}.md"));
+        conn.execute(
+            "INSERT INTO notes (title, content, path, created_at) VALUES (?, ?, ?, '2020-01-01 00:00:00')",
+            params![title, content, path.display().to_string()],
+        )?;
+    }
+    Ok(())
+}
+
+fn schema_version(index: &Index) -> i32 {
+    let conn = index.conn.lock().unwrap();
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn unversioned_database_is_migrated_in_place() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let content = "---\ntags: [legacy]\n---\nOld body about zebras.";
+    fs::write(dir.join("Legacy.md"), content)?;
+    create_unversioned_db(dir, &[("Legacy", content), ("Vanished", "gone")])
+        .map_err(OraError::from)?;
+
+    let (index, report) = Index::new_with_report(dir)?;
+    assert!(!report.rebuilt);
+    assert_eq!(schema_version(&index), SCHEMA_VERSION);
+    assert_eq!(report.updated, vec![dir.join("Legacy.md")]);
+    assert_eq!(report.removed, vec![dir.join("Vanished.md")]);
+
+    let legacy = index.get_by_path(&dir.join("Legacy.md"))?.unwrap();
+    assert_eq!(legacy.content, "Old body about zebras.");
+    assert!(!legacy.id.is_empty());
+
+    let created_at: String = {
+        let conn = index.conn.lock().unwrap();
+        conn.query_row("SELECT created_at FROM notes", [], |row| row.get(0))?
+    };
+    assert_eq!(created_at, "2020-01-01 00:00:00");
+
+    let query = Query::new(&index);
+    assert_eq!(query.search("zebras")?.len(), 1);
+    assert_eq!(query.notes_with_tag("legacy")?.len(), 1);
+    drop(index);
+
+    // Reopening an up-to-date database changes nothing.
+    let (index, report) = Index::new_with_report(dir)?;
+    assert!(!report.rebuilt);
+    assert!(report.is_clean());
+    assert_eq!(
+        index.get_by_path(&dir.join("Legacy.md"))?.unwrap().id,
+        legacy.id
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unmigratable_or_corrupt_databases_are_rebuilt_from_disk() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    let db_path = dir.join(".shelf.db");
+    fs::write(dir.join("Note.md"), "Rebuilt from disk.")?;
+
+    // An old schema the migrations cannot bring up to date.
+    Connection::open(&db_path)
+        .and_then(|conn| conn.execute_batch("CREATE TABLE notes (id INTEGER PRIMARY KEY);"))
+        .map_err(OraError::from)?;
+    let (index, report) = Index::new_with_report(dir)?;
+    assert!(report.rebuilt);
+    assert_eq!(report.added, vec![dir.join("Note.md")]);
+    assert_eq!(schema_version(&index), SCHEMA_VERSION);
+    drop(index);
+
+    // Not a database at all, with a stale journal left behind.
+    fs::write(&db_path, "this is not a database")?;
+    fs::write(dir.join(".shelf.db-wal"), "stale")?;
+    let (index, report) = Index::new_with_report(dir)?;
+    assert!(report.rebuilt);
+    assert!(!dir.join(".shelf.db-wal").exists());
+    assert_eq!(Query::new(&index).search("rebuilt")?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn newer_databases_are_left_alone() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    fs::write(dir.join("Note.md"), "Indexed by a newer version.")?;
+
+    {
+        let index = Index::new(dir)?;
+        let conn = index.conn.lock().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)?;
+    }
+
+    assert!(matches!(
+        Index::new(dir),
+        Err(OraError::SchemaTooNew { found, supported })
+            if found == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+    ));
+
+    let conn = Connection::open(dir.join(".shelf.db"))?;
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let notes: i64 = conn.query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))?;
+    assert_eq!((version, notes), (SCHEMA_VERSION + 1, 1));
+
+    Ok(())
+}
+
+#[test]
+fn watchers_report_newer_databases() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path();
+    {
+        let index = Index::new(dir)?;
+        let conn = index.conn.lock().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)?;
+    }
+
+    assert!(matches!(
+        WatcherService::create(dir, Duration::from_millis(50)),
+        Err(OraError::SchemaTooNew { found, supported })
+            if found == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,606 @@
+use ora_core::domain::{LocalNote, NoteError};
+use ora_core::error::OraError;
+use ora_core::search::fuzzy::{edit_distance, fuzzy_match};
+use ora_core::search::parser::{QueryParseError, UserQuery};
+use ora_core::search::{
+    FuzzyOptions, MatchKind, Query, SearchMode, SearchOptions, SortBy, SortDirection, TagCount,
+    TimeRange,
+};
+use ora_core::shelf::config::ShelfConfig;
+use ora_core::watcher::index::Index;
+use rusqlite::{Connection, params};
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+/// Helper: create a shelf directory with the given `(title, content)` notes
+/// and return an index over it.
+fn indexed_shelf(notes: &[(&str, &str)]) -> Result<(TempDir, Index), OraError> {
+    let tmpdir = TempDir::new()?;
+    for (title, content) in notes {
+        LocalNote::create(title, content, tmpdir.path())?;
+    }
+    let index = Index::new(tmpdir.path())?;
+    Ok((tmpdir, index))
+}
+
+#[test]
+fn tags_are_listed_with_counts() -> Result<(), OraError> {
+    let (_tmpdir, index) = indexed_shelf(&[
+        ("One", "---\ntags: [rust]\n---\n#project/ora notes"),
+        ("Two", "#project/web and #rust"),
+        ("Three", "#project"),
+    ])?;
+
+    let tags = Query::new(&index).tags()?;
+    let count = |tag: &str| tags.iter().find(|t| t.tag == tag).map(|t| t.count);
+
+    assert_eq!(count("project"), Some(3));
+    assert_eq!(count("project/ora"), Some(1));
+    assert_eq!(count("project/web"), Some(1));
+    assert_eq!(count("rust"), Some(2));
+    assert_eq!(
+        tags.first(),
+        Some(&TagCount {
+            tag: "project".into(),
+            count: 3
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn notes_with_tag_includes_nested_tags() -> Result<(), OraError> {
+    let (_tmpdir, index) = indexed_shelf(&[
+        ("Ora", "#project/ora"),
+        ("Projects", "#project"),
+        ("Prose", "#projection is not a child"),
+    ])?;
+
+    let query = Query::new(&index);
+    let titles = |notes: Vec<ora_core::watcher::index::IndexedNote>| {
+        notes.into_iter().map(|n| n.title).collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        titles(query.notes_with_tag("#Project")?),
+        vec!["Ora", "Projects"]
+    );
+    assert_eq!(titles(query.notes_with_tag("project/ora")?), vec!["Ora"]);
+    assert!(query.notes_with_tag("missing")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn search_can_be_filtered_by_tags() -> Result<(), OraError> {
+    let (_tmpdir, index) = indexed_shelf(&[
+        ("Rust Work", "rust notes #work"),
+        ("Rust Home", "rust notes #home #work/remote"),
+        ("Other", "python notes #work"),
+    ])?;
+
+    let query = Query::new(&index);
+    let options = SearchOptions {
+        tags: vec!["work".into()],
+        ..Default::default()
+    };
+    assert_eq!(query.search_with_options("rust", &options)?.len(), 2);
+
+    let options = SearchOptions {
+        tags: vec!["work".into(), "home".into()],
+        ..Default::default()
+    };
+    let results = query.search_with_options("rust", &options)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].note.title, "Rust Home");
+
+    Ok(())
+}
+
+#[test]
+fn user_queries_render_as_escaped_fts5() -> Result<(), QueryParseError> {
+    let fts = |input: &str| UserQuery::parse(input).map(|query| query.to_fts5());
+
+    assert_eq!(fts("rust programming")?, "\"rust\" AND \"programming\"");
+    assert_eq!(
+        fts("notes rust OR go")?,
+        "\"notes\" AND (\"rust\" OR \"go\")"
+    );
+    assert_eq!(
+        fts("title:\"Rust Notes\" prog*")?,
+        "title : \"Rust Notes\" AND \"prog\"*"
+    );
+    assert_eq!(
+        fts("rust -draft NOT old")?,
+        "\"rust\" NOT \"draft\" NOT \"old\""
+    );
+    assert_eq!(fts("foo:bar say\"hi")?, "\"foo:bar\" AND \"say\"\"hi\"");
+    assert_eq!(fts("rust ++ AND go")?, "\"rust\" AND \"go\"");
+
+    Ok(())
+}
+
+#[test]
+fn malformed_user_queries_report_a_position() {
+    let error = |input: &str| UserQuery::parse(input).unwrap_err();
+
+    assert_eq!(error("   "), QueryParseError::Empty);
+    assert_eq!(
+        error("rust \"open phrase"),
+        QueryParseError::UnterminatedQuote { position: 5 }
+    );
+    assert_eq!(
+        error("rust -"),
+        QueryParseError::MissingTerm { position: 5 }
+    );
+    assert_eq!(
+        error("title: rust"),
+        QueryParseError::MissingTerm { position: 0 }
+    );
+    assert_eq!(
+        error("OR rust"),
+        QueryParseError::MisplacedOperator {
+            operator: "OR",
+            position: 0
+        }
+    );
+    assert_eq!(
+        error("rust AND"),
+        QueryParseError::MisplacedOperator {
+            operator: "AND",
+            position: 5
+        }
+    );
+    assert_eq!(
+        error("rust OR -go"),
+        QueryParseError::ExclusionInOr { position: 8 }
+    );
+    assert_eq!(
+        error("-draft -old"),
+        QueryParseError::OnlyExclusions { position: 0 }
+    );
+    assert_eq!(error("a \"b").position(), 2);
+}
+
+#[test]
+fn search_accepts_punctuation_and_keeps_advanced_search_raw() -> Result<(), OraError> {
+    let (_tmpdir, index) = indexed_shelf(&[
+        ("C++ Tips", "Templates, don't panic."),
+        ("Rust Notes", "ownership and borrowing"),
+        ("Draft", "rust ideas, not done"),
+    ])?;
+    let query = Query::new(&index);
+    let titles = |results: Vec<ora_core::search::SearchResult>| {
+        let mut titles: Vec<_> = results.into_iter().map(|r| r.note.title).collect();
+        titles.sort();
+        titles
+    };
+
+    assert_eq!(titles(query.search("C++")?), vec!["C++ Tips"]);
+    assert_eq!(titles(query.search("don't")?), vec!["C++ Tips"]);
+    assert!(query.search("foo:bar")?.is_empty());
+    assert_eq!(titles(query.search("rust -done")?), vec!["Rust Notes"]);
+    assert_eq!(
+        titles(query.search("ownership OR templates")?),
+        vec!["C++ Tips", "Rust Notes"]
+    );
+    assert_eq!(titles(query.search_title("rust")?), vec!["Rust Notes"]);
+    assert_eq!(query.count_results("rust OR panic")?, 3);
+
+    assert!(matches!(
+        query.search("\"unbalanced"),
+        Err(OraError::Query(QueryParseError::UnterminatedQuote {
+            position: 0
+        }))
+    ));
+    assert!(matches!(
+        query.advanced_search("\"unbalanced", &SearchOptions::default()),
+        Err(OraError::Db(_))
+    ));
+    assert_eq!(
+        titles(query.advanced_search("NEAR(rust ideas)", &SearchOptions::default())?),
+        vec!["Draft"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn search_filters_apply_to_results_and_counts() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let projects = tmpdir.path().join("projects");
+    std::fs::create_dir_all(projects.join("2024"))?;
+    LocalNote::create("Plan", "rust plan #work", &projects.join("2024"))?;
+    LocalNote::create("Ideas", "rust ideas, a longer note #work/draft", &projects)?;
+    LocalNote::create("Inbox", "rust #home", tmpdir.path())?;
+    let index = Index::new(tmpdir.path())?;
+    let query = Query::new(&index);
+
+    let titles = |options: &SearchOptions| -> Result<Vec<String>, OraError> {
+        let mut titles: Vec<_> = query
+            .search_with_options("rust", options)?
+            .into_iter()
+            .map(|r| r.note.title)
+            .collect();
+        titles.sort();
+        assert_eq!(
+            query.count_results_with_options("rust", options)?,
+            titles.len() as u64
+        );
+        Ok(titles)
+    };
+
+    let in_projects = SearchOptions {
+        folder: Some("projects/".into()),
+        ..Default::default()
+    };
+    assert_eq!(titles(&in_projects)?, vec!["Ideas", "Plan"]);
+    let nested = SearchOptions {
+        folder: Some("projects/2024".into()),
+        ..Default::default()
+    };
+    assert_eq!(titles(&nested)?, vec!["Plan"]);
+    let prefix_only = SearchOptions {
+        folder: Some("proj".into()),
+        ..Default::default()
+    };
+    assert!(titles(&prefix_only)?.is_empty());
+
+    let without_drafts = SearchOptions {
+        tags: vec!["work".into()],
+        exclude_tags: vec!["#work/draft".into()],
+        ..Default::default()
+    };
+    assert_eq!(titles(&without_drafts)?, vec!["Plan"]);
+
+    let short = SearchOptions {
+        min_length: Some(5),
+        max_length: Some(15),
+        ..Default::default()
+    };
+    assert_eq!(titles(&short)?, vec!["Inbox", "Plan"]);
+
+    // Backdate one note to check the time ranges.
+    let conn = Connection::open(tmpdir.path().join(".shelf.db"))?;
+    conn.execute(
+        "UPDATE notes SET created_at = '2020-01-01 00:00:00' WHERE title = ?",
+        params!["Inbox"],
+    )?;
+    let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+    let old = SearchOptions {
+        created: TimeRange::before(an_hour_ago),
+        ..Default::default()
+    };
+    assert_eq!(titles(&old)?, vec!["Inbox"]);
+    let recent = SearchOptions {
+        created: TimeRange::since(an_hour_ago),
+        updated: TimeRange::between(an_hour_ago, SystemTime::now() + Duration::from_secs(3600)),
+        ..Default::default()
+    };
+    assert_eq!(titles(&recent)?, vec!["Ideas", "Plan"]);
+
+    // Pagination counts only filtered notes.
+    let page = SearchOptions {
+        limit: Some(1),
+        offset: Some(1),
+        ..in_projects.clone()
+    };
+    assert_eq!(query.search_with_options("rust", &page)?.len(), 1);
+    assert_eq!(query.count_results_with_options("rust", &page)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn search_results_can_be_sorted() -> Result<(), OraError> {
+    let (tmpdir, index) = indexed_shelf(&[
+        ("b note", "rust rust rust"),
+        ("A note", "rust and a much longer body of text"),
+        ("c note", "some rust"),
+    ])?;
+    let query = Query::new(&index);
+    let titles = |sort_by, sort_direction| -> Result<Vec<String>, OraError> {
+        let options = SearchOptions {
+            sort_by,
+            sort_direction,
+            ..Default::default()
+        };
+        Ok(query
+            .search_with_options("rust", &options)?
+            .into_iter()
+            .map(|r| r.note.title)
+            .collect())
+    };
+
+    assert_eq!(
+        titles(SortBy::Relevance, SortDirection::Ascending)?[0],
+        "b note"
+    );
+    assert_eq!(
+        titles(SortBy::Title, SortDirection::Ascending)?,
+        ["A note", "b note", "c note"]
+    );
+    assert_eq!(
+        titles(SortBy::Path, SortDirection::Descending)?,
+        ["c note", "b note", "A note"]
+    );
+    assert_eq!(
+        titles(SortBy::Size, SortDirection::Descending)?,
+        ["A note", "b note", "c note"]
+    );
+
+    // The most relevant note, last touched long ago, loses to a fresh one.
+    let conn = Connection::open(tmpdir.path().join(".shelf.db"))?;
+    conn.execute(
+        "UPDATE notes SET created_at = '2020-01-01 00:00:00', updated_at = '2020-01-01 00:00:00'
+         WHERE title = ?",
+        params!["b note"],
+    )?;
+    let recency = SortBy::RelevanceWithRecency {
+        half_life: Duration::from_secs(86400),
+    };
+    assert_ne!(titles(recency, SortDirection::Ascending)?[0], "b note");
+    assert_eq!(
+        titles(SortBy::Created, SortDirection::Ascending)?[0],
+        "b note"
+    );
+    assert_eq!(
+        titles(SortBy::Updated, SortDirection::Descending)?
+            .last()
+            .map(String::as_str),
+        Some("b note")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fuzzy_match_prefers_word_starts_and_runs() {
+    let found = fuzzy_match("rsprog", "Rust Programming Notes").unwrap();
+    assert_eq!(found.positions, vec![0, 2, 5, 6, 7, 8]);
+    assert!(fuzzy_match("rsprog", "Python Notes").is_none());
+    assert!(fuzzy_match("RUST", "rust notes").is_none(), "smart case");
+    assert!(
+        fuzzy_match("r n", "Rust Notes").is_some(),
+        "spaces are ignored"
+    );
+
+    let score = |pattern, text| fuzzy_match(pattern, text).unwrap().score;
+    assert!(score("rn", "Rust Notes") > score("rn", "Barn"));
+    assert!(score("note", "Notebook") > score("note", "No tea, thanks"));
+    assert!(score("ts", "ThisSuggestion") > score("ts", "Thistles"));
+    assert_eq!(fuzzy_match("", "Anything").map(|m| m.score), Some(0));
+}
+
+#[test]
+fn fuzzy_titles_rank_matches_with_recency() -> Result<(), OraError> {
+    let (tmpdir, index) = indexed_shelf(&[
+        ("Rust Programming Notes", ""),
+        ("Python Notes", ""),
+        ("Ab", ""),
+        ("Axb", ""),
+        ("100% done_", ""),
+    ])?;
+    let query = Query::new(&index);
+
+    let found = query.fuzzy_titles("rsprog", &FuzzyOptions::default())?;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].title, "Rust Programming Notes");
+    assert_eq!(found[0].positions, vec![0, 2, 5, 6, 7, 8]);
+
+    // LIKE wildcards in the pattern are matched literally.
+    let found = query.fuzzy_titles("0%_", &FuzzyOptions::default())?;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].title, "100% done_");
+
+    let conn = Connection::open(tmpdir.path().join(".shelf.db"))?;
+    conn.execute(
+        "UPDATE notes SET updated_at = '2020-01-01 00:00:00' WHERE title = ?",
+        params!["Ab"],
+    )?;
+    let titles = |options: &FuzzyOptions| -> Result<Vec<String>, OraError> {
+        Ok(query
+            .fuzzy_titles("ab", options)?
+            .into_iter()
+            .map(|m| m.title)
+            .collect())
+    };
+    let no_recency = FuzzyOptions {
+        recency_half_life: None,
+        ..Default::default()
+    };
+    assert_eq!(titles(&no_recency)?, ["Ab", "Axb"]);
+    assert_eq!(titles(&FuzzyOptions::default())?, ["Axb", "Ab"]);
+
+    let limited = FuzzyOptions {
+        limit: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(query.fuzzy_titles("", &limited)?.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn fuzzy_mode_finds_typos_and_substrings() -> Result<(), OraError> {
+    let (_tmpdir, index) = indexed_shelf(&[
+        ("Inbox", "How to receive mail"),
+        ("Typo", "Did you recieve it?"),
+        ("Weather", "Sunny all week"),
+    ])?;
+    let query = Query::new(&index);
+    let fuzzy = SearchOptions {
+        mode: SearchMode::Fuzzy,
+        ..Default::default()
+    };
+    let found = |text: &str, options: &SearchOptions| -> Result<Vec<_>, OraError> {
+        Ok(query
+            .search_with_options(text, options)?
+            .into_iter()
+            .map(|r| (r.note.title, r.matched_by))
+            .collect())
+    };
+
+    assert_eq!(edit_distance("recieve", "receive"), 1);
+    assert_eq!(found("ceiv", &SearchOptions::default())?, []);
+    assert_eq!(
+        found("ceiv", &fuzzy)?,
+        [("Inbox".to_string(), MatchKind::Substring)]
+    );
+
+    // Full-text matches come first, then the fuzzy ones.
+    assert_eq!(
+        found("recieve", &fuzzy)?,
+        [
+            ("Typo".to_string(), MatchKind::FullText),
+            ("Inbox".to_string(), MatchKind::Fuzzy),
+        ]
+    );
+    assert_eq!(query.count_results_with_options("recieve", &fuzzy)?, 2);
+    assert_eq!(query.count_results("recieve")?, 1);
+    assert_eq!(found("recieve -mail", &fuzzy)?.len(), 1);
+    assert_eq!(found("title:recieve", &fuzzy)?, []);
+
+    let second_page = SearchOptions {
+        limit: Some(1),
+        offset: Some(1),
+        ..fuzzy.clone()
+    };
+    let results = query.search_with_options("recieve", &second_page)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].note.title, "Inbox");
+    assert_eq!(results[0].rank, -0.5);
+    assert!(results[0].snippet_content.is_none());
+
+    // Short terms must match exactly.
+    assert_eq!(found("sun", &fuzzy)?.len(), 1);
+    assert_eq!(found("sum", &fuzzy)?, []);
+
+    Ok(())
+}
+
+#[test]
+fn fuzzy_matches_follow_index_changes_and_are_capped() -> Result<(), OraError> {
+    let (tmpdir, index) = indexed_shelf(&[
+        ("Inbox", "How to receive mail"),
+        ("Weather", "Sunny all week"),
+    ])?;
+    let query = Query::new(&index);
+    let fuzzy = SearchOptions {
+        mode: SearchMode::Fuzzy,
+        ..Default::default()
+    };
+
+    assert_eq!(query.search_with_options("ceiv", &fuzzy)?.len(), 1);
+    assert_eq!(query.count_results_with_options("ceiv", &fuzzy)?, 1);
+
+    // Changes from other connections are seen by the next page and count.
+    let conn = Connection::open(tmpdir.path().join(".shelf.db"))?;
+    conn.execute(
+        "UPDATE notes SET content = 'Nothing here' WHERE title = ?",
+        params!["Inbox"],
+    )?;
+    assert_eq!(query.search_with_options("ceiv", &fuzzy)?.len(), 0);
+    assert_eq!(query.count_results_with_options("ceiv", &fuzzy)?, 0);
+
+    // Without the trigram index, only the most recently updated notes are
+    // checked.
+    conn.execute(
+        "UPDATE notes SET content = 'How to receive mail', \
+         updated_at = '2020-01-01 00:00:00' WHERE title = ?",
+        params!["Inbox"],
+    )?;
+    assert_eq!(query.count_results_with_options("ceiv", &fuzzy)?, 1);
+    for i in 0..500 {
+        LocalNote::create(&format!("Filler {i}"), "Nothing here", tmpdir.path())?;
+    }
+    let index = Index::new(tmpdir.path())?;
+    let query = Query::new(&index);
+    assert_eq!(query.search_with_options("ceiv", &fuzzy)?.len(), 0);
+    assert_eq!(query.count_results_with_options("ceiv", &fuzzy)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn trigram_index_follows_config() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    LocalNote::create("Inbox", "How to receive mail", tmpdir.path())?;
+    let config = ShelfConfig {
+        trigram_index: true,
+        ..ShelfConfig::default()
+    };
+    config.save(tmpdir.path())?;
+
+    let has_table = || -> Result<bool, OraError> {
+        let conn = Connection::open(tmpdir.path().join(".shelf.db"))?;
+        Ok(conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'trigrams')",
+            [],
+            |row| row.get(0),
+        )?)
+    };
+    let fuzzy = SearchOptions {
+        mode: SearchMode::Fuzzy,
+        ..Default::default()
+    };
+
+    {
+        let index = Index::new(tmpdir.path())?;
+        assert!(has_table()?);
+        let note = LocalNote::create("Later", "Perceived wisdom", tmpdir.path())?;
+        index.index_note(&note)?;
+
+        let query = Query::new(&index);
+        let titles: Vec<String> = query
+            .search_with_options("recieve", &fuzzy)?
+            .into_iter()
+            .map(|r| r.note.title)
+            .collect();
+        assert_eq!(titles, ["Inbox"]);
+        assert_eq!(query.count_results_with_options("ceiv", &fuzzy)?, 2);
+    }
+
+    ShelfConfig::default().save(tmpdir.path())?;
+    let index = Index::new(tmpdir.path())?;
+    assert!(!has_table()?);
+    let results = Query::new(&index).search_with_options("ceiv", &fuzzy)?;
+    assert_eq!(results.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn folder_filters_cannot_leave_the_shelf() -> Result<(), OraError> {
+    let parent = TempDir::new()?;
+    let shelf = parent.path().join("shelf");
+    let outside = parent.path().join("outside");
+    fs::create_dir(&shelf)?;
+    fs::create_dir(&outside)?;
+    LocalNote::create("Inside", "rust", &shelf)?;
+    let index = Index::new(&shelf)?;
+    let query = Query::new(&index);
+
+    for folder in [
+        outside.clone(),
+        "../outside".into(),
+        "./".into(),
+        "a/../b".into(),
+    ] {
+        let options = SearchOptions {
+            folder: Some(folder),
+            ..Default::default()
+        };
+        assert!(matches!(
+            query.search_with_options("rust", &options),
+            Err(OraError::Note(NoteError::InvalidPath))
+        ));
+        assert!(matches!(
+            query.count_results_with_options("rust", &options),
+            Err(OraError::Note(NoteError::InvalidPath))
+        ));
+    }
+    Ok(())
+}
@@ -1,39 +1,106 @@
-use ora_core::shelf::storage::Shelf;
-use std::fs;
-use uuid::Uuid;
-
-/// Helper: generate a unique name for test shelves so runs don't collide
-fn test_shelf_name(name: &str) -> String {
-    format!("test_{}_{}", name, Uuid::new_v4())
-}
+use ora_core::shelf::library::{Library, SHELVES_DIR_ENV};
+use ora_core::shelf::storage::ShelfError;
+use tempfile::TempDir;
 
 #[test]
 fn creating_new_shelf_should_create_directory() {
-    let name = test_shelf_name("create");
-    let shelf = Shelf::new(&name).expect("Should be able to create shelf");
+    let tmpdir = TempDir::new().unwrap();
+    let library = Library::new(tmpdir.path().join("shelves"));
+    let shelf = library
+        .create_shelf("create")
+        .expect("Should be able to create shelf");
 
-    assert!(shelf.root.exists());
+    assert_eq!(shelf.root, tmpdir.path().join("shelves").join("create"));
     assert!(shelf.root.is_dir());
-
-    // cleanup after test
-    fs::remove_dir_all(shelf.root).unwrap();
+    assert!(matches!(
+        library.create_shelf("create"),
+        Err(ShelfError::AlreadyExists(_))
+    ));
 }
 
 #[test]
 fn opening_nonexistent_shelf_should_fail() {
-    let name = test_shelf_name("open_missing");
-    let result = Shelf::open(&name);
+    let tmpdir = TempDir::new().unwrap();
+    let library = Library::new(tmpdir.path());
+    let result = library.open_shelf("open_missing");
 
-    assert!(result.is_err());
+    assert!(matches!(result, Err(ShelfError::NotFound(_))));
 }
 
 #[test]
 fn ensure_will_create_if_missing() {
-    let name = test_shelf_name("ensure");
-    let shelf = Shelf::ensure_exists(&name).expect("ensure should create shelf");
+    let tmpdir = TempDir::new().unwrap();
+    let library = Library::new(tmpdir.path());
+    let shelf = library
+        .ensure_shelf("ensure")
+        .expect("ensure should create shelf");
 
     assert!(shelf.root.exists());
+    assert_eq!(library.ensure_shelf("ensure").unwrap().root, shelf.root);
+}
+
+#[test]
+fn shelves_are_listed_and_renamed_within_their_library() {
+    let tmpdir = TempDir::new().unwrap();
+    let library = Library::new(tmpdir.path());
+    library.create_shelf("work").unwrap();
+    let mut shelf = library.create_shelf("personal").unwrap();
+
+    let mut names = library.list_shelves().unwrap();
+    names.sort();
+    assert_eq!(names, vec!["personal", "work"]);
+
+    assert!(matches!(
+        shelf.rename("work"),
+        Err(ShelfError::AlreadyExists(_))
+    ));
+    shelf.rename("private").unwrap();
+    assert_eq!(shelf.root, tmpdir.path().join("private"));
+    assert_eq!(shelf.library().unwrap(), library);
+    assert!(library.open_shelf("private").is_ok());
+    assert!(library.open_shelf("personal").is_err());
+}
+
+#[test]
+fn hidden_shelf_names_are_rejected() {
+    let tmpdir = TempDir::new().unwrap();
+    let library = Library::new(tmpdir.path());
+    let mut shelf = library.create_shelf("work").unwrap();
+
+    for name in [".trash", ".hidden", ".", "..", " .padded"] {
+        assert!(
+            matches!(library.create_shelf(name), Err(ShelfError::InvalidInput)),
+            "{name:?}"
+        );
+        assert!(
+            matches!(library.ensure_shelf(name), Err(ShelfError::InvalidInput)),
+            "{name:?}"
+        );
+        assert!(
+            matches!(shelf.rename(name), Err(ShelfError::InvalidInput)),
+            "{name:?}"
+        );
+    }
+    assert!(!tmpdir.path().join(".trash").exists());
+    assert!(!tmpdir.path().join(".hidden").exists());
+    assert_eq!(library.list_shelves().unwrap(), vec!["work"]);
+}
+
+#[test]
+fn default_library_honors_environment_variable() {
+    let tmpdir = TempDir::new().unwrap();
+    let lookup = |name: &str| (name == SHELVES_DIR_ENV).then(|| tmpdir.path().into());
+
+    let library = Library::locate_with(lookup).unwrap();
+    assert_eq!(library.root(), tmpdir.path());
+    let shelf = library.create_shelf("from_env").unwrap();
+    assert_eq!(shelf.root, tmpdir.path().join("from_env"));
+    assert_eq!(library.list_shelves().unwrap(), vec!["from_env"]);
 
-    // cleanup
-    fs::remove_dir_all(shelf.root).unwrap();
+    // An empty variable falls back to the default location.
+    if let Ok(default) = Library::default_location() {
+        let empty = Library::locate_with(|_| Some("".into())).unwrap();
+        assert_eq!(empty, default);
+        assert_eq!(Library::locate_with(|_| None).unwrap(), default);
+    }
 }
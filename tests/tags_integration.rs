@@ -0,0 +1,39 @@
+use ora_core::tags::{extract_tags, normalize_tag};
+
+#[test]
+fn tags_come_from_front_matter_and_body() {
+    let content =
+        "---\ntags: [Rust, \"#notes\"]\n---\n# Heading\n\nWorking on #project/ora and #Ideas.\n";
+    assert_eq!(
+        extract_tags(content),
+        vec!["ideas", "notes", "project/ora", "rust"]
+    );
+}
+
+#[test]
+fn scalar_tag_lists_are_split() {
+    let content = "---\ntags: alpha, beta gamma\n---\nbody";
+    assert_eq!(extract_tags(content), vec!["alpha", "beta", "gamma"]);
+}
+
+#[test]
+fn code_headings_and_numbers_are_not_tags() {
+    let content = "## Section\n\
+        Issue #123 is fixed, see C#sharp and a#b.\n\
+        Inline `#code` is skipped.\n\
+        ```\n#fenced\n```\n\
+        But #real/tag/ counts.\n";
+    assert_eq!(extract_tags(content), vec!["real/tag"]);
+}
+
+#[test]
+fn normalize_rejects_invalid_tags() {
+    assert_eq!(
+        normalize_tag("#Project/Ora"),
+        Some("project/ora".to_string())
+    );
+    assert_eq!(normalize_tag("#2024"), None);
+    assert_eq!(normalize_tag("has space"), None);
+    assert_eq!(normalize_tag("a//b"), None);
+    assert_eq!(normalize_tag("#"), None);
+}
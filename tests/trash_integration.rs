@@ -0,0 +1,205 @@
+use ora_core::domain::LocalNote;
+use ora_core::error::OraError;
+use ora_core::shelf::config::ShelfConfig;
+use ora_core::shelf::library::Library;
+use ora_core::shelf::manager::ShelfManager;
+use ora_core::shelf::storage::{Shelf, ShelfError};
+use ora_core::shelf::trash::{TRASH_DIR, Trash};
+use ora_core::watcher::index::Index;
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+#[test]
+fn trash_puts_lists_and_restores_with_conflicts() -> Result<(), ShelfError> {
+    let tmpdir = TempDir::new()?;
+    let root = tmpdir.path();
+    fs::create_dir_all(root.join("notes"))?;
+    fs::write(root.join("notes/Plan.md"), "first")?;
+    fs::write(root.join("notes/image.png"), "png")?;
+
+    let trash = Trash::new(root);
+    assert!(trash.list()?.is_empty());
+
+    let before = SystemTime::now() - Duration::from_secs(1);
+    let plan = trash.put(&root.join("notes/Plan.md"))?;
+    assert_eq!(plan.original_path, root.join("notes/Plan.md"));
+    assert!(plan.path.starts_with(root.join(TRASH_DIR)));
+    assert!(plan.deleted_at >= before && !plan.is_dir);
+    assert!(!root.join("notes/Plan.md").exists());
+
+    let folder = trash.put(&root.join("notes"))?;
+    assert!(folder.is_dir);
+    let listed = trash.list()?;
+    assert_eq!(listed.len(), 2);
+    assert!(listed.contains(&plan) && listed.contains(&folder));
+
+    assert!(matches!(
+        trash.put(&root.join(TRASH_DIR)),
+        Err(ShelfError::InvalidInput)
+    ));
+    assert!(matches!(trash.put(root), Err(ShelfError::InvalidInput)));
+
+    // The folder comes back first, then the note conflicts with a new one.
+    assert_eq!(trash.restore(&folder.id)?, root.join("notes"));
+    assert!(root.join("notes/image.png").exists());
+    fs::write(root.join("notes/Plan.md"), "second")?;
+    let restored = trash.restore(&plan.id)?;
+    assert_eq!(restored, root.join("notes/Plan 1.md"));
+    assert_eq!(fs::read_to_string(restored)?, "first");
+    assert_eq!(fs::read_to_string(root.join("notes/Plan.md"))?, "second");
+
+    assert!(trash.list()?.is_empty());
+    assert!(matches!(
+        trash.restore(&plan.id),
+        Err(ShelfError::TrashItemNotFound(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn info_files_cannot_restore_outside_the_owner() -> Result<(), ShelfError> {
+    let tmpdir = TempDir::new()?;
+    let root = tmpdir.path().join("shelf");
+    fs::create_dir_all(&root)?;
+    fs::write(root.join("Plan.md"), "plan")?;
+
+    let trash = Trash::new(&root);
+    let item = trash.put(&root.join("Plan.md"))?;
+    let info = root
+        .join(TRASH_DIR)
+        .join("info")
+        .join(format!("{}.trashinfo", item.id));
+    let outside = tmpdir.path().join("Escaped.md");
+    for path in ["../Escaped.md", "/tmp/Escaped.md", ""] {
+        fs::write(&info, format!("path={path}\ndeleted=0\n"))?;
+        assert!(trash.list()?.is_empty(), "{path:?}");
+        assert!(matches!(
+            trash.restore(&item.id),
+            Err(ShelfError::TrashItemNotFound(_))
+        ));
+    }
+    assert!(!outside.exists());
+
+    Ok(())
+}
+
+#[test]
+fn local_notes_are_deleted_into_their_shelf_trash() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let root = tmpdir.path();
+    ShelfConfig::default().save(root)?;
+    fs::create_dir(root.join("projects"))?;
+    let note = LocalNote::create("Plan", "plan", &root.join("projects"))?;
+
+    let item = note.delete()?;
+    assert!(!note.path.exists());
+    assert!(item.path.starts_with(root.join(TRASH_DIR)));
+    assert_eq!(Trash::new(root).restore(&item.id)?, note.path);
+
+    note.delete_permanently()?;
+    assert!(!note.path.exists());
+    assert!(Trash::new(root).list()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn trash_purges_by_retention_and_empties() -> Result<(), ShelfError> {
+    let tmpdir = TempDir::new()?;
+    let root = tmpdir.path();
+    for name in ["a.md", "b.md", "c.md"] {
+        fs::write(root.join(name), name)?;
+    }
+    let trash = Trash::new(root);
+    let a = trash.put(&root.join("a.md"))?;
+    trash.put(&root.join("b.md"))?;
+    trash.put(&root.join("c.md"))?;
+
+    assert!(
+        trash
+            .purge_older_than(Duration::from_secs(86_400))?
+            .is_empty()
+    );
+    assert_eq!(trash.list()?.len(), 3);
+
+    let removed = trash.remove(&a.id)?;
+    assert_eq!(removed.original_path, root.join("a.md"));
+    assert!(!removed.path.exists());
+
+    sleep_past_second();
+    assert_eq!(trash.purge_older_than(Duration::ZERO)?.len(), 2);
+    assert!(trash.list()?.is_empty());
+
+    fs::write(root.join("d.md"), "d")?;
+    trash.put(&root.join("d.md"))?;
+    assert_eq!(trash.empty()?.len(), 1);
+    assert!(trash.list()?.is_empty());
+    Ok(())
+}
+
+/// Deletion times have second precision.
+fn sleep_past_second() {
+    std::thread::sleep(Duration::from_millis(1100));
+}
+
+#[test]
+fn manager_deletes_into_trash_and_restores_into_index() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "trashy".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let manager = ShelfManager::with_index(&shelf, Index::new(&shelf.root)?);
+    let index = manager.index().unwrap().clone();
+
+    let note = manager.create_note("Plan", "---\nid: plan-1\n---\nthe plan")?;
+    manager.create_note("work/Task", "task")?;
+    let item = manager.delete_note("Plan")?;
+    assert!(!note.path.exists());
+    assert!(index.get_by_id("plan-1")?.is_none());
+
+    let folder = manager.delete_folder("work", true)?;
+    assert!(index.paths_under(&shelf.root.join("work"))?.is_empty());
+    assert_eq!(manager.trash().list()?.len(), 2);
+
+    // Nothing in the trash is indexed or listed.
+    let (reopened, report) = Index::new_with_report(&shelf.root)?;
+    assert!(report.added.is_empty(), "{report:?}");
+    assert!(reopened.get_by_id("plan-1")?.is_none());
+    assert!(manager.list_notes_in("", true)?.is_empty());
+
+    manager.create_note("Plan", "replacement")?;
+    let restored = manager.restore(&item.id)?;
+    assert_eq!(restored, shelf.root.join("Plan 1.md"));
+    assert_eq!(index.get_by_id("plan-1")?.unwrap().path, restored);
+
+    manager.restore(&folder.id)?;
+    assert!(
+        index
+            .get_by_path(&shelf.root.join("work/Task.md"))?
+            .is_some()
+    );
+    assert!(manager.trash().list()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn deleted_shelves_go_to_the_library_trash() -> Result<(), ShelfError> {
+    let tmpdir = TempDir::new()?;
+    let library = Library::new(tmpdir.path());
+    let shelf = library.create_shelf("old")?;
+    fs::write(shelf.root.join("Note.md"), "kept")?;
+
+    let item = shelf.delete_shelf()?;
+    assert!(!shelf.root.exists());
+    assert!(library.list_shelves()?.is_empty());
+    assert_eq!(library.trash().list()?, vec![item.clone()]);
+
+    library.trash().restore(&item.id)?;
+    assert_eq!(library.list_shelves()?, vec!["old"]);
+    let reopened = library.open_shelf("old")?;
+    assert_eq!(fs::read_to_string(reopened.root.join("Note.md"))?, "kept");
+    Ok(())
+}
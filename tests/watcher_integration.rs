@@ -1,25 +1,27 @@
+use ora_core::domain::LocalNote;
 use ora_core::error::OraError;
+use ora_core::history::RevisionSource;
+use ora_core::shelf::config::ShelfConfig;
+use ora_core::shelf::manager::ShelfManager;
+use ora_core::shelf::storage::{Shelf, ShelfError};
+use ora_core::watcher::debounce::ShutdownMode;
+use ora_core::watcher::event::{DirWatcher, FsEvent};
 use ora_core::watcher::index::Index;
 use ora_core::watcher::service::WatcherService;
+use ora_core::watcher::subscription::{NoteEvent, Subscribers};
 use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 
 #[test]
 fn watcher_full_integration_test() -> Result<(), OraError> {
-    // Use Documents folder for real-world testing
-    let docs_dir = dirs::document_dir()
-        .ok_or_else(|| OraError::Other("Cannot find Documents directory".to_string()))?;
-    let test_shelf = docs_dir.join("ora_test_shelf");
-
-    // Clean up any existing test shelf
-    if test_shelf.exists() {
-        fs::remove_dir_all(&test_shelf)?;
-    }
-
-    fs::create_dir_all(&test_shelf)?;
-    let shelf_path = test_shelf;
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
 
     println!("Starting watcher service...");
 
@@ -163,7 +165,7 @@ fn watcher_full_integration_test() -> Result<(), OraError> {
     for i in 0..5 {
         fs::write(
             &debounce_path,
-            &format!("# Rapid Change {}\nContent version {}", i, i),
+            format!("# Rapid Change {}\nContent version {}", i, i),
         )?;
         thread::sleep(Duration::from_millis(20)); // Faster than debounce time
     }
@@ -196,7 +198,7 @@ fn watcher_full_integration_test() -> Result<(), OraError> {
             let note_path = shelf_clone.join(format!("Concurrent Note {}.md", i));
             fs::write(
                 &note_path,
-                &format!("# Concurrent Note {}\nContent for note {}", i, i),
+                format!("# Concurrent Note {}\nContent for note {}", i, i),
             )
             .unwrap();
         });
@@ -229,17 +231,6 @@ fn watcher_full_integration_test() -> Result<(), OraError> {
     let shutdown_duration = shutdown_start.elapsed();
     println!("Watcher shutdown completed in {:?}", shutdown_duration);
 
-    thread::sleep(Duration::from_millis(1000));
-
-    // Clean up test shelf
-    println!("About to clean up test shelf...");
-    let cleanup_start = std::time::Instant::now();
-    if shelf_path.exists() {
-        fs::remove_dir_all(&shelf_path)?;
-    }
-    let cleanup_duration = cleanup_start.elapsed();
-    println!("Test shelf cleanup completed in {:?}", cleanup_duration);
-
     println!("🎉 All watcher tests completed successfully!");
     println!("✅ File creation detection");
     println!("✅ File modification detection");
@@ -260,10 +251,602 @@ fn watcher_service_creates_successfully() -> Result<(), OraError> {
     // Create watcher service
     let mut service = WatcherService::create(&shelf_path, Duration::from_millis(100))?;
 
-    // Verify service was created (no panic)
-    assert!(true, "Watcher service should create successfully");
-
     service.shutdown()?;
     thread::sleep(Duration::from_millis(1000));
     Ok(())
 }
+
+#[test]
+fn watcher_service_reports_invalid_config() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    fs::write(tmpdir.path().join(".shelf.toml"), "ignore = 3\n")?;
+
+    assert!(matches!(
+        WatcherService::create(tmpdir.path(), Duration::from_millis(100)),
+        Err(OraError::Shelf(ShelfError::InvalidConfig { line: 1, .. }))
+    ));
+    Ok(())
+}
+
+#[test]
+fn watcher_renames_keep_index_rows() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+    let original = shelf_path.join("Original.md");
+    fs::write(&original, "Links to [[Other]].")?;
+    fs::create_dir(shelf_path.join("folder"))?;
+    let nested = shelf_path.join("folder").join("Nested.md");
+    fs::write(&nested, "Nested note")?;
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    let index = service.get_index();
+    let id = index.get_by_path(&original)?.unwrap().id;
+    let nested_id = index.get_by_path(&nested)?.unwrap().id;
+
+    service.run()?;
+    thread::sleep(Duration::from_millis(200));
+
+    let renamed = shelf_path.join("Renamed.md");
+    fs::rename(&original, &renamed)?;
+    let moved_dir = shelf_path.join("moved");
+    fs::rename(shelf_path.join("folder"), &moved_dir)?;
+    thread::sleep(Duration::from_millis(500));
+
+    assert!(index.get_by_path(&original)?.is_none());
+    let note = index.get_by_path(&renamed)?.expect("renamed note indexed");
+    assert_eq!(note.id, id);
+    assert_eq!(note.title, "Renamed");
+    assert_eq!(index.outgoing_links(&renamed)?.len(), 1);
+
+    let moved = moved_dir.join("Nested.md");
+    assert!(index.get_by_path(&nested)?.is_none());
+    assert_eq!(index.get_by_path(&moved)?.unwrap().id, nested_id);
+
+    // Moving a note out of the shelf removes it.
+    let outside = TempDir::new()?;
+    fs::rename(&renamed, outside.path().join("Gone.md"))?;
+    thread::sleep(Duration::from_millis(500));
+    assert!(index.get_by_id(&id)?.is_none());
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn pending_renames_are_not_replaced_by_later_events() {
+    let rename = FsEvent::Rename {
+        from: PathBuf::from("a.md"),
+        to: PathBuf::from("b.md"),
+    };
+
+    let merged = rename.clone().merge(FsEvent::Modify(PathBuf::from("b.md")));
+    assert_eq!(merged, rename);
+
+    let merged = FsEvent::Create(PathBuf::from("b.md")).merge(rename.clone());
+    assert_eq!(merged, rename);
+
+    let merged = FsEvent::Create(PathBuf::from("b.md")).merge(FsEvent::Remove("b.md".into()));
+    assert_eq!(merged, FsEvent::Remove(PathBuf::from("b.md")));
+}
+
+#[test]
+fn shutdown_flushes_or_discards_pending_events() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+
+    // A debounce far longer than the test, so events stay pending.
+    let mut service = WatcherService::create(&shelf_path, Duration::from_secs(30))?;
+    let index = service.get_index();
+
+    service.run()?;
+    assert!(service.is_running());
+    assert!(service.run().is_err(), "running twice should fail");
+
+    let flushed = shelf_path.join("Flushed.md");
+    fs::write(&flushed, "pending at shutdown")?;
+    thread::sleep(Duration::from_millis(200));
+    assert!(index.get_by_path(&flushed)?.is_none());
+
+    let started = Instant::now();
+    service.shutdown()?;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(!service.is_running());
+    assert!(index.get_by_path(&flushed)?.is_some());
+
+    // Restart and discard instead.
+    service.run()?;
+    let discarded = shelf_path.join("Discarded.md");
+    fs::write(&discarded, "dropped at shutdown")?;
+    thread::sleep(Duration::from_millis(200));
+    service.shutdown_with(ShutdownMode::Discard, Duration::from_secs(5))?;
+    assert!(index.get_by_path(&discarded)?.is_none());
+
+    // Shutting down a stopped service is a no-op.
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn shutdown_flushes_unpaired_rename_halves() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let outside = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+    let note = shelf_path.join("Leaving.md");
+    fs::write(&note, "moved out of the shelf")?;
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_secs(30))?;
+    let index = service.get_index();
+    assert!(index.get_by_path(&note)?.is_some());
+    service.run()?;
+    thread::sleep(Duration::from_millis(200));
+
+    // Shut down while the old path still waits for its new half.
+    fs::rename(&note, outside.path().join("Leaving.md"))?;
+    thread::sleep(Duration::from_millis(20));
+    service.shutdown()?;
+
+    assert!(index.get_by_path(&note)?.is_none());
+    Ok(())
+}
+
+#[test]
+fn restarted_service_keeps_indexing_and_drop_stops_it() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    let index = service.get_index();
+    service.run()?;
+    service.shutdown()?;
+    service.run()?;
+    thread::sleep(Duration::from_millis(100));
+
+    let note = shelf_path.join("After Restart.md");
+    fs::write(&note, "indexed by the restarted watcher")?;
+    thread::sleep(Duration::from_millis(500));
+    assert!(index.get_by_path(&note)?.is_some());
+
+    let started = Instant::now();
+    drop(service);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    Ok(())
+}
+
+#[test]
+fn subscribers_receive_note_events() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    let first = service.subscribe();
+    let second = service.subscribe();
+    let counted = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&counted);
+    service.on_event(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    service.run()?;
+    thread::sleep(Duration::from_millis(100));
+
+    let note = shelf_path.join("Evented.md");
+    fs::write(&note, "first")?;
+    let created = first.recv_timeout(Duration::from_secs(2)).unwrap();
+    let NoteEvent::NoteCreated { path, id } = created else {
+        panic!("expected NoteCreated, got {created:?}");
+    };
+    assert_eq!(path, note);
+    // The index is already updated when the event arrives.
+    assert_eq!(service.get_index().get_by_path(&note)?.unwrap().id, id);
+
+    fs::write(&note, "second")?;
+    assert_eq!(
+        first.recv_timeout(Duration::from_secs(2)).unwrap(),
+        NoteEvent::NoteModified {
+            path: note.clone(),
+            id: id.clone()
+        }
+    );
+
+    let renamed = shelf_path.join("Renamed.md");
+    fs::rename(&note, &renamed)?;
+    assert_eq!(
+        first.recv_timeout(Duration::from_secs(2)).unwrap(),
+        NoteEvent::NoteRenamed {
+            from: note.clone(),
+            to: renamed.clone(),
+            id: id.clone()
+        }
+    );
+
+    fs::remove_file(&renamed)?;
+    assert_eq!(
+        first.recv_timeout(Duration::from_secs(2)).unwrap(),
+        NoteEvent::NoteRemoved {
+            path: renamed.clone(),
+            id
+        }
+    );
+
+    service.shutdown()?;
+    assert_eq!(second.try_iter().count(), 4);
+    assert_eq!(counted.load(Ordering::SeqCst), 4);
+
+    // Dropped receivers are unsubscribed on the next event.
+    drop(first);
+    drop(second);
+    service.run()?;
+    fs::write(shelf_path.join("Later.md"), "later")?;
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(service.subscribers().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn manager_writes_publish_once_and_echoes_are_skipped() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "echo".to_string(),
+        config: ShelfConfig::default(),
+    };
+
+    let mut service = WatcherService::create(&shelf.root, Duration::from_millis(50))?;
+    let events = service.subscribe();
+    service.run()?;
+    thread::sleep(Duration::from_millis(100));
+
+    let manager = ShelfManager::with_watcher(&shelf, &service);
+    let settle = || thread::sleep(Duration::from_millis(400));
+
+    let note = manager.create_note("Echo", "v1")?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(received.len(), 1, "{received:?}");
+    let NoteEvent::NoteCreated { id, .. } = &received[0] else {
+        panic!("expected NoteCreated, got {received:?}");
+    };
+    let id = id.clone();
+
+    manager.update_note("Echo", None, Some("v2"))?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![NoteEvent::NoteModified {
+            path: note.path.clone(),
+            id: id.clone()
+        }]
+    );
+
+    let renamed = manager.update_note("Echo", Some("Echoed"), None)?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![NoteEvent::NoteRenamed {
+            from: note.path.clone(),
+            to: renamed.path.clone(),
+            id: id.clone()
+        }]
+    );
+
+    manager.delete_note("Echoed")?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![NoteEvent::NoteRemoved {
+            path: renamed.path.clone(),
+            id
+        }]
+    );
+
+    // Changes made outside the library are still reported.
+    fs::write(shelf.root.join("External.md"), "outside")?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert!(matches!(received[..], [NoteEvent::NoteCreated { .. }]));
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn local_note_saves_through_the_watcher_publish_once() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let dir = tmpdir.path().to_path_buf();
+    let mut note = LocalNote::create("Draft", "v1", &dir)?;
+
+    let mut service = WatcherService::create(&dir, Duration::from_millis(50))?;
+    let events = service.subscribe();
+    service.run()?;
+    thread::sleep(Duration::from_millis(100));
+    let settle = || thread::sleep(Duration::from_millis(400));
+    let id = service.get_index().get_by_path(&note.path)?.unwrap().id;
+
+    note = note.with_content("v2");
+    note.save_with(&service)?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![NoteEvent::NoteModified {
+            path: note.path.clone(),
+            id: id.clone()
+        }]
+    );
+
+    let old_path = note.path.clone();
+    note.save_as_with("Final", &service)?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![NoteEvent::NoteRenamed {
+            from: old_path,
+            to: note.path.clone(),
+            id: id.clone()
+        }]
+    );
+
+    // A plain save is not known to the watcher and reported as external.
+    note = note.with_content("v3");
+    note.save()?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![NoteEvent::NoteModified {
+            path: note.path.clone(),
+            id
+        }]
+    );
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn watcher_skips_paths_ignored_by_shelf_config() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+    fs::create_dir(shelf_path.join("drafts"))?;
+    ShelfConfig {
+        ignore: vec!["drafts/".into()],
+        ..ShelfConfig::default()
+    }
+    .save(&shelf_path)?;
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    assert_eq!(service.config().ignore, vec!["drafts/"]);
+    let index = service.get_index();
+    service.run()?;
+    thread::sleep(Duration::from_millis(200));
+
+    let draft = shelf_path.join("drafts").join("Draft.md");
+    let note = shelf_path.join("Note.md");
+    fs::write(&draft, "Not ready")?;
+    fs::write(&note, "Ready")?;
+    thread::sleep(Duration::from_millis(500));
+    assert!(index.get_by_path(&draft)?.is_none());
+    assert!(index.get_by_path(&note)?.is_some());
+
+    // Moving into an ignored directory removes, moving out indexes.
+    let hidden_away = shelf_path.join("drafts").join("Note.md");
+    fs::rename(&note, &hidden_away)?;
+    let published = shelf_path.join("Draft.md");
+    fs::rename(&draft, &published)?;
+    thread::sleep(Duration::from_millis(500));
+    assert!(index.get_by_path(&note)?.is_none());
+    assert!(index.get_by_path(&hidden_away)?.is_none());
+    assert!(index.get_by_path(&published)?.is_some());
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn editing_ignore_file_reloads_rules() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+    fs::create_dir(shelf_path.join(".obsidian"))?;
+    let note = shelf_path.join("Scratch.md");
+    fs::write(&note, "scratch")?;
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    let index = service.get_index();
+    let id = index.get_by_path(&note)?.unwrap().id;
+    let events = service.subscribe();
+    service.run()?;
+    thread::sleep(Duration::from_millis(200));
+
+    let hidden = shelf_path.join(".obsidian").join("Workspace.md");
+    fs::write(&hidden, "editor state")?;
+    fs::write(shelf_path.join(".oraignore"), "Scratch.md\n")?;
+    thread::sleep(Duration::from_millis(500));
+
+    assert!(index.get_by_path(&hidden)?.is_none());
+    assert!(index.get_by_path(&note)?.is_none());
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        vec![NoteEvent::NoteRemoved {
+            path: note.clone(),
+            id
+        }]
+    );
+
+    fs::remove_file(shelf_path.join(".oraignore"))?;
+    thread::sleep(Duration::from_millis(500));
+    assert!(index.get_by_path(&note)?.is_some());
+    assert!(matches!(
+        events.try_iter().collect::<Vec<_>>().as_slice(),
+        [NoteEvent::NoteCreated { path, .. }] if *path == note
+    ));
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn only_included_directories_are_watched() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let root = tmpdir.path().to_path_buf();
+    for dir in ["notes/deep", "vendor/pkg"] {
+        fs::create_dir_all(root.join(dir))?;
+    }
+
+    let (tx, rx) = channel::<FsEvent>();
+    let mut watcher = DirWatcher::new(tx, |_| true)?;
+    let vendor = root.join("vendor");
+    watcher.sync(&root, &|dir| !dir.starts_with(&vendor))?;
+    let settle = || thread::sleep(Duration::from_millis(200));
+    let changed = || -> Vec<PathBuf> { rx.try_iter().map(|e| e.path().to_path_buf()).collect() };
+
+    fs::write(root.join("notes/deep/Note.md"), "seen")?;
+    fs::write(root.join("vendor/pkg/Readme.md"), "not seen")?;
+    settle();
+    let paths = changed();
+    assert!(
+        paths.contains(&root.join("notes/deep/Note.md")),
+        "{paths:?}"
+    );
+    assert!(!paths.iter().any(|p| p.starts_with(&vendor)), "{paths:?}");
+
+    // Including the directory later adds its watches.
+    watcher.sync(&root, &|_| true)?;
+    fs::write(root.join("vendor/pkg/Changelog.md"), "seen now")?;
+    settle();
+    assert!(changed().contains(&root.join("vendor/pkg/Changelog.md")));
+
+    watcher.unwatch_tree(&root.join("notes"));
+    fs::write(root.join("notes/deep/Other.md"), "not seen")?;
+    settle();
+    assert!(!changed().iter().any(|p| p.starts_with(root.join("notes"))));
+
+    Ok(())
+}
+
+#[test]
+fn unignored_directories_are_watched_again() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+    fs::create_dir(shelf_path.join("drafts"))?;
+    fs::write(shelf_path.join(".oraignore"), "drafts/\n")?;
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    let index = service.get_index();
+    service.run()?;
+    thread::sleep(Duration::from_millis(200));
+
+    fs::remove_file(shelf_path.join(".oraignore"))?;
+    thread::sleep(Duration::from_millis(500));
+    let draft = shelf_path.join("drafts").join("Draft.md");
+    fs::write(&draft, "written after the rules changed")?;
+    thread::sleep(Duration::from_millis(500));
+    assert!(index.get_by_path(&draft)?.is_some());
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn trashed_and_restored_folders_are_reported_once() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf = Shelf {
+        root: tmpdir.path().to_path_buf(),
+        name: "trash".to_string(),
+        config: ShelfConfig::default(),
+    };
+    let mut service = WatcherService::create(&shelf.root, Duration::from_millis(50))?;
+    let index = service.get_index();
+    let events = service.subscribe();
+    service.run()?;
+    thread::sleep(Duration::from_millis(100));
+
+    let manager = ShelfManager::with_watcher(&shelf, &service);
+    let settle = || thread::sleep(Duration::from_millis(400));
+    let note = manager.create_note("work/Task", "task")?;
+    settle();
+    let _ = events.try_iter().count();
+
+    let item = manager.delete_folder("work", true)?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert!(
+        matches!(&received[..], [NoteEvent::NoteRemoved { path, .. }] if *path == note.path),
+        "{received:?}"
+    );
+    assert!(index.paths_under(&shelf.root)?.is_empty());
+
+    manager.restore(&item.id)?;
+    settle();
+    let received: Vec<_> = events.try_iter().collect();
+    assert!(
+        matches!(&received[..], [NoteEvent::NoteCreated { path, .. }] if *path == note.path),
+        "{received:?}"
+    );
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn watcher_records_external_revisions() -> Result<(), OraError> {
+    let tmpdir = TempDir::new()?;
+    let shelf_path = tmpdir.path().to_path_buf();
+    ShelfConfig {
+        history: true,
+        ..ShelfConfig::default()
+    }
+    .save(&shelf_path)?;
+
+    let mut service = WatcherService::create(&shelf_path, Duration::from_millis(50))?;
+    let index = service.get_index();
+    service.run()?;
+    thread::sleep(Duration::from_millis(200));
+
+    let note = shelf_path.join("Note.md");
+    fs::write(&note, "first")?;
+    thread::sleep(Duration::from_millis(500));
+    fs::write(&note, "second")?;
+    thread::sleep(Duration::from_millis(500));
+    let moved = shelf_path.join("Moved.md");
+    fs::rename(&note, &moved)?;
+    thread::sleep(Duration::from_millis(500));
+
+    let history = index.history().expect("history is enabled");
+    let contents: Vec<_> = history
+        .revisions(&moved)?
+        .into_iter()
+        .map(|r| (r.content, r.source))
+        .collect();
+    assert_eq!(
+        contents,
+        vec![
+            ("second".to_string(), RevisionSource::External),
+            ("first".to_string(), RevisionSource::External),
+        ]
+    );
+
+    service.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn callbacks_run_without_the_subscriber_list_locked() {
+    let subscribers = Subscribers::new();
+    let registry = subscribers.clone();
+    subscribers.on_event(move |event| {
+        // Would deadlock if the list were still locked.
+        if registry.len() == 1 {
+            registry.on_event(|_| {});
+            registry.publish(event.clone());
+        }
+    });
+
+    subscribers.publish(NoteEvent::NoteRemoved {
+        path: PathBuf::from("Gone.md"),
+        id: "gone".into(),
+    });
+    assert_eq!(subscribers.len(), 2);
+}